@@ -0,0 +1,57 @@
+// Executed commands in atuin's shell history (`atuin = true` in config.toml), so a command
+// run with --exec shows up next to the ones typed at the prompt, with its start time,
+// duration, directory, and exit status. This uses the same `atuin history start`/`end`
+// calls as atuin's own shell hooks.
+use std::process::Command;
+
+use crate::config::config;
+use crate::env::which;
+
+// An entry that atuin has started; `end` completes it.
+pub struct Entry {
+    id: String,
+    session: Option<String>,
+}
+
+// Start an atuin entry for `command`, just before it runs. None when the sink is off or
+// atuin could not record it; a run is never held up over its history.
+pub fn start(command: &str) -> Option<Entry> {
+    if !config().atuin {
+        return None;
+    }
+    if which("atuin").is_none() {
+        eprintln!("atuin = true is set in config.toml, but atuin is not on PATH; not recording.");
+        return None;
+    }
+    // atuin groups history by shell session. Outside a shell with atuin's hooks there is
+    // none, so the entry gets a session of its own.
+    let session = match std::env::var("ATUIN_SESSION") {
+        Ok(s) if !s.is_empty() => None,
+        _ => Some(atuin(&["uuid"], None)?),
+    };
+    let id = atuin(&["history", "start", "--", command], session.as_deref())?;
+    Some(Entry { id, session })
+}
+
+pub fn end(entry: Entry, exit_code: Option<i32>) {
+    // Killed by a signal: report it the way shells do for their own history.
+    let exit = exit_code.unwrap_or(128).to_string();
+    let args = ["history", "end", "--exit", &exit, "--", &entry.id];
+    if atuin(&args, entry.session.as_deref()).is_none() {
+        eprintln!("Could not record the command in atuin.");
+    }
+}
+
+// Run atuin and return its trimmed stdout, or None if it fails.
+fn atuin(args: &[&str], session: Option<&str>) -> Option<String> {
+    let mut cmd = Command::new("atuin");
+    cmd.args(args).stdin(std::process::Stdio::null());
+    if let Some(s) = session {
+        cmd.env("ATUIN_SESSION", s);
+    }
+    let out = cmd.output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
@@ -0,0 +1,128 @@
+// Append-only log of every outbound API call, kept apart from history. Prompts are
+// never stored; only a SHA-256 of the messages sent, so entries can be matched
+// against a known prompt without revealing it.
+use anyhow::Result;
+use chrono::Utc;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+use crate::history::append_line;
+use crate::llm::OpenRouterResponse;
+use crate::paths::state_dir;
+
+#[derive(Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub url: String,
+    // Last characters of the API key used (see llm::key_label)
+    pub key: Option<String>,
+    pub model: String,
+    pub provider: Option<String>,
+    pub prompt_sha256: String,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    pub cost: Option<f64>,
+    pub status: String,
+}
+
+pub fn audit_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("audit.jsonl"))
+}
+
+pub fn record(url: &str, key: &str, body: &serde_json::Value, result: &Result<OpenRouterResponse>) {
+    let messages = body
+        .get("messages")
+        .map(|m| m.to_string())
+        .unwrap_or_default();
+    let hash = Sha256::digest(messages.as_bytes());
+    let (provider, usage, status) = match result {
+        Ok(r) => (
+            r.provider.clone(),
+            r.usage.clone().unwrap_or_default(),
+            "ok".to_string(),
+        ),
+        Err(e) => (None, Default::default(), format!("error: {}", e)),
+    };
+    let entry = AuditEntry {
+        timestamp: Utc::now().to_rfc3339(),
+        url: url.to_string(),
+        key: Some(key.to_string()),
+        model: body
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        provider,
+        prompt_sha256: hash.iter().map(|b| format!("{:02x}", b)).collect(),
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        cost: usage.cost,
+        status,
+    };
+    // Auditing must never break generation; report and carry on.
+    if let Err(e) = append(&entry) {
+        eprintln!("Failed to write audit log: {}", e);
+    }
+}
+
+fn append(entry: &AuditEntry) -> Result<()> {
+    if let Some(path) = audit_path() {
+        append_line(&path, &serde_json::to_string(entry)?)?;
+    }
+    Ok(())
+}
+
+pub fn command() -> Command {
+    Command::new("audit")
+        .about("Show the audit log of outbound API requests")
+        .after_help("Examples:\n  ss audit\n  ss audit --last 20")
+        .arg(
+            Arg::new("last")
+                .long("last")
+                .help("Only show the most recent N entries")
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+}
+
+// All entries in the log, oldest first; empty when there is none yet.
+pub fn read_entries() -> Result<Vec<AuditEntry>> {
+    let path = match audit_path() {
+        Some(p) if p.exists() => p,
+        _ => return Ok(Vec::new()),
+    };
+    let s = std::fs::read_to_string(&path)?;
+    Ok(s.lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect())
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let entries = read_entries()?;
+    if entries.is_empty() {
+        println!("no audit entries");
+        return Ok(());
+    }
+    let skip = matches
+        .get_one::<usize>("last")
+        .map(|n| entries.len().saturating_sub(*n))
+        .unwrap_or(0);
+    let tokens = |t: Option<u64>| t.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string());
+    for e in entries.iter().skip(skip) {
+        println!(
+            "{} {} via {} -> {} (key {})\n  tokens in/out: {}/{}  prompt sha256: {}  status: {}",
+            e.timestamp,
+            e.model,
+            e.provider.as_deref().unwrap_or("?"),
+            e.url,
+            e.key.as_deref().unwrap_or("?"),
+            tokens(e.prompt_tokens),
+            tokens(e.completion_tokens),
+            &e.prompt_sha256[..16.min(e.prompt_sha256.len())],
+            e.status
+        );
+    }
+    Ok(())
+}
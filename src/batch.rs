@@ -0,0 +1,168 @@
+// `ss batch`: run many prompts from a file and write one JSON result per line.
+use anyhow::Result;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
+
+use crate::llm::{complete, Settings};
+use crate::output::{is_not_able_response, plain, refusal};
+use crate::prompt::{command_system, few_shot};
+
+pub fn command() -> Command {
+    Command::new("batch")
+        .about("Generate commands for every prompt in a file (one per line) and write JSON lines")
+        .after_help("Examples:\n  ss batch prompts.txt\n  ss batch prompts.txt --json-out results.jsonl -j 4")
+        .arg(
+            Arg::new("file")
+                .help("Prompts file; blank lines and lines starting with # are skipped")
+                .index(1)
+                .required(true)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("json-out")
+                .long("json-out")
+                .help("Write results to this JSONL file instead of stdout")
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("concurrency")
+                .short('j')
+                .long("concurrency")
+                .help("Number of requests in flight at once (default: 1)")
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("multiline")
+                .short('L')
+                .long("multiline")
+                .help("Allow multiline scripts instead of single-line commands")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+#[derive(Serialize)]
+struct BatchResult {
+    line: usize,
+    prompt: String,
+    command: Option<String>,
+    not_able: bool,
+    error: Option<String>,
+    model: String,
+    latency_ms: u128,
+}
+
+// `root` carries the top-level -s/--system-* overrides, which are not defined on the subcommand.
+pub async fn run(matches: &ArgMatches, root: &ArgMatches) -> Result<()> {
+    let file = matches
+        .get_one::<PathBuf>("file")
+        .cloned()
+        .unwrap_or_default();
+    let concurrency = matches
+        .get_one::<usize>("concurrency")
+        .copied()
+        .unwrap_or(1)
+        .max(1);
+
+    let prompts: Vec<(usize, String)> = std::fs::read_to_string(&file)?
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim().to_string()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+
+    let settings = Settings::from_matches(matches);
+    let system = command_system(root, matches.get_flag("multiline"));
+    let examples = few_shot(matches.get_flag("multiline"));
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+
+    let mut tasks = Vec::new();
+    for (line, prompt) in prompts {
+        let settings = settings.clone();
+        let system = system.clone();
+        let examples = examples.clone();
+        let semaphore = semaphore.clone();
+        tasks.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let mut messages = vec![serde_json::json!({"role": "system", "content": system})];
+            messages.extend(examples);
+            messages.push(serde_json::json!({"role": "user", "content": prompt}));
+            let start = Instant::now();
+            let result = complete(&settings, &messages).await;
+            let latency_ms = start.elapsed().as_millis();
+            match result {
+                Ok(c) => {
+                    let out = c.content.trim().to_string();
+                    // Blocked commands are reported as errors and never written out.
+                    if let Some(reason) = refusal(&out) {
+                        return BatchResult {
+                            line,
+                            prompt,
+                            command: None,
+                            not_able: false,
+                            error: Some(format!("command {}", reason)),
+                            model: settings.model,
+                            latency_ms,
+                        };
+                    }
+                    BatchResult {
+                        line,
+                        prompt,
+                        not_able: is_not_able_response(&out),
+                        command: Some(out),
+                        error: None,
+                        model: settings.model,
+                        latency_ms,
+                    }
+                }
+                Err(e) => BatchResult {
+                    line,
+                    prompt,
+                    command: None,
+                    not_able: false,
+                    error: Some(e.to_string()),
+                    model: settings.model,
+                    latency_ms,
+                },
+            }
+        }));
+    }
+
+    let total = tasks.len();
+    let json_out = matches.get_one::<PathBuf>("json-out");
+    let mut sink: Box<dyn Write> = match json_out {
+        Some(p) => Box::new(std::fs::File::create(p)?),
+        None => Box::new(std::io::stdout()),
+    };
+    // Results are written in input order as they complete.
+    let mut failed = 0;
+    for (done, task) in tasks.into_iter().enumerate() {
+        let r = task.await?;
+        if r.error.is_some() {
+            failed += 1;
+        }
+        writeln!(sink, "{}", serde_json::to_string(&r)?)?;
+        // Progress only when results go to a file, so it never interleaves with stdout
+        if json_out.is_some() {
+            if plain() {
+                eprintln!("Finished {} of {} prompts.", done + 1, total);
+            } else {
+                eprint!("\r{}/{} done", done + 1, total);
+            }
+        }
+    }
+    sink.flush()?;
+    if json_out.is_some() && !plain() {
+        eprintln!();
+    }
+    if failed > 0 {
+        eprintln!("{} of {} prompts failed.", failed, total);
+    }
+    Ok(())
+}
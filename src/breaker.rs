@@ -0,0 +1,173 @@
+// Circuit breaker per model: after `failures` outage-like errors (timeouts, 5xx, and 429s
+// from the provider) within `window`, the model is skipped for `cooldown` and requests
+// go straight to the fallback or fast model, or the response cache, instead of waiting
+// out another timeout. The first request after the cooldown is a trial; one more failure
+// opens the circuit again. State is shared by every `ss` through a file in the state dir.
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::config;
+use crate::llm::{parse_duration, ProviderBusy, TimedOut};
+use crate::paths::state_dir;
+
+const DEFAULT_FAILURES: u32 = 3;
+const DEFAULT_WINDOW: Duration = Duration::from_secs(120);
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(300);
+
+#[derive(Serialize, Deserialize, Default)]
+struct Circuit {
+    // Failure times (ms) within the window
+    failures: Vec<i64>,
+    // Set when the circuit opened; kept after it elapses so a failed trial reopens it
+    open_until: Option<i64>,
+}
+
+fn state_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("breaker.json"))
+}
+
+fn threshold() -> u32 {
+    config().breaker.failures.unwrap_or(DEFAULT_FAILURES)
+}
+
+fn setting(value: Option<&str>, name: &str, default: Duration) -> Duration {
+    match value {
+        None => default,
+        Some(s) => parse_duration(s).unwrap_or_else(|e| {
+            eprintln!(
+                "Breaker: bad {} ({}); using {}s.",
+                name,
+                e,
+                default.as_secs()
+            );
+            default
+        }),
+    }
+}
+
+fn window_ms() -> i64 {
+    setting(config().breaker.window.as_deref(), "window", DEFAULT_WINDOW).as_millis() as i64
+}
+
+fn cooldown_ms() -> i64 {
+    setting(
+        config().breaker.cooldown.as_deref(),
+        "cooldown",
+        DEFAULT_COOLDOWN,
+    )
+    .as_millis() as i64
+}
+
+// Read, change, and write back the circuits under an exclusive lock. Errors (no state
+// dir, unwritable file) leave the breaker out of the way rather than failing requests.
+fn update<T: Default>(f: impl FnOnce(&mut BTreeMap<String, Circuit>, i64) -> T) -> T {
+    let attempt = || -> Result<T> {
+        let Some(path) = state_path() else {
+            return Ok(T::default());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)?;
+        file.lock()?;
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        let mut circuits: BTreeMap<String, Circuit> =
+            serde_json::from_str(&text).unwrap_or_default();
+        let out = f(&mut circuits, Utc::now().timestamp_millis());
+        let new = serde_json::to_string(&circuits)?;
+        if new != text {
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(new.as_bytes())?;
+        }
+        Ok(out)
+    };
+    attempt().unwrap_or_default()
+}
+
+// Nothing to read or clear until a failure has been recorded.
+fn tracking() -> bool {
+    threshold() > 0 && state_path().is_some_and(|p| p.exists())
+}
+
+// How much longer `model` is skipped, if its circuit is open.
+pub fn open_for(model: &str) -> Option<Duration> {
+    if !tracking() {
+        return None;
+    }
+    update(|circuits, now| {
+        let until = circuits.get(model)?.open_until?;
+        (until > now).then(|| Duration::from_millis((until - now) as u64))
+    })
+}
+
+// Whether an error looks like the provider being down or overloaded, as opposed to a
+// problem with this request (bad key, policy, oversized prompt), with the key (its own
+// 429s), or with this machine's network (connection errors).
+pub fn is_outage(e: &anyhow::Error) -> bool {
+    if e.is::<TimedOut>() || e.chain().any(|c| c.is::<ProviderBusy>()) {
+        return true;
+    }
+    e.chain()
+        .filter_map(|c| c.downcast_ref::<reqwest::Error>())
+        .any(|e| match e.status() {
+            Some(s) => s.as_u16() == 408 || s.is_server_error(),
+            None => e.is_timeout(),
+        })
+}
+
+pub fn failure(model: &str) {
+    let limit = threshold();
+    if limit == 0 {
+        return;
+    }
+    let (window, cooldown) = (window_ms(), cooldown_ms());
+    let opened = update(|circuits, now| {
+        let c = circuits.entry(model.to_string()).or_default();
+        c.failures.retain(|t| now - t < window);
+        c.failures.push(now);
+        // A failed trial after the cooldown reopens at once.
+        let trial = c.open_until.is_some_and(|u| u <= now);
+        if trial || c.failures.len() >= limit as usize {
+            c.open_until = Some(now + cooldown);
+            return true;
+        }
+        false
+    });
+    if opened {
+        eprintln!(
+            "{} keeps failing; skipping it for {}s.",
+            model,
+            cooldown / 1000
+        );
+    }
+}
+
+pub fn success(model: &str) {
+    if !tracking() {
+        return;
+    }
+    update(|circuits, _| circuits.remove(model).map(|_| ()));
+}
+
+// Pass a request's result through, counting it for or against the model.
+pub fn watch<T>(model: &str, result: Result<T>) -> Result<T> {
+    match &result {
+        Ok(_) => success(model),
+        Err(e) if is_outage(e) => failure(model),
+        Err(_) => {}
+    }
+    result
+}
@@ -0,0 +1,82 @@
+// On-disk cache of successful completions, keyed by model, effort, and the exact
+// messages sent. Written on every successful request; read by --offline. Holds at most
+// `response_cache` entries (default 500), dropping the oldest first; 0 stops writing.
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::config::config;
+use crate::llm::{Completion, Settings, Usage};
+use crate::paths::cache_dir;
+use crate::reasoning;
+
+const DEFAULT_SIZE: usize = 500;
+
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    content: String,
+    reasoning: Option<JsonValue>,
+}
+
+pub fn key(settings: &Settings, messages: &[JsonValue]) -> String {
+    let input = serde_json::json!([settings.model, settings.effort, messages]).to_string();
+    Sha256::digest(input.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn path(key: &str) -> Option<PathBuf> {
+    cache_dir().map(|d| d.join("responses").join(format!("{}.json", key)))
+}
+
+pub fn get(key: &str) -> Option<Completion> {
+    let s = std::fs::read_to_string(path(key)?).ok()?;
+    let c: CachedResponse = serde_json::from_str(&s).ok()?;
+    // Entries written before think blocks were stripped may still carry one.
+    let (content, thoughts) = reasoning::split_think(&c.content);
+    Some(Completion {
+        content,
+        reasoning: c.reasoning.or(thoughts.map(JsonValue::String)),
+        usage: Usage::default(),
+        model: None,
+        provider: None,
+        cached: true,
+    })
+}
+
+// Best effort: a cache that cannot be written is not worth failing the request over.
+pub fn put(key: &str, completion: &Completion) {
+    let size = config().response_cache.unwrap_or(DEFAULT_SIZE);
+    let Some(path) = path(key).filter(|_| size > 0) else {
+        return;
+    };
+    let entry = CachedResponse {
+        content: completion.content.clone(),
+        reasoning: completion.reasoning.clone(),
+    };
+    if let (Some(dir), Ok(s)) = (path.parent(), serde_json::to_string(&entry)) {
+        let _ = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, s));
+        evict(dir, size);
+    }
+}
+
+// Remove the least recently written entries past `size`.
+fn evict(dir: &Path, size: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    if files.len() <= size {
+        return;
+    }
+    files.sort();
+    for (_, path) in &files[..files.len() - size] {
+        let _ = std::fs::remove_file(path);
+    }
+}
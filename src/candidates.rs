@@ -0,0 +1,131 @@
+// `-n N`: ask for N alternative commands in one request. The first is used like any
+// answer; the others are listed on stderr, and the shell widget (`ss init <shell> -n N`)
+// cycles through all of them on repeated presses of its key. The widget's place in the
+// cycle is kept per shell session in a small state file between invocations.
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::output::{quiet, refusal};
+use crate::paths::state_dir;
+
+// Set by the widget to the shell's pid, so terminals do not share one cycle.
+pub const SESSION_ENV: &str = "SNAPSHELL_WIDGET_SESSION";
+// Cycles older than this are forgotten.
+const MAX_AGE_SECS: i64 = 3600;
+
+pub fn note(n: usize) -> String {
+    format!(" Exception to the single-command rule: give {} different commands that each accomplish the request, one per line, best first. Each must be a complete single-line command; no numbering and nothing else.", n)
+}
+
+// Up to `n` commands from a response for -n, in order, without numbering, duplicates,
+// or any that would be refused.
+pub fn split(content: &str, n: usize) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let line = line
+            .strip_prefix(|c: char| c.is_ascii_digit())
+            .and_then(|l| l.strip_prefix(['.', ')']))
+            .or_else(|| line.strip_prefix("- "))
+            .unwrap_or(line)
+            .trim();
+        if line.is_empty() || out.iter().any(|c| c == line) || refusal(line).is_some() {
+            continue;
+        }
+        out.push(line.to_string());
+    }
+    out.truncate(n);
+    out
+}
+
+// The alternatives after the first, on stderr.
+pub fn show_rest(candidates: &[String]) {
+    if candidates.len() < 2 || quiet() {
+        return;
+    }
+    eprintln!("Alternatives:");
+    for (i, c) in candidates.iter().enumerate().skip(1) {
+        eprintln!("  {}. {}", i + 1, c);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cycle {
+    candidates: Vec<String>,
+    index: usize,
+    // Unix seconds of the last press
+    at: i64,
+}
+
+fn state_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("widget.json"))
+}
+
+fn session() -> Option<String> {
+    std::env::var(SESSION_ENV).ok().filter(|s| !s.is_empty())
+}
+
+fn load() -> BTreeMap<String, Cycle> {
+    state_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn store(cycles: &BTreeMap<String, Cycle>) -> Result<()> {
+    let Some(path) = state_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string(cycles)?)?;
+    Ok(())
+}
+
+// Remember the candidates the widget was just given, starting at the first.
+pub fn save(candidates: &[String]) -> Result<()> {
+    let Some(session) = session() else {
+        return Ok(());
+    };
+    let now = Utc::now().timestamp();
+    let mut cycles = load();
+    cycles.retain(|_, c| now - c.at < MAX_AGE_SECS);
+    if candidates.len() < 2 {
+        cycles.remove(&session);
+    } else {
+        cycles.insert(
+            session,
+            Cycle {
+                candidates: candidates.to_vec(),
+                index: 0,
+                at: now,
+            },
+        );
+    }
+    store(&cycles)
+}
+
+// When the widget is pressed again on the candidate it last put in the buffer, the next
+// one (wrapping around), with its position; anything else starts a new request.
+pub fn next(buffer: &str) -> Option<(String, usize, usize)> {
+    let session = session()?;
+    let mut cycles = load();
+    let cycle = cycles.get_mut(&session)?;
+    let now = Utc::now().timestamp();
+    if now - cycle.at >= MAX_AGE_SECS || cycle.candidates.get(cycle.index)? != buffer.trim() {
+        return None;
+    }
+    cycle.index = (cycle.index + 1) % cycle.candidates.len();
+    cycle.at = now;
+    let shown = (
+        cycle.candidates[cycle.index].clone(),
+        cycle.index + 1,
+        cycle.candidates.len(),
+    );
+    let _ = store(&cycles);
+    Some(shown)
+}
@@ -0,0 +1,384 @@
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::llm::{complete_or_exit, Settings, Usage};
+use crate::models::estimate_cost;
+use crate::output::stats_footer;
+use crate::paste;
+use crate::prompt::{chat_system, persona, persona_names};
+use crate::session::{Branch, Session, Stats, Turn};
+
+const MAIN: &str = "main";
+// Opens a multi-line message, which a lone `.` closes.
+const BLOCK_START: &str = "\"\"\"";
+const BLOCK_END: &str = ".";
+
+// One line of conversation. Branches start as a copy of their parent up to a checkpoint.
+struct Thread {
+    parent: String,
+    fork: usize,
+    messages: Vec<JsonValue>,
+    turns: Vec<Turn>,
+}
+
+// A point saved with /checkpoint that /branch can fork from.
+struct Checkpoint {
+    name: String,
+    thread: String,
+    messages: usize,
+    turns: usize,
+}
+
+struct Chat {
+    session: Session,
+    threads: BTreeMap<String, Thread>,
+    current: String,
+    checkpoints: Vec<Checkpoint>,
+    // Kept to rebuild the system message when /persona switches
+    prettify_table: bool,
+    persona: Option<String>,
+}
+
+// Interactive loop: keep conversation messages and prompt user after each model response.
+pub async fn run(
+    settings: &Settings,
+    messages: Vec<JsonValue>,
+    prettify_table: bool,
+    persona: Option<String>,
+) -> Result<()> {
+    println!("Entering interactive chat mode. Type '/exit' or empty line to quit, '/export <file>' to save the conversation, '/checkpoint', '/branch <name>', and '/branches' to explore alternatives, '/persona <name>' to change who answers. Start a multi-line message with a line of \"\"\" and end it with a lone '.', or continue a line with a trailing backslash.");
+    // messages already contains the chat system instruction and the first user prompt
+    let main = Thread {
+        parent: String::new(),
+        fork: 0,
+        messages,
+        turns: Vec::new(),
+    };
+    let mut chat = Chat {
+        session: Session::new(&settings.model),
+        threads: BTreeMap::from([(MAIN.to_string(), main)]),
+        current: MAIN.to_string(),
+        checkpoints: Vec::new(),
+        prettify_table,
+        persona,
+    };
+    loop {
+        let thread = chat.thread();
+        let prompt = thread
+            .messages
+            .last()
+            .and_then(|m| m["content"].as_str())
+            .unwrap_or_default()
+            .to_string();
+        let start = Instant::now();
+        let completion = complete_or_exit(settings, &thread.messages).await;
+        let latency = start.elapsed();
+        stats_footer(&completion, latency);
+        chat.record(settings, &completion.usage, latency).await;
+        let response = completion.content;
+
+        // Print assistant response
+        println!("{}", response.trim());
+
+        // If show_reasoning is requested, the model may include a trailing reasoning field; print nothing here — interactive mode shows full assistant response.
+
+        // Append assistant message to conversation
+        let thread = chat.thread();
+        thread
+            .messages
+            .push(serde_json::json!({"role": "assistant", "content": response}));
+        thread.turns.push(Turn {
+            prompt,
+            response: response.clone(),
+            reasoning: completion.reasoning,
+        });
+        chat.save();
+
+        // Prompt for next user input; chat commands are handled here without a model call
+        let line = loop {
+            match read_line() {
+                Some(l) if chat.command(&l) => {}
+                other => break other,
+            }
+        };
+        let line = match line {
+            Some(l) if !l.is_empty() && l != "/exit" => l,
+            _ => break,
+        };
+        // add user message and continue loop
+        chat.thread()
+            .messages
+            .push(serde_json::json!({"role": "user", "content": line}));
+    }
+    if let Some(stats) = &chat.session.stats {
+        eprintln!("Chat: {}.", stats.summary());
+    }
+    eprintln!(
+        "Session {} saved; `ss chat export {}` renders it.",
+        chat.session.id, chat.session.id
+    );
+    Ok(())
+}
+
+impl Chat {
+    fn thread(&mut self) -> &mut Thread {
+        self.threads.get_mut(&self.current).expect("current thread")
+    }
+
+    // Add one answer to the session's stats; saved with the next turn.
+    async fn record(&mut self, settings: &Settings, usage: &Usage, latency: Duration) {
+        let stats = self.session.stats.get_or_insert_with(Stats::default);
+        let prompt_tokens = usage.prompt_tokens.unwrap_or(0);
+        let completion_tokens = usage.completion_tokens.unwrap_or(0);
+        stats.turns += 1;
+        stats.prompt_tokens += prompt_tokens;
+        stats.completion_tokens += completion_tokens;
+        stats.total_latency_ms += latency.as_millis() as u64;
+        match usage.cost {
+            Some(cost) => stats.cost += cost,
+            None if prompt_tokens + completion_tokens > 0 => {
+                if let Some(cost) =
+                    estimate_cost(&settings.model, prompt_tokens, completion_tokens).await
+                {
+                    stats.cost += cost;
+                    stats.cost_estimated = true;
+                }
+            }
+            None => {}
+        }
+    }
+
+    // Mirror every thread into the session file: main in full, branches past their fork.
+    fn save(&mut self) {
+        self.session.turns = self.threads[MAIN].turns.clone();
+        self.session.branches = self
+            .threads
+            .iter()
+            .filter(|(name, _)| name.as_str() != MAIN)
+            .map(|(name, t)| Branch {
+                name: name.clone(),
+                parent: t.parent.clone(),
+                fork: t.fork,
+                turns: t.turns[t.fork..].to_vec(),
+            })
+            .collect();
+        if let Err(e) = self.session.save() {
+            eprintln!("Failed to save chat session: {}", e);
+        }
+    }
+
+    // Handle a /command; false means the line is a prompt for the model.
+    fn command(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("/export") => {
+                let path = line["/export".len()..].trim();
+                if path.is_empty() {
+                    eprintln!("Usage: /export <file.md|file.html>");
+                } else if let Err(e) = self.session.export(Path::new(path)) {
+                    eprintln!("Export failed: {}", e);
+                } else {
+                    eprintln!("Exported the conversation to {}.", path);
+                }
+            }
+            Some("/checkpoint") => {
+                let name = match words.next() {
+                    Some(n) => n.to_string(),
+                    None => format!("cp{}", self.checkpoints.len() + 1),
+                };
+                let thread = self.current.clone();
+                let t = self.thread();
+                let checkpoint = Checkpoint {
+                    name,
+                    thread,
+                    messages: t.messages.len(),
+                    turns: t.turns.len(),
+                };
+                eprintln!(
+                    "Checkpoint {} saved on {} after turn {}.",
+                    checkpoint.name, checkpoint.thread, checkpoint.turns
+                );
+                self.checkpoints.retain(|c| c.name != checkpoint.name);
+                self.checkpoints.push(checkpoint);
+            }
+            Some("/branch") => match words.next() {
+                Some(name) => self.branch(name, words.next()),
+                None => eprintln!("Usage: /branch <name> [checkpoint]"),
+            },
+            Some("/branches") => match words.next() {
+                Some(name) => self.switch(name),
+                None => self.list(),
+            },
+            Some("/persona") => match words.next() {
+                Some(name) => self.set_persona(name),
+                None => {
+                    for name in persona_names() {
+                        let marker = if self.persona.as_deref() == Some(name.as_str()) {
+                            "*"
+                        } else {
+                            " "
+                        };
+                        eprintln!("{} {}", marker, name);
+                    }
+                    eprintln!("  (/persona none goes back to the plain assistant)");
+                }
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    // Switch to `name`, creating it from a checkpoint (the latest by default) if it is new.
+    fn branch(&mut self, name: &str, from: Option<&str>) {
+        if self.threads.contains_key(name) {
+            self.switch(name);
+            return;
+        }
+        let checkpoint = match from {
+            Some(f) => match self.checkpoints.iter().find(|c| c.name == f) {
+                Some(c) => c,
+                None => {
+                    eprintln!("No checkpoint named {}; see /branches.", f);
+                    return;
+                }
+            },
+            None => match self.checkpoints.last() {
+                Some(c) => c,
+                None => {
+                    eprintln!("No checkpoint yet; save one with /checkpoint first.");
+                    return;
+                }
+            },
+        };
+        let parent = &self.threads[&checkpoint.thread];
+        let thread = Thread {
+            parent: checkpoint.thread.clone(),
+            fork: checkpoint.turns,
+            messages: parent.messages[..checkpoint.messages].to_vec(),
+            turns: parent.turns[..checkpoint.turns].to_vec(),
+        };
+        eprintln!(
+            "Branch {} starts from checkpoint {} ({} after turn {}).",
+            name, checkpoint.name, checkpoint.thread, checkpoint.turns
+        );
+        self.threads.insert(name.to_string(), thread);
+        self.current = name.to_string();
+    }
+
+    // Rewrite the current thread's system message; later turns answer in the new voice
+    // and earlier ones are kept as they were.
+    fn set_persona(&mut self, name: &str) {
+        let chosen = if name == "none" {
+            None
+        } else {
+            match persona(name) {
+                Ok(text) => Some((name.to_string(), text)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            }
+        };
+        let system = chat_system(
+            self.prettify_table,
+            chosen.as_ref().map(|(_, t)| t.as_str()),
+        );
+        let thread = self.thread();
+        if let Some(first) = thread
+            .messages
+            .first_mut()
+            .filter(|m| m["role"] == "system")
+        {
+            first["content"] = JsonValue::String(system);
+        }
+        self.persona = chosen.map(|(n, _)| n);
+        match &self.persona {
+            Some(n) => eprintln!("Persona {} on branch {}.", n, self.current),
+            None => eprintln!("No persona on branch {}.", self.current),
+        }
+    }
+
+    fn switch(&mut self, name: &str) {
+        match self.threads.get(name) {
+            Some(t) => {
+                self.current = name.to_string();
+                eprintln!("On branch {} ({} turns).", name, t.turns.len());
+                if let Some(last) = t.turns.last() {
+                    println!("{}", last.response.trim());
+                }
+            }
+            None => eprintln!("No branch named {}; create it with /branch {}.", name, name),
+        }
+    }
+
+    fn list(&self) {
+        for (name, t) in &self.threads {
+            let marker = if *name == self.current { "*" } else { " " };
+            let origin = if t.parent.is_empty() {
+                String::new()
+            } else {
+                format!("  (from {} after turn {})", t.parent, t.fork)
+            };
+            eprintln!("{} {}  {} turns{}", marker, name, t.turns.len(), origin);
+        }
+        for c in &self.checkpoints {
+            eprintln!(
+                "  checkpoint {}: {} after turn {}",
+                c.name, c.thread, c.turns
+            );
+        }
+    }
+}
+
+// One message from the user: a single line, lines joined by a trailing backslash, or a
+// block between `"""` and a lone `.` for pasted YAML or stack traces. None on EOF or a
+// read error before anything was typed.
+fn read_line() -> Option<String> {
+    let _paste = paste::enable();
+    let (mut first, pasted) = paste::collect(prompt_line("> ")?, || prompt_line(""));
+    if pasted {
+        return Some(first.trim_matches('\n').trim_end().to_string());
+    }
+    while first.trim() == BLOCK_START {
+        let mut lines = Vec::new();
+        while let Some(line) = prompt_line("... ") {
+            if line.trim() == BLOCK_END {
+                break;
+            }
+            lines.push(line);
+        }
+        // Leading indentation matters in YAML, so only blank lines are trimmed.
+        let block = lines.join("\n");
+        if !block.trim().is_empty() {
+            return Some(block.trim_start_matches('\n').trim_end().to_string());
+        }
+        // An empty block is not a request to leave the chat.
+        eprintln!("Empty message; nothing sent.");
+        first = paste::collect(prompt_line("> ")?, || prompt_line("")).0;
+    }
+    let mut message = first;
+    while message.ends_with('\\') {
+        message.pop();
+        message.push('\n');
+        match prompt_line("... ") {
+            Some(line) => message.push_str(&line),
+            None => break,
+        }
+    }
+    Some(message.trim().to_string())
+}
+
+// A line without its newline; None on EOF or a read error.
+fn prompt_line(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    let _ = io::stdout().flush();
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+    }
+}
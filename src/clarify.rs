@@ -0,0 +1,64 @@
+// `--clarify`: let the model ask one short question before committing to a command.
+use serde_json::Value as JsonValue;
+use std::io::{self, IsTerminal, Write};
+
+use crate::llm::{complete_or_exit, Completion, Settings};
+
+const MARKER: &str = "(QUESTION):";
+
+// Appended to the system instruction. The question is a single line so it can never be
+// mistaken for a multi-line script.
+pub const CLARIFY_NOTE: &str = " If the request is ambiguous in a way that would change the command (for example recursive or top-level only, which directory, or delete versus list), you may instead ask ONE short clarifying question, responding exactly with the following format and nothing else: (QUESTION): <one short question>. Only ask when a wrong guess is likely; otherwise answer with the command directly.";
+
+const NO_MORE_QUESTIONS: &str = "Now give the command. Do not ask another question.";
+const NO_ANSWER: &str = "No answer is available. Make the most reasonable assumption and give the command. Do not ask a question.";
+
+// The question in a clarifying response, if that is what the model sent.
+pub fn question(content: &str) -> Option<&str> {
+    let q = content.trim().strip_prefix(MARKER)?.trim();
+    (!q.is_empty()).then_some(q)
+}
+
+// If `first` is a question, put it to the user (or, without a terminal, tell the model to
+// assume) and ask again. Returns the final completion, with the usage of both rounds.
+pub async fn resolve(
+    settings: &Settings,
+    messages: &mut Vec<JsonValue>,
+    first: Completion,
+) -> Completion {
+    let Some(q) = question(&first.content) else {
+        return first;
+    };
+    let reply = match ask(q) {
+        Some(answer) => format!("{} {}", answer, NO_MORE_QUESTIONS),
+        None => NO_ANSWER.to_string(),
+    };
+    messages.push(serde_json::json!({"role": "assistant", "content": first.content.trim()}));
+    messages.push(serde_json::json!({"role": "user", "content": reply}));
+
+    let mut next = complete_or_exit(settings, messages).await;
+    next.usage.add(&first.usage);
+    // A second question means the model could not settle on a command.
+    if let Some(q) = question(&next.content) {
+        next.content = format!(
+            "(NOT ABLE TO ANSWER): The request is ambiguous ({}).",
+            q.trim_end_matches('?')
+        );
+    }
+    next
+}
+
+// None when stdin is not a terminal, on EOF, or on an empty answer.
+fn ask(q: &str) -> Option<String> {
+    if !io::stdin().is_terminal() {
+        eprintln!("{} (no terminal to answer; assuming)", q);
+        return None;
+    }
+    eprint!("{} ", q);
+    let _ = io::stderr().flush();
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()).filter(|l| !l.is_empty()),
+    }
+}
@@ -0,0 +1,556 @@
+use clap::{Arg, ArgAction, Command};
+
+use crate::modes;
+
+pub fn build() -> Command {
+    Command::new("snapshell")
+        .about("Snappy shell command generation (minimal)")
+        .after_help("Examples:\n  ss \"find files larger than 100MB\"\n  ss -L -o backup.sh \"back up ~/docs to s3 nightly with rotation\"\n  ss -x \"kill whatever is listening on port 3000\"\n  ss -a \"why does my ssh key keep asking for a passphrase?\"\n  ss git \"undo the last commit but keep the changes\"\n\nRun `ss <mode> --help` for examples of each mode, or `ss init --man` to install man pages.")
+        .arg(Arg::new("input").help("Command instruction or chat text (or @snippet)").index(1).num_args(1).required(false))
+        .arg(
+            Arg::new("history")
+                .help_heading("Modes")
+                .short('H')
+                .long("history")
+                .help("Show history of prompts and generated commands")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("all")
+                .help_heading("Modes")
+                .short('a')
+                .long("ask")
+                .help("Interactive LLM chat mode (prints conversation)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("reasoning")
+                .help_heading("Model")
+                .short('r')
+                .long("reasoning")
+                .help("Reasoning effort: low, medium, or high (default: low)")
+                .num_args(1)
+                .global(true),
+        )
+        .arg(
+            Arg::new("model")
+                .help_heading("Model")
+                .short('m')
+                .long("model")
+                .help("Model to use (defaults to openai/gpt-oss-120b); `?` picks one with fzf and remembers it")
+                .num_args(1)
+                .global(true),
+        )
+        .arg(
+            Arg::new("retry")
+                .help_heading("Model")
+                .long("retry")
+                .help("When the model answers NOT ABLE TO ANSWER, retry once with --fallback-model, or with reasoning high")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("escalate")
+                .help_heading("Model")
+                .long("escalate")
+                .help("Start at reasoning low and retry at medium, then high, while the answer is NOT ABLE, does not parse, or fails lint")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["reasoning", "offline"]),
+        )
+        .arg(
+            Arg::new("offline")
+                .help_heading("Model")
+                .long("offline")
+                .help("Never use the network: answer from the response cache, similar history entries, or cached cheat sheets")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("free-only")
+                .help_heading("Model")
+                .long("free-only")
+                .help("Refuse to send requests to models that OpenRouter's pricing does not list as free (config: free_only)")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("max-wait")
+                .help_heading("Model")
+                .long("max-wait")
+                .help("Latency budget (e.g. 3s); on timeout, fall back to the fast model (SNAPSHELL_FAST_MODEL), then cached or offline answers")
+                .num_args(1)
+                .value_parser(crate::llm::parse_duration)
+                .global(true),
+        )
+        .arg(
+            Arg::new("max-context-tokens")
+                .help_heading("Model")
+                .long("max-context-tokens")
+                .help("Cap on the estimated tokens of attached context (diffs, samples, schemas, docs); larger attachments are truncated and reported (default: 8000)")
+                .num_args(1)
+                .value_parser(clap::value_parser!(usize))
+                .global(true),
+        )
+        .arg(
+            Arg::new("max-tokens")
+                .help_heading("Model")
+                .long("max-tokens")
+                .help("Cap on the tokens of each answer; a cut-off answer is reported (config: max_tokens)")
+                .num_args(1)
+                .value_parser(clap::value_parser!(u32))
+                .global(true),
+        )
+        .arg(
+            Arg::new("max-prompt-tokens")
+                .help_heading("Model")
+                .long("max-prompt-tokens")
+                .help("Refuse requests whose estimated size exceeds this many tokens, e.g. a whole log pasted by accident (default: 32000)")
+                .num_args(1)
+                .value_parser(clap::value_parser!(usize))
+                .global(true),
+        )
+        .arg(
+            Arg::new("tools")
+                .help_heading("Model")
+                .long("tools")
+                .help("Let the model call local read-only tools (which, help, ls, ffprobe) while generating; optionally a comma-separated subset")
+                .num_args(0..=1)
+                .require_equals(true)
+                .value_delimiter(',')
+                .default_missing_value("")
+                .global(true),
+        )
+        .arg(
+            Arg::new("profile")
+                .help_heading("Model")
+                .long("profile")
+                .help("Use the API keys of this profile from config.toml (or SNAPSHELL_PROFILE)")
+                .num_args(1)
+                .global(true),
+        )
+        .arg(
+            Arg::new("redact")
+                .help_heading("Safety")
+                .long("redact")
+                .help("Mask internal hostnames, IPs, usernames, and emails before prompts leave the machine; they are restored in the answer")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("fallback-model")
+                .help_heading("Model")
+                .long("fallback-model")
+                .help("Stronger model used by --retry, and while the main model is failing (or set SNAPSHELL_FALLBACK_MODEL)")
+                .num_args(1)
+                .global(true),
+        )
+        .arg(
+            Arg::new("multiline")
+                .help_heading("Generation")
+                .short('L')
+                .long("multiline")
+                .help("Allow multiline/multi-line shell script output instead of forcing a single-line command")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("regen")
+                .help_heading("Modes")
+                .long("regen")
+                .help("Regenerate the most recent history entry and show a word diff against it")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("refine")
+                .help_heading("Modes")
+                .long("refine")
+                .help("Refine the most recent history entry with an instruction (e.g. --refine 'make it recursive') and show a word diff")
+                .num_args(1)
+                .conflicts_with("regen"),
+        )
+        .arg(
+            Arg::new("confidence")
+                .help_heading("Safety")
+                .long("confidence")
+                .help("Ask the model (in a second, low-effort request) to rate its confidence in the command; low scores print a warning")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("loop")
+                .help_heading("Modes")
+                .long("loop")
+                .help("Refine the generated command in a short REPL; only the accepted version is printed, copied, and saved")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("exec")
+                .help_heading("Safety")
+                .short('x')
+                .long("exec")
+                .help("Run the generated command after confirmation (sudo is confirmed separately)")
+                .conflicts_with_all(["all", "output"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("plain")
+                .help_heading("Output")
+                .long("plain")
+                .help("Screen-reader friendly output: no colors, in-place progress, or banners, and status messages as full sentences on stderr (also when TERM=dumb)")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .help_heading("Output")
+                .short('q')
+                .long("quiet")
+                .help("Print exactly the command on stdout and nothing else; drop warnings and send -S reasoning to stderr")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["all", "exec", "loop", "json", "alfred", "raycast", "deterministic"]),
+        )
+        .arg(
+            Arg::new("eval")
+                .help_heading("Output")
+                .long("eval")
+                .help("Like --quiet, but refuse (exit 4) unless the command is one line with no control characters or interactive prompts, so $(ss --eval ...) is safe")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["all", "exec", "loop", "json", "alfred", "raycast", "deterministic", "multiline", "annotate", "output"]),
+        )
+        .arg(
+            Arg::new("print0")
+                .help_heading("Output")
+                .long("print0")
+                .help("End the command with a NUL byte instead of a newline, for xargs -0")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["all", "output", "json", "alfred", "raycast", "deterministic"]),
+        )
+        .arg(
+            Arg::new("escaped")
+                .help_heading("Output")
+                .long("escaped")
+                .help("Print the command as one single-quoted shell word, to embed it in other commands")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["all", "output", "json", "alfred", "raycast", "deterministic"]),
+        )
+        .arg(
+            Arg::new("as-abbr")
+                .help_heading("Output")
+                .long("as-abbr")
+                .value_name("NAME")
+                .value_parser(crate::modes::alias::abbr_name)
+                .help("Print the command as a zsh-abbr or fish abbreviation named NAME and offer to save it")
+                .num_args(1)
+                .conflicts_with_all(["all", "output", "exec", "loop", "json", "alfred", "raycast", "deterministic", "print0", "escaped", "eval", "multiline", "annotate"]),
+        )
+        .arg(
+            Arg::new("hyperlinks")
+                .help_heading("Output")
+                .long("hyperlinks")
+                .help("Turn files named in the printed command into clickable links, in terminals that support OSC 8")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("stats")
+                .help_heading("Output")
+                .long("stats")
+                .help("After each answer, print the model and provider that served it, latency, tokens, and cache status on stderr")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("json")
+                .help_heading("Output")
+                .long("json")
+                .help("Print one JSON object (prompt, model, command, and not_able or refused) instead of the command; nothing is copied")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["all", "output", "exec", "loop", "alfred", "raycast"]),
+        )
+        .arg(
+            Arg::new("deterministic")
+                .help_heading("Output")
+                .long("deterministic")
+                .help("For scripts: temperature 0 and a fixed seed, --json and --plain output, no clipboard, and every confirmation answered no")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("alfred")
+                .help_heading("Output")
+                .long("alfred")
+                .help("Print the result as Alfred Script Filter JSON instead of copying it")
+                .conflicts_with_all(["all", "output", "exec", "raycast", "loop"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("raycast")
+                .help_heading("Output")
+                .long("raycast")
+                .help("Print the result as Raycast list items JSON instead of copying it")
+                .conflicts_with_all(["all", "output", "exec", "loop"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dry-explain")
+                .help_heading("Safety")
+                .long("dry-explain")
+                .help("With --exec, explain what the command will touch before asking to run it (or answer d at the prompt)")
+                .requires("exec")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("record")
+                .help_heading("Safety")
+                .long("record")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(std::path::PathBuf))
+                .help("With --exec, save the session (prompt, command, confirmation, and output) as an asciinema recording, e.g. session.cast")
+                .requires("exec")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("schedule")
+                .help_heading("Output")
+                .long("schedule")
+                .value_name("WHEN")
+                .help("Wrap the command to run later with at, cron, or a systemd timer, e.g. \"tonight at 2am\" or \"every weekday at 9\", and offer to install it")
+                .num_args(1)
+                .conflicts_with_all(["all", "output", "exec", "json", "alfred", "raycast", "deterministic", "eval", "as-abbr", "candidates"]),
+        )
+        .arg(
+            Arg::new("candidates")
+                .help_heading("Generation")
+                .short('n')
+                .long("candidates")
+                .value_name("N")
+                .help("Ask for N alternative one-line commands: the first is used, the others are listed on stderr (the shell widget cycles through them)")
+                .value_parser(clap::value_parser!(u8).range(1..=9))
+                .conflicts_with_all(["all", "multiline", "output", "annotate", "loop"]),
+        )
+        .arg(
+            Arg::new("clarify")
+                .help_heading("Generation")
+                .long("clarify")
+                .help("Let the model ask one short clarifying question (e.g. recursive or top-level only?) before answering ambiguous prompts")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["all", "deterministic"]),
+        )
+        .arg(
+            Arg::new("from-clipboard")
+                .help_heading("Generation")
+                .long("from-clipboard")
+                .help("Send the clipboard as the prompt, or attach it to the prompt given (refused above 1 MB, trimmed to --max-context-tokens)")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["regen", "refine"]),
+        )
+        .arg(
+            Arg::new("image")
+                .help_heading("Generation")
+                .long("image")
+                .value_name("PATH")
+                .help("Attach an image (png, jpeg, webp, gif) for a vision-capable model; repeatable")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(std::path::PathBuf)),
+        )
+        .arg(
+            Arg::new("screenshot")
+                .help_heading("Generation")
+                .long("screenshot")
+                .help("Select a screen region and attach it like --image")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("annotate")
+                .help_heading("Generation")
+                .long("annotate")
+                .help("Ask for brief # comments above each logical block of a multiline script (implies --multiline)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("strip-comments")
+                .help_heading("Output")
+                .long("strip-comments")
+                .help("Remove full-line # comments from the generated script before printing, copying, or writing it")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("check-paths")
+                .help_heading("Safety")
+                .long("check-paths")
+                .help("Warn when files the command reads do not exist here; =fix swaps in near-miss names")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("warn")
+                .value_parser(["warn", "fix"]),
+        )
+        .arg(
+            Arg::new("output")
+                .help_heading("Output")
+                .short('o')
+                .long("output")
+                .help("Write the generated script to this file (implies --multiline) with a shebang, preamble, and executable permissions")
+                .num_args(1)
+                .value_parser(clap::value_parser!(std::path::PathBuf)),
+        )
+        .arg(
+            Arg::new("preamble")
+                .help_heading("Output")
+                .long("preamble")
+                .help("Lines placed after the shebang of an --output script (default: set -euo pipefail; empty to disable)")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("force")
+                .help_heading("Output")
+                .long("force")
+                .help("Overwrite the --output or --record file if it already exists")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pt")
+                .help_heading("Generation")
+                .long("pt")
+                .help("Prettify table output: true|false (default: true). When true, instruct model to format tables in psql-style ASCII tables (no markdown). Use --pt=false to disable.")
+                .num_args(1)
+                .value_parser(["true", "false"])
+                .default_value("true"),
+        )
+        .arg(
+            Arg::new("persona")
+                .help_heading("Generation")
+                .long("persona")
+                .value_name("NAME")
+                .help("Chat persona (-a): sysadmin, sre, teacher, or one from `personas` in config.toml")
+                .num_args(1)
+                .requires("all"),
+        )
+        .arg(
+            Arg::new("system")
+                .help_heading("Generation")
+                .short('s')
+                .long("system")
+                .help("Custom system instruction (overrides defaults). Can be used for both single- and multiline modes unless specific flags are provided.")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("system-single")
+                .help_heading("Generation")
+                .long("system-single")
+                .help("Custom system instruction for single-line mode")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("system-multiline")
+                .help_heading("Generation")
+                .long("system-multiline")
+                .help("Custom system instruction for multiline mode")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("auto-context")
+                .help_heading("Generation")
+                .long("auto-context")
+                .help("Describe the working directory to the model: project type, notable config files, npm scripts and make targets, and git state")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("system-append")
+                .help_heading("Generation")
+                .long("system-append")
+                .help("Add an instruction to the built-in system prompt instead of replacing it, e.g. 'we use podman, not docker' (repeatable; config: system_extra)")
+                .num_args(1)
+                .action(ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("lang")
+                .help_heading("Generation")
+                .long("lang")
+                .help("Language for explanations, chat replies, and NOT ABLE reasons, e.g. de or Japanese (default: from LC_ALL/LANG; commands are never translated)")
+                .num_args(1)
+                .global(true),
+        )
+        .arg(
+            Arg::new("var")
+                .help_heading("Generation")
+                .long("var")
+                .help("Fill a {{name}} placeholder in the prompt: --var name=value (repeatable; missing values are asked for)")
+                .num_args(1)
+                .action(ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("history-file")
+                .help_heading("Output")
+                .long("history-file")
+                .value_name("PATH")
+                .help("Read and write history in this file instead of the default (also SNAPSHELL_HISTORY_FILE)")
+                .num_args(1)
+                .value_parser(clap::value_parser!(std::path::PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("dump-request")
+                .help_heading("Model")
+                .long("dump-request")
+                .help("Write the exact JSON of each API request (keys redacted) to stderr, or append it to =FILE")
+                .value_name("FILE")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("-")
+                .value_parser(clap::value_parser!(std::path::PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("dump-response")
+                .help_heading("Model")
+                .long("dump-response")
+                .help("Write the exact JSON of each API response, error bodies included, to stderr, or append it to =FILE")
+                .value_name("FILE")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("-")
+                .value_parser(clap::value_parser!(std::path::PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("show-reasoning")
+                .help_heading("Model")
+                .short('S')
+                .long("show-reasoning")
+                .help("Include model reasoning in output as a trailing JSON object {\"reasoning\": \"...\"}")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .subcommand(modes::alias::command())
+        .subcommand(crate::audit::command())
+        .subcommand(crate::batch::command())
+        .subcommand(crate::session::command())
+        .subcommand(modes::cheat::command())
+        .subcommand(crate::clipring::command())
+        .subcommand(modes::cloud::command())
+        .subcommand(crate::cost::command())
+        .subcommand(modes::docker::command())
+        .subcommand(crate::doctor::command())
+        .subcommand(crate::dotenv::command())
+        .subcommand(modes::ffmpeg::command())
+        .subcommand(modes::find::command())
+        .subcommand(modes::git::command())
+        .subcommand(crate::history::command())
+        .subcommand(modes::http::command())
+        .subcommand(crate::widget::command())
+        .subcommand(modes::install::command())
+        .subcommand(modes::jq::command())
+        .subcommand(modes::k8s::command())
+        .subcommand(modes::man::command())
+        .subcommand(crate::models::command())
+        .subcommand(crate::runbook::command())
+        .subcommand(crate::selfupdate::command())
+        .subcommand(crate::serve::command())
+        .subcommand(crate::snippet::command())
+        .subcommand(modes::sql::command())
+        .subcommand(crate::status::command())
+        .subcommand(modes::systemd::command())
+        .subcommand(modes::text::command())
+        .subcommand(modes::tf::command())
+        .subcommand(modes::watch::command())
+}
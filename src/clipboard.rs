@@ -0,0 +1,77 @@
+// --from-clipboard: use what was copied (an error message from a browser, a log excerpt)
+// as the prompt, or as context for a typed one. What will be sent is previewed on stderr.
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use std::process::Command;
+
+use crate::context::{estimate_tokens, fit};
+use crate::env::truncate;
+
+// Clipboards larger than this are refused outright rather than trimmed; a copy that big
+// is almost always a mistake.
+const MAX_BYTES: usize = 1024 * 1024;
+// Lines and characters per line shown in the preview.
+const PREVIEW_LINES: usize = 5;
+const PREVIEW_WIDTH: usize = 100;
+
+// Readers tried in order; the first that succeeds wins. On Linux that depends on whether
+// Wayland or X11 is running and which tool is installed.
+const READERS: [(&str, &[&str]); 5] = [
+    ("pbpaste", &[]),
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("powershell", &["-NoProfile", "-Command", "Get-Clipboard"]),
+];
+
+pub fn read() -> Result<String> {
+    for (program, args) in READERS {
+        let Ok(out) = Command::new(program).args(args).output() else {
+            continue;
+        };
+        if out.status.success() {
+            return Ok(String::from_utf8_lossy(&out.stdout).into_owned());
+        }
+    }
+    bail!("could not read the clipboard; install wl-paste (Wayland), xclip or xsel (X11)")
+}
+
+// The prompt to send: the clipboard on its own, or `typed` with the clipboard attached.
+pub fn prompt(matches: &ArgMatches, typed: Option<&str>) -> Result<String> {
+    let text = read()?;
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("the clipboard is empty");
+    }
+    if text.len() > MAX_BYTES {
+        bail!(
+            "the clipboard holds {} bytes, more than the {} allowed with --from-clipboard",
+            text.len(),
+            MAX_BYTES
+        );
+    }
+    let text = fit(matches, "clipboard", text);
+    preview(&text);
+    Ok(match typed {
+        Some(p) => format!("{}\n\nClipboard contents:\n```\n{}\n```", p, text),
+        None => text,
+    })
+}
+
+fn preview(text: &str) {
+    let lines: Vec<&str> = text.lines().collect();
+    eprintln!(
+        "Clipboard: sending {} lines, {} bytes (~{} tokens):",
+        lines.len(),
+        text.len(),
+        estimate_tokens(text)
+    );
+    for line in lines.iter().take(PREVIEW_LINES) {
+        let shown = truncate(line, PREVIEW_WIDTH);
+        let cut = if shown.len() < line.len() { "..." } else { "" };
+        eprintln!("  | {}{}", shown, cut);
+    }
+    if lines.len() > PREVIEW_LINES {
+        eprintln!("  | ({} more lines)", lines.len() - PREVIEW_LINES);
+    }
+}
@@ -0,0 +1,148 @@
+// The last few commands handed to the clipboard or the shell widget, newest first, so
+// one that was overwritten in the system clipboard can be copied again with `ss clip N`.
+// Separate from history: no prompts, duplicates move to the front, and it stays short.
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, Utc};
+use clap::{Arg, ArgMatches, Command};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use crate::config::config;
+use crate::output::copy_to_clipboard;
+use crate::paths::state_dir;
+
+const DEFAULT_SIZE: usize = 20;
+
+#[derive(Serialize, Deserialize)]
+struct Clip {
+    timestamp: String,
+    command: String,
+}
+
+pub fn command() -> Command {
+    Command::new("clip")
+        .about("List recently copied commands, or copy one of them again")
+        .after_help("Examples:\n  ss clip list\n  ss clip 2\n  ss clip clear")
+        .args_conflicts_with_subcommands(true)
+        .arg(
+            Arg::new("number")
+                .help("Copy the command with this number from `ss clip list` (1 is the newest)")
+                .index(1)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .subcommand(Command::new("list").about("List the recent commands, newest first"))
+        .subcommand(Command::new("clear").about("Forget the recent commands"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("clear", _)) => {
+            if let Some(path) = ring_path().filter(|p| p.exists()) {
+                std::fs::remove_file(path)?;
+            }
+            Ok(())
+        }
+        _ => match matches.get_one::<usize>("number") {
+            Some(n) => recopy(*n),
+            None => list(),
+        },
+    }
+}
+
+fn ring_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("clip.json"))
+}
+
+fn size() -> usize {
+    config().clip_ring.unwrap_or(DEFAULT_SIZE)
+}
+
+fn load() -> Vec<Clip> {
+    ring_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+// Put a delivered command at the front. Best effort, like the execution log: a command
+// is never held back over its copy here.
+pub fn push(command: &str) {
+    let size = size();
+    let Some(path) = ring_path().filter(|_| size > 0) else {
+        return;
+    };
+    let attempt = || -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)?;
+        file.lock()?;
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        let mut ring: Vec<Clip> = serde_json::from_str(&text).unwrap_or_default();
+        ring.retain(|c| c.command != command);
+        ring.insert(
+            0,
+            Clip {
+                timestamp: Utc::now().to_rfc3339(),
+                command: command.to_string(),
+            },
+        );
+        ring.truncate(size);
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(serde_json::to_string(&ring)?.as_bytes())?;
+        Ok(())
+    };
+    if let Err(e) = attempt() {
+        eprintln!("Failed to update the clip ring: {}", e);
+    }
+}
+
+fn list() -> Result<()> {
+    let ring = load();
+    if ring.is_empty() {
+        println!("no recent commands");
+        return Ok(());
+    }
+    for (i, c) in ring.iter().enumerate() {
+        let when = DateTime::parse_from_rfc3339(&c.timestamp)
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| c.timestamp.clone());
+        let mut lines = c.command.lines();
+        println!(
+            "{:>2}  {}  {}",
+            i + 1,
+            when,
+            lines.next().unwrap_or_default()
+        );
+        // Further lines of a multi-line command, under the first
+        for line in lines {
+            println!("{:22}{}", "", line);
+        }
+    }
+    Ok(())
+}
+
+// Copied again as is, and printed, so it still reaches you where there is no clipboard.
+// The order is left alone; numbers stay valid until the next new command.
+fn recopy(n: usize) -> Result<()> {
+    let ring = load();
+    let Some(clip) = n.checked_sub(1).and_then(|i| ring.get(i)) else {
+        bail!(
+            "no command #{}; `ss clip list` shows {} recent command(s)",
+            n,
+            ring.len()
+        );
+    };
+    copy_to_clipboard(&clip.command);
+    println!("{}", clip.command);
+    Ok(())
+}
@@ -0,0 +1,166 @@
+// Context collectors from config.toml: shell commands whose output is added to the
+// environment note when the prompt mentions one of their trigger words, so the model
+// sees the current kube context or cloud profile without a dedicated mode.
+//
+//   [context.collectors]
+//   k8s = "kubectl config current-context"            # triggered by the word "k8s"
+//   aws = { command = "aws configure get region", triggers = ["aws", "s3"], ttl = "10m" }
+use clap::ArgMatches;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use crate::config::{config, Collector};
+use crate::env::truncate;
+use crate::llm::parse_duration;
+use crate::paths::cache_dir;
+use crate::snippet;
+
+// Output kept per collector; the note is for facts, not logs.
+const MAX_BYTES: usize = 500;
+// How long a collector's output is reused when it sets no ttl.
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+// How long all triggered collectors together may take; slower ones are left out.
+const BUDGET: Duration = Duration::from_secs(2);
+
+static NOTE: OnceLock<String> = OnceLock::new();
+
+// Run the collectors the prompt triggers, once, before any system prompt is built. The
+// prompt is the positional input of the top-level command or of the subcommand.
+pub fn init(matches: &ArgMatches) {
+    let collectors = &config().context.collectors;
+    if collectors.is_empty() {
+        return;
+    }
+    let sub = matches.subcommand().map(|(_, m)| m).unwrap_or(matches);
+    let Some(input) = sub.try_get_one::<String>("input").ok().flatten() else {
+        return;
+    };
+    let prompt = match input.strip_prefix('@') {
+        Some(_) => snippet::resolve(input).unwrap_or_else(|_| input.clone()),
+        None => input.clone(),
+    };
+    let words: Vec<String> = prompt
+        .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    let triggered: Vec<(&String, &Collector)> = collectors
+        .iter()
+        .filter(|(name, c)| {
+            c.triggers(name)
+                .iter()
+                .any(|t| words.contains(&t.to_lowercase()))
+        })
+        .collect();
+    if triggered.is_empty() {
+        return;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    for (i, (name, c)) in triggered.iter().enumerate() {
+        let tx = tx.clone();
+        let command = c.command().to_string();
+        let ttl = ttl(name, c);
+        std::thread::spawn(move || {
+            let _ = tx.send((i, collect(&command, ttl)));
+        });
+    }
+    drop(tx);
+    let mut outputs: Vec<Option<Option<String>>> = vec![None; triggered.len()];
+    let deadline = std::time::Instant::now() + BUDGET;
+    while let Some(left) = deadline.checked_duration_since(std::time::Instant::now()) {
+        match rx.recv_timeout(left) {
+            Ok((i, out)) => outputs[i] = Some(out),
+            Err(_) => break,
+        }
+    }
+
+    let mut parts = Vec::new();
+    for ((name, _), out) in triggered.iter().zip(outputs) {
+        match out {
+            Some(Some(text)) => parts.push(format!("{}: {}", name, text)),
+            Some(None) => eprintln!("Context: collector {} failed; sending without it.", name),
+            None => eprintln!(
+                "Context: collector {} took longer than {}s; sending without it.",
+                name,
+                BUDGET.as_secs()
+            ),
+        }
+    }
+    if !parts.is_empty() {
+        let _ = NOTE.set(format!(
+            " Context from the user's machine: {}.",
+            parts.join("; ")
+        ));
+    }
+}
+
+// Appended to the environment note; empty when nothing was triggered.
+pub fn note() -> &'static str {
+    NOTE.get().map(|s| s.as_str()).unwrap_or("")
+}
+
+fn ttl(name: &str, c: &Collector) -> Duration {
+    match c.ttl() {
+        None => DEFAULT_TTL,
+        Some(s) => parse_duration(s).unwrap_or_else(|e| {
+            eprintln!(
+                "Context: collector {} has a bad ttl ({}); using 60s.",
+                name, e
+            );
+            DEFAULT_TTL
+        }),
+    }
+}
+
+fn cache_path(command: &str) -> Option<PathBuf> {
+    let key: String = Sha256::digest(command.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    cache_dir().map(|d| d.join("collectors").join(key))
+}
+
+// The command's output, flattened to one capped line: from the cache while it is younger
+// than `ttl`, else from running it. None when it fails or prints nothing.
+fn collect(command: &str, ttl: Duration) -> Option<String> {
+    let path = cache_path(command);
+    if let Some(p) = &path {
+        let fresh = std::fs::metadata(p)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .is_some_and(|age| age < ttl);
+        if fresh {
+            if let Ok(text) = std::fs::read_to_string(p) {
+                return Some(text);
+            }
+        }
+    }
+    let out = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&out.stdout);
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = truncate(&flat, MAX_BYTES).to_string();
+    if text.is_empty() {
+        return None;
+    }
+    if let Some(p) = &path {
+        if let Some(dir) = p.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(p, &text);
+    }
+    Some(text)
+}
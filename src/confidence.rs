@@ -0,0 +1,90 @@
+// Signals that a generated command may be a guess: hedging language in the output,
+// and an optional self-rated confidence score from a second, cheap request.
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::llm::{complete, Settings};
+use crate::output::quiet;
+
+// Scores below this are reported as low confidence.
+pub const LOW_CONFIDENCE: u8 = 60;
+
+const HEDGES: [&str; 14] = [
+    "might",
+    "may need",
+    "may not",
+    "depending on",
+    "depends on",
+    "probably",
+    "not sure",
+    "assuming",
+    "if your version",
+    "should work",
+    "untested",
+    "replace with",
+    "adjust as needed",
+    "i think",
+];
+
+// Hedging phrases found in the output, matched on word boundaries.
+pub fn detect_hedges(text: &str) -> Vec<&'static str> {
+    let lower = text.to_lowercase();
+    HEDGES
+        .iter()
+        .copied()
+        .filter(|h| {
+            lower.match_indices(h).any(|(i, _)| {
+                let before = lower[..i].chars().next_back();
+                let after = lower[i + h.len()..].chars().next();
+                !before.map(|c| c.is_alphanumeric()).unwrap_or(false)
+                    && !after.map(|c| c.is_alphanumeric()).unwrap_or(false)
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+pub struct Rating {
+    pub confidence: u8,
+    pub concern: Option<String>,
+}
+
+// Ask the model to rate the command it produced. Runs at low effort to stay cheap;
+// returns None if the request fails or the answer does not parse.
+pub async fn rate(settings: &Settings, task: &str, command: &str, env: &str) -> Option<Rating> {
+    let mut cheap = settings.clone();
+    cheap.effort = "low".to_string();
+    cheap.retry_not_able = false;
+    let messages: Vec<JsonValue> = vec![
+        serde_json::json!({"role": "system", "content": "You review shell commands. Rate from 0 to 100 how confident you are that the command correctly and safely accomplishes the task in the given environment. Reply ONLY with JSON: {\"confidence\": <0-100>, \"concern\": \"<main doubt in one short sentence, or null>\"}"}),
+        serde_json::json!({"role": "user", "content": format!("Environment: {}\nTask: {}\nCommand:\n{}", env, task, command)}),
+    ];
+    let c = complete(&cheap, &messages).await.ok()?;
+    let start = c.content.find('{')?;
+    let end = c.content.rfind('}')?;
+    serde_json::from_str(c.content.get(start..=end)?).ok()
+}
+
+// Warnings go to stderr so stdout stays the command.
+pub fn warn_hedges(hedges: &[&str]) {
+    if !hedges.is_empty() && !quiet() {
+        eprintln!(
+            "Warning: the answer hedges ({}); double-check it, or retry with --reasoning high.",
+            hedges.join(", ")
+        );
+    }
+}
+
+pub fn warn_rating(r: &Rating) {
+    if r.confidence >= LOW_CONFIDENCE || quiet() {
+        return;
+    }
+    eprintln!(
+        "Warning: low confidence ({}/100){}. Consider --reasoning high or --retry with a --fallback-model.",
+        r.confidence,
+        r.concern
+            .as_deref()
+            .map(|c| format!(": {}", c))
+            .unwrap_or_default()
+    );
+}
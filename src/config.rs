@@ -0,0 +1,321 @@
+// User settings from config.toml in the OS config dir. Anything here is subordinate to
+// the administrator policy (see policy.rs).
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::paths::config_dir;
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub commands: CommandRules,
+    // Quoting and injection checks on generated commands (default: on)
+    pub lint: Option<bool>,
+    pub redact: RedactConfig,
+    pub openrouter: OpenRouterConfig,
+    // API keys tried after SNAPSHELL_OPENROUTER_API_KEY, in order
+    pub keys: Vec<String>,
+    // Named key sets selected with --profile or SNAPSHELL_PROFILE
+    pub profiles: BTreeMap<String, Profile>,
+    // Tools --tools may offer the model (default: all of them)
+    pub tools: Option<Vec<String>>,
+    // Default for --max-wait ("3s"), and the model used when it runs out
+    pub max_wait: Option<String>,
+    pub fast_model: Option<String>,
+    // Default for --max-context-tokens
+    pub max_context_tokens: Option<usize>,
+    // Defaults for --max-tokens (response) and --max-prompt-tokens (whole request)
+    pub max_tokens: Option<u32>,
+    pub max_prompt_tokens: Option<usize>,
+    // Default for --lang; otherwise the locale decides
+    pub lang: Option<String>,
+    // Instructions appended to every built-in system prompt, before --system-append
+    pub system_extra: Option<String>,
+    // Refuse paid models, like --free-only
+    pub free_only: bool,
+    // Model per mode (chat, single, multiline, or a subcommand name such as git), used
+    // when -m is not given
+    pub models: BTreeMap<String, String>,
+    // Few-shot prompt -> command pairs sent ahead of the prompt in command generation
+    pub examples: Vec<Example>,
+    // Chat personas by name (added to, or replacing, the built-in ones), and the one
+    // used when --persona is not given
+    pub personas: BTreeMap<String, String>,
+    pub persona: Option<String>,
+    // Record commands run with --exec in atuin's history too (see atuin.rs)
+    pub atuin: bool,
+    // Keep the end of --exec runs' stderr in the execution log (see exec.rs)
+    pub capture_stderr: bool,
+    // Send a digest of recent history with each request (see personalize.rs)
+    pub personalize: bool,
+    // Team registry (https URL or git repository) pulled by `ss snippet sync`
+    pub registry: Option<String>,
+    // API requests per minute across all running `ss` processes (see ratelimit.rs)
+    pub rate_limit: Option<u32>,
+    // How many responses the completion cache keeps; 0 turns it off (see cache.rs)
+    pub response_cache: Option<usize>,
+    // How many delivered commands `ss clip` keeps; 0 turns it off (see clipring.rs)
+    pub clip_ring: Option<usize>,
+    // When to stop trying a failing model for a while (see breaker.rs)
+    pub breaker: BreakerConfig,
+    pub context: ContextConfig,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct BreakerConfig {
+    // Failures within `window` that open the circuit (default 3; 0 turns it off)
+    pub failures: Option<u32>,
+    // Durations such as "2m" (default) and "5m" (default cooldown)
+    pub window: Option<String>,
+    pub cooldown: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContextConfig {
+    // Commands whose output joins the environment note when triggered (see collectors.rs)
+    pub collectors: BTreeMap<String, Collector>,
+}
+
+// A collector is a bare command, triggered by its own name, or a table with its own
+// trigger words and cache lifetime.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Collector {
+    Command(String),
+    Full(CollectorConfig),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CollectorConfig {
+    pub command: String,
+    #[serde(default)]
+    pub triggers: Vec<String>,
+    // How long the output is reused ("30s", "10m"; default 60s)
+    pub ttl: Option<String>,
+}
+
+impl Collector {
+    pub fn command(&self) -> &str {
+        match self {
+            Collector::Command(c) => c,
+            Collector::Full(c) => &c.command,
+        }
+    }
+
+    pub fn triggers(&self, name: &str) -> Vec<String> {
+        match self {
+            Collector::Full(c) if !c.triggers.is_empty() => c.triggers.clone(),
+            _ => vec![name.to_string()],
+        }
+    }
+
+    pub fn ttl(&self) -> Option<&str> {
+        match self {
+            Collector::Command(_) => None,
+            Collector::Full(c) => c.ttl.as_deref(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Example {
+    pub prompt: String,
+    pub command: String,
+    // Only for multiline (true) or single-line (false) generation; both when unset
+    pub multiline: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub keys: Vec<String>,
+}
+
+// Request headers for OpenRouter app attribution and for gateways in front of it.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenRouterConfig {
+    // Sent as HTTP-Referer; OpenRouter lists the app under this URL
+    pub referer: Option<String>,
+    // Sent as X-Title (default: snapshell; empty to omit)
+    pub title: Option<String>,
+    // Extra headers added to every request (tenant ids, gateway auth, ...)
+    pub headers: BTreeMap<String, String>,
+}
+
+// Masking of identifying details in outbound prompts (see redact.rs).
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactConfig {
+    pub enabled: bool,
+    // Internal domains whose hostnames are masked, in addition to .internal, .corp, ...
+    pub domains: Vec<String>,
+    // Extra literal strings to mask (project codenames, customer names, ...)
+    pub terms: Vec<String>,
+}
+
+// Regexes matched against generated commands. With a non-empty `allow`, a command must
+// match one of them; any `deny` match refuses it.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommandRules {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+pub struct CompiledRules {
+    pub allow: Vec<Regex>,
+    pub deny: Vec<Regex>,
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|d| d.join("config.toml"))
+}
+
+fn load() -> Result<Config> {
+    let path = match config_path() {
+        Some(p) if p.exists() => p,
+        _ => return Ok(Config::default()),
+    };
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("cannot read config file {}", path.display()))?;
+    let mut value: toml::Value =
+        toml::from_str(&text).with_context(|| format!("invalid config file {}", path.display()))?;
+    expand_value(&mut value, "", &path);
+    value
+        .try_into()
+        .with_context(|| format!("invalid config file {}", path.display()))
+}
+
+// Expand `${VAR}` in every string value, so one checked-in team config can carry
+// per-machine endpoints, headers, and keys. `key` is the dotted path, for warnings. An
+// unset variable only warns: the text is kept as written, since it may be a shell
+// command or hint that means `${VAR}` literally, and the rest of the config still loads.
+fn expand_value(value: &mut toml::Value, key: &str, path: &Path) {
+    match value {
+        toml::Value::String(s) => {
+            let (expanded, unset) = expand_env(s);
+            for name in unset {
+                eprintln!(
+                    "Warning: ${{{}}} is not set; `{}` in {} keeps it as written.",
+                    name,
+                    key,
+                    path.display()
+                );
+            }
+            *s = expanded;
+        }
+        toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                expand_value(item, &format!("{}[{}]", key, i), path);
+            }
+        }
+        toml::Value::Table(table) => {
+            for (k, v) in table.iter_mut() {
+                let child = if key.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", key, k)
+                };
+                expand_value(v, &child, path);
+            }
+        }
+        _ => {}
+    }
+}
+
+// `${VAR}` is the variable's value, `${VAR:-default}` falls back when it is unset or
+// empty, and `$${` is a literal `${`. A bare `$` is left alone, so regexes still work, as
+// is anything in braces that is not a variable name. Returns the text and the names of
+// unset variables without a default, which are left as written.
+fn expand_env(s: &str) -> (String, Vec<String>) {
+    let mut unset = Vec::new();
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        if let Some(literal) = after.strip_prefix("${") {
+            out.push_str("${");
+            rest = literal;
+            continue;
+        }
+        let Some(body) = after.strip_prefix('{') else {
+            out.push('$');
+            rest = after;
+            continue;
+        };
+        let Some(end) = body.find('}') else {
+            out.push('$');
+            rest = after;
+            continue;
+        };
+        let (name, default) = match body[..end].split_once(":-") {
+            Some((n, d)) => (n, Some(d)),
+            None => (&body[..end], None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            out.push('$');
+            rest = after;
+            continue;
+        }
+        match (std::env::var(name).ok().filter(|v| !v.is_empty()), default) {
+            (Some(v), _) => out.push_str(&v),
+            (None, Some(d)) => out.push_str(d),
+            (None, None) => {
+                out.push_str(&format!("${{{}}}", &body[..end]));
+                unset.push(name.to_string());
+            }
+        }
+        rest = &body[end + 1..];
+    }
+    out.push_str(rest);
+    (out, unset)
+}
+
+fn fatal<T>(result: Result<T>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    })
+}
+
+// Parse the config and its patterns without exiting, for `ss doctor`.
+pub fn check() -> Result<()> {
+    let config = load()?;
+    compile(&config.commands.allow, "allow")?;
+    compile(&config.commands.deny, "deny")?;
+    Ok(())
+}
+
+// Loaded once per process; a broken config file is reported rather than ignored.
+pub fn config() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(|| fatal(load()))
+}
+
+fn compile(patterns: &[String], key: &str) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(p).with_context(|| format!("invalid commands.{} pattern `{}`", key, p)))
+        .collect()
+}
+
+pub fn command_rules() -> &'static CompiledRules {
+    static RULES: OnceLock<CompiledRules> = OnceLock::new();
+    RULES.get_or_init(|| {
+        let rules = &config().commands;
+        CompiledRules {
+            allow: fatal(compile(&rules.allow, "allow")),
+            deny: fatal(compile(&rules.deny, "deny")),
+        }
+    })
+}
@@ -0,0 +1,189 @@
+// Size accounting for the context modes attach to a request (diffs, samples, schemas, docs).
+use clap::ArgMatches;
+use std::io::IsTerminal;
+use std::time::Duration;
+
+use crate::config::config;
+use crate::llm::{warm_up, Settings};
+
+pub const DEFAULT_MAX_CONTEXT_TOKENS: usize = 8000;
+// Prompts smaller than this go out without a size note.
+const PREVIEW_MIN_TOKENS: usize = 1000;
+// How long all context probes together may take; any still running are left out.
+const GATHER_BUDGET: Duration = Duration::from_secs(2);
+
+// A named piece of context gathering, typically a few CLI calls.
+pub type Probe = (&'static str, Box<dyn FnOnce() -> String + Send>);
+
+// Approximate a BPE tokenizer like cl100k: runs of letters and digits cost about one token
+// per four characters, and each punctuation or symbol character costs one.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut word = 0usize;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word += 1;
+            continue;
+        }
+        tokens += word.div_ceil(4);
+        word = 0;
+        if !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + word.div_ceil(4)
+}
+
+// --max-context-tokens, then the config default.
+pub fn max_tokens(matches: &ArgMatches) -> usize {
+    matches
+        .get_one::<usize>("max-context-tokens")
+        .copied()
+        .or(config().max_context_tokens)
+        .unwrap_or(DEFAULT_MAX_CONTEXT_TOKENS)
+}
+
+// Keep whole lines of an attachment until the token budget is spent, and say on stderr
+// exactly how much of it was left out.
+pub fn fit(matches: &ArgMatches, label: &str, text: &str) -> String {
+    fit_lines(matches, label, text, false)
+}
+
+// `fit` for logs, where the end matters most: keeps the last lines instead of the first.
+pub fn fit_tail(matches: &ArgMatches, label: &str, text: &str) -> String {
+    fit_lines(matches, label, text, true)
+}
+
+fn fit_lines(matches: &ArgMatches, label: &str, text: &str, from_end: bool) -> String {
+    let max = max_tokens(matches);
+    let total = estimate_tokens(text);
+    if total <= max {
+        return text.to_string();
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut kept = 0;
+    let mut used = 0;
+    let ordered: Box<dyn Iterator<Item = &&str>> = if from_end {
+        Box::new(lines.iter().rev())
+    } else {
+        Box::new(lines.iter())
+    };
+    for line in ordered {
+        let cost = estimate_tokens(line) + 1;
+        if used + cost > max {
+            break;
+        }
+        used += cost;
+        kept += 1;
+    }
+
+    let marker = format!("... ({} truncated)", label);
+    if kept == 0 {
+        // A single overlong line: cut it at roughly the budget's worth of characters.
+        let out = if from_end {
+            tail(text, max * 4)
+        } else {
+            crate::env::truncate(text, max * 4)
+        };
+        used = estimate_tokens(out);
+        eprintln!(
+            "Context: {} is ~{} tokens on one line; sent the {} {} of {} bytes (~{} tokens, limit {}; raise with --max-context-tokens).",
+            label,
+            total,
+            if from_end { "last" } else { "first" },
+            out.len(),
+            text.len(),
+            used,
+            max
+        );
+        return if from_end {
+            format!("{}\n{}", marker, out)
+        } else {
+            format!("{}\n{}", out, marker)
+        };
+    }
+    let (first, last) = if from_end {
+        (lines.len() - kept + 1, lines.len())
+    } else {
+        (1, kept)
+    };
+    eprintln!(
+        "Context: {} is ~{} tokens; sent lines {}-{} of {} and dropped {} (~{} tokens, limit {}; raise with --max-context-tokens).",
+        label,
+        total,
+        first,
+        last,
+        lines.len(),
+        lines.len() - kept,
+        used,
+        max
+    );
+    let out = lines[first - 1..last].join("\n");
+    if from_end {
+        format!("{}\n{}", marker, out)
+    } else {
+        format!("{}\n{}", out, marker)
+    }
+}
+
+// The last `max` bytes of `s`, starting on a character boundary.
+pub fn tail(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut start = s.len() - max;
+    while !s.is_char_boundary(start) {
+        start += 1;
+    }
+    &s[start..]
+}
+
+// Note the size of a large prompt on stderr before it is sent.
+pub fn preview(messages: &[serde_json::Value]) {
+    let tokens: usize = messages
+        .iter()
+        .filter_map(|m| m["content"].as_str())
+        .map(estimate_tokens)
+        .sum();
+    if tokens >= PREVIEW_MIN_TOKENS && std::io::stderr().is_terminal() {
+        eprintln!("Prompt: ~{} tokens.", tokens);
+    }
+}
+
+// Run the probes concurrently while the connection to the API is opened, and join their
+// output in the order given. Probes run on plain threads so a hung CLI cannot hold up exit.
+pub async fn gather(settings: &Settings, probes: Vec<Probe>) -> String {
+    warm_up(settings);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let names: Vec<&str> = probes.iter().map(|(name, _)| *name).collect();
+    for (i, (_, probe)) in probes.into_iter().enumerate() {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send((i, probe()));
+        });
+    }
+    drop(tx);
+
+    let mut parts: Vec<Option<String>> = vec![None; names.len()];
+    let deadline = tokio::time::Instant::now() + GATHER_BUDGET;
+    while let Ok(Some((i, out))) = tokio::time::timeout_at(deadline, rx.recv()).await {
+        parts[i] = Some(out);
+    }
+
+    let slow: Vec<&str> = names
+        .iter()
+        .zip(&parts)
+        .filter(|(_, p)| p.is_none())
+        .map(|(name, _)| *name)
+        .collect();
+    if !slow.is_empty() {
+        eprintln!(
+            "Context: {} took longer than {}s; sending without it.",
+            slow.join(", "),
+            GATHER_BUDGET.as_secs()
+        );
+    }
+    parts.into_iter().flatten().collect()
+}
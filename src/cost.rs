@@ -0,0 +1,84 @@
+// `ss cost`: token usage and spend per API key, summed from the audit log.
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use clap::{value_parser, Arg, ArgMatches, Command};
+use std::collections::BTreeMap;
+
+use crate::audit::read_entries;
+
+pub fn command() -> Command {
+    Command::new("cost")
+        .about("Show requests, tokens, and spend per API key from the audit log")
+        .after_help("Examples:\n  ss cost\n  ss cost --days 7")
+        .arg(
+            Arg::new("days")
+                .long("days")
+                .help("Only count requests from the last N days")
+                .num_args(1)
+                .value_parser(value_parser!(i64)),
+        )
+}
+
+#[derive(Default)]
+struct Totals {
+    requests: u64,
+    failed: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    cost: f64,
+}
+
+impl Totals {
+    fn row(&self, label: &str) -> String {
+        format!(
+            "{:<12} {:>8} {:>7} {:>12} {:>12} {:>10.4}",
+            label,
+            self.requests,
+            self.failed,
+            self.prompt_tokens,
+            self.completion_tokens,
+            self.cost
+        )
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let since = matches
+        .get_one::<i64>("days")
+        .map(|d| Utc::now() - Duration::days(*d));
+    let mut by_key: BTreeMap<String, Totals> = BTreeMap::new();
+    let mut total = Totals::default();
+    for e in read_entries()? {
+        if let Some(since) = since {
+            let when = DateTime::parse_from_rfc3339(&e.timestamp).map(|t| t.with_timezone(&Utc));
+            if when.map(|t| t < since).unwrap_or(true) {
+                continue;
+            }
+        }
+        let key = e.key.clone().unwrap_or_else(|| "?".to_string());
+        for t in [by_key.entry(key).or_default(), &mut total] {
+            t.requests += 1;
+            if e.status != "ok" {
+                t.failed += 1;
+            }
+            t.prompt_tokens += e.prompt_tokens.unwrap_or(0);
+            t.completion_tokens += e.completion_tokens.unwrap_or(0);
+            t.cost += e.cost.unwrap_or(0.0);
+        }
+    }
+    if total.requests == 0 {
+        println!("no requests recorded");
+        return Ok(());
+    }
+    println!(
+        "{:<12} {:>8} {:>7} {:>12} {:>12} {:>10}",
+        "key", "requests", "failed", "tokens in", "tokens out", "credits"
+    );
+    for (key, t) in &by_key {
+        println!("{}", t.row(key));
+    }
+    if by_key.len() > 1 {
+        println!("{}", total.row("total"));
+    }
+    Ok(())
+}
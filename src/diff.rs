@@ -0,0 +1,86 @@
+// Word-level diff used to show what changed between two generated commands.
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+// Longest-common-subsequence table over two token lists; commands are short, so O(n*m) is fine.
+fn lcs(old: &[&str], new: &[&str]) -> Vec<Vec<usize>> {
+    let mut t = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            t[i][j] = if old[i] == new[j] {
+                t[i + 1][j + 1] + 1
+            } else {
+                t[i + 1][j].max(t[i][j + 1])
+            };
+        }
+    }
+    t
+}
+
+// Split into alternating runs of whitespace and non-whitespace, so joining the tokens
+// reproduces the input exactly.
+fn tokens(s: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in s.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some() && in_space != Some(space) {
+            out.push(&s[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+    if start < s.len() {
+        out.push(&s[start..]);
+    }
+    out
+}
+
+enum Op<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+fn ops<'a>(old: &'a str, new: &'a str) -> Vec<Op<'a>> {
+    let a = tokens(old);
+    let b = tokens(new);
+    let t = lcs(&a, &b);
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(Op::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || t[i + 1][j] >= t[i][j + 1]) {
+            out.push(Op::Removed(a[i]));
+            i += 1;
+        } else {
+            out.push(Op::Added(b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+// Colored when stderr is a terminal with color (and NO_COLOR and --plain are unset),
+// otherwise git's `--word-diff=plain` markers: [-removed-]{+added+}.
+pub fn render(old: &str, new: &str) -> String {
+    let color = crate::term::stderr_color();
+    let mut out = String::new();
+    for op in ops(old, new) {
+        match op {
+            Op::Same(s) => out.push_str(s),
+            Op::Removed(s) if color => out.push_str(&format!("{}{}{}", RED, s, RESET)),
+            Op::Added(s) if color => out.push_str(&format!("{}{}{}", GREEN, s, RESET)),
+            Op::Removed(s) => out.push_str(&format!("[-{}-]", s)),
+            Op::Added(s) => out.push_str(&format!("{{+{}+}}", s)),
+        }
+    }
+    out.push('\n');
+    out
+}
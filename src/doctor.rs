@@ -0,0 +1,277 @@
+// `ss doctor`: check the pieces snapshell depends on and say how to fix each one that
+// is broken. It runs before the config and policy are loaded, so it can report a file
+// that would otherwise stop every other command.
+use anyhow::Result;
+use clap::{ArgMatches, Command};
+
+use crate::config::{self, config_path};
+use crate::env::{runtime, which};
+use crate::history::history_path;
+use crate::llm::{key_label, ping, Settings};
+use crate::modes::alias::{detect_shell, rc_file};
+use crate::policy::{self, policy_path};
+use crate::{dotenv, term};
+
+pub fn command() -> Command {
+    Command::new("doctor").about(
+        "Check config, policy, API keys, clipboard, and shell integration, with fixes for failures",
+    )
+        .after_help("Examples:\n  ss doctor")
+}
+
+#[derive(Default)]
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn ok(&self, what: &str, detail: &str) {
+        println!("ok    {}: {}", what, detail);
+    }
+
+    fn warn(&self, what: &str, detail: &str, fix: &str) {
+        println!("warn  {}: {}", what, detail);
+        println!("      fix: {}", fix);
+    }
+
+    fn fail(&mut self, what: &str, detail: &str, fix: &str) {
+        self.failures += 1;
+        println!("FAIL  {}: {}", what, detail);
+        println!("      fix: {}", fix);
+    }
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let mut report = Report::default();
+
+    let policy_ok = match policy::check() {
+        Ok(()) if policy_path().exists() => {
+            report.ok("policy", &policy_path().display().to_string());
+            true
+        }
+        Ok(()) => {
+            report.ok("policy", "none installed");
+            true
+        }
+        Err(e) => {
+            report.fail(
+                "policy",
+                &format!("{:#}", e),
+                "ask your administrator to correct the policy file; snapshell will not run until it parses",
+            );
+            false
+        }
+    };
+
+    let path = config_path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "no config directory".to_string());
+    let config_ok = match config::check() {
+        Ok(()) if config_path().map(|p| p.exists()).unwrap_or(false) => {
+            report.ok("config", &path);
+            true
+        }
+        Ok(()) => {
+            report.ok("config", &format!("{} not present, using defaults", path));
+            true
+        }
+        Err(e) => {
+            report.fail(
+                "config",
+                &format!("{:#}", e),
+                &format!(
+                    "correct the line named above in {}, or move the file aside",
+                    path
+                ),
+            );
+            false
+        }
+    };
+
+    let env_files = dotenv::loaded();
+    if !env_files.is_empty() {
+        let names: Vec<String> = env_files.iter().map(|p| p.display().to_string()).collect();
+        report.ok("env files", &names.join(", "));
+    }
+    let untrusted = dotenv::untrusted();
+    if !untrusted.is_empty() {
+        let names: Vec<String> = untrusted.iter().map(|p| p.display().to_string()).collect();
+        report.warn(
+            "project env files",
+            &format!("not read: {}", names.join(", ")),
+            "run `ss env allow` to use their API key and model variables",
+        );
+    }
+
+    if policy_ok && config_ok {
+        check_keys(&mut report, matches).await;
+    } else {
+        report.warn(
+            "api keys",
+            "skipped",
+            "fix the config and policy files first",
+        );
+    }
+
+    check_clipboard(&report);
+    check_terminal(&report);
+    check_widget(&report);
+
+    match history_path() {
+        Some(p) => {
+            let dir = p.parent().map(|d| d.to_path_buf()).unwrap_or_default();
+            match std::fs::create_dir_all(&dir) {
+                Ok(()) => report.ok("history", &p.display().to_string()),
+                Err(e) => report.fail(
+                    "history",
+                    &format!("cannot create {}: {}", dir.display(), e),
+                    &format!("make {} writable by your user", dir.display()),
+                ),
+            }
+        }
+        None => report.fail(
+            "history",
+            "no home directory found",
+            "set HOME so snapshell can find its data directory",
+        ),
+    }
+
+    if which("shellcheck").is_some() {
+        report.ok("lint", "shellcheck found");
+    } else {
+        report.ok(
+            "lint",
+            "shellcheck not found; using the built-in quoting checks",
+        );
+    }
+
+    if report.failures > 0 {
+        println!("{} problem(s) found.", report.failures);
+        std::process::exit(1);
+    }
+    println!("No problems found.");
+    Ok(())
+}
+
+// Every configured key gets a one-token request with the default model.
+async fn check_keys(report: &mut Report, matches: &ArgMatches) {
+    let settings = Settings::from_matches(matches);
+    if settings.api_keys.is_empty() {
+        report.fail(
+            "api key",
+            "none configured",
+            "export SNAPSHELL_OPENROUTER_API_KEY=sk-or-... or add keys = [\"...\"] to config.toml",
+        );
+        return;
+    }
+    if settings.offline {
+        report.warn(
+            "api keys",
+            "offline mode is on; not contacting OpenRouter",
+            "unset SNAPSHELL_OFFLINE to check the keys",
+        );
+        return;
+    }
+    for key in &settings.api_keys {
+        let what = format!("api key {}", key_label(key));
+        match ping(key, &settings.model).await {
+            Ok(()) => report.ok(&what, &format!("answered with {}", settings.model)),
+            Err(e) => {
+                let status = e
+                    .downcast_ref::<reqwest::Error>()
+                    .and_then(|e| e.status())
+                    .map(|s| s.as_u16());
+                let fix = match status {
+                    Some(401) | Some(403) => {
+                        "the key was rejected; create a new one at https://openrouter.ai/keys"
+                            .to_string()
+                    }
+                    Some(402) => "the account is out of credit; top up at https://openrouter.ai/credits".to_string(),
+                    Some(429) => "rate limited; wait a minute or add a second key".to_string(),
+                    Some(400) | Some(404) => format!(
+                        "the model {} may not exist; pick another with -m or SNAPSHELL_OPENROUTER_MODEL",
+                        settings.model
+                    ),
+                    Some(_) => "OpenRouter returned an error; try again later".to_string(),
+                    None => "check your network connection, proxy settings, and the policy's allowed_endpoints".to_string(),
+                };
+                report.fail(&what, &format!("{:#}", e), &fix);
+            }
+        }
+    }
+}
+
+fn check_clipboard(report: &Report) {
+    let runtime = runtime();
+    if runtime.ssh {
+        report.ok(
+            "clipboard",
+            "over SSH: sent to your terminal with OSC 52 (the terminal must allow it)",
+        );
+    } else if runtime.wsl {
+        if which("clip.exe").is_some() {
+            report.ok("clipboard", "clip.exe (WSL)");
+        } else {
+            report.warn(
+                "clipboard",
+                "clip.exe not found under WSL",
+                "enable Windows interop (appendWindowsPath in /etc/wsl.conf), or use the shell widget (ss init)",
+            );
+        }
+    } else if cfg!(target_os = "macos") {
+        if which("pbcopy").is_some() {
+            report.ok("clipboard", "pbcopy");
+        } else {
+            report.warn(
+                "clipboard",
+                "pbcopy not found",
+                "restore /usr/bin/pbcopy, or use the shell widget (ss init)",
+            );
+        }
+    } else {
+        report.warn(
+            "clipboard",
+            "copying is supported on macOS, under WSL, and over SSH (OSC 52); commands are printed",
+            "use the shell widget (ss init) to get commands into your prompt",
+        );
+    }
+}
+
+fn check_terminal(report: &Report) {
+    let caps = term::caps();
+    let width = match caps.width {
+        Some(w) => format!("{} columns", w),
+        None => "unknown width".to_string(),
+    };
+    let links = if caps.hyperlinks {
+        "hyperlinks (--hyperlinks)"
+    } else {
+        "no hyperlinks"
+    };
+    report.ok(
+        "terminal",
+        &format!("{}, {}, {}", width, caps.colors.describe(), links),
+    );
+}
+
+fn check_widget(report: &Report) {
+    let shell = detect_shell();
+    let Some(rc) = rc_file(&shell) else {
+        return;
+    };
+    let text = std::fs::read_to_string(&rc).unwrap_or_default();
+    if text.contains("snapshell init") || text.contains("ss init") {
+        report.ok("shell widget", &format!("installed in {}", rc.display()));
+    } else {
+        let line = if shell == "fish" {
+            "snapshell init fish | source".to_string()
+        } else {
+            format!("eval \"$(snapshell init {})\"", shell)
+        };
+        report.warn(
+            "shell widget",
+            &format!("not found in {}", rc.display()),
+            &format!("add `{}` to {} for Alt-s", line, rc.display()),
+        );
+    }
+}
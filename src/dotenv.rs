@@ -0,0 +1,293 @@
+// Settings and API keys from env files, so they need not live in a global shell profile.
+// Real environment variables always win. `.env` in the snapshell config dir is yours and
+// is always read. Files in a project (`.snapshell.env`, then `.env`, in the current
+// directory or a parent up to the git root, nearest first) come with whatever repository
+// was cloned, so they are read only after `ss env allow` and only for the API key and
+// models. Allowing records the file's hash; after any change it must be allowed again.
+use anyhow::Result;
+use clap::{ArgMatches, Command};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::output::quiet;
+use crate::paths::{config_dir, state_dir};
+
+// All a project file may set. Anything else (system prompts, preambles, the history
+// file, PATH, LD_PRELOAD) would let a repository steer every generated command.
+const PROJECT_VARS: [&str; 4] = [
+    "SNAPSHELL_OPENROUTER_API_KEY",
+    "SNAPSHELL_OPENROUTER_MODEL",
+    "SNAPSHELL_FAST_MODEL",
+    "SNAPSHELL_FALLBACK_MODEL",
+];
+const PROJECT_FILES: [&str; 2] = [".snapshell.env", ".env"];
+
+static LOADED: OnceLock<Vec<PathBuf>> = OnceLock::new();
+static UNTRUSTED: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+pub fn command() -> Command {
+    Command::new("env")
+        .about("Show which env files are read, or allow a project's .snapshell.env and .env")
+        .after_help("Examples:\n  ss env\n  ss env allow\n  ss env revoke")
+        .subcommand(Command::new("status").about("List the env files found and whether they are read (the default)"))
+        .subcommand(Command::new("allow").about(
+            "Read the project env files found from here as they are now (API key and model variables only)",
+        ))
+        .subcommand(Command::new("revoke").about("Stop reading the project env files found from here"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let files = project_files();
+    match matches.subcommand() {
+        Some(("allow", _)) => {
+            if files.is_empty() {
+                println!("no project env files here");
+                return Ok(());
+            }
+            let mut trusted = load_trusted();
+            for path in &files {
+                let Some(hash) = hash_file(path) else {
+                    continue;
+                };
+                trusted.insert(path.display().to_string(), hash);
+                let names: Vec<String> = read_vars(path)
+                    .into_iter()
+                    .map(|(k, _)| k)
+                    .filter(|k| PROJECT_VARS.contains(&k.as_str()))
+                    .collect();
+                println!(
+                    "allowed {} ({})",
+                    path.display(),
+                    if names.is_empty() {
+                        "sets nothing snapshell reads from project files".to_string()
+                    } else {
+                        names.join(", ")
+                    }
+                );
+            }
+            store_trusted(&trusted)
+        }
+        Some(("revoke", _)) => {
+            let mut trusted = load_trusted();
+            for path in &files {
+                if trusted.remove(&path.display().to_string()).is_some() {
+                    println!("revoked {}", path.display());
+                }
+            }
+            store_trusted(&trusted)
+        }
+        _ => {
+            let trusted = load_trusted();
+            if let Some(path) = config_dir().map(|d| d.join(".env")).filter(|p| p.exists()) {
+                println!("{}  read (config dir)", path.display());
+            }
+            for path in &files {
+                let state = if is_trusted(&trusted, path) {
+                    "read (API key and model only)"
+                } else {
+                    "not read; run `ss env allow`"
+                };
+                println!("{}  {}", path.display(), state);
+            }
+            Ok(())
+        }
+    }
+}
+
+// Set every variable the files may set that is not already set. Runs first thing in
+// main, before anything reads the environment.
+pub fn load() {
+    let mut loaded = Vec::new();
+    let mut untrusted = Vec::new();
+    let trusted = load_trusted();
+    for path in project_files() {
+        if !is_trusted(&trusted, &path) {
+            // Most projects have a .env for themselves; only mention ones meant for us.
+            if read_vars(&path)
+                .iter()
+                .any(|(k, _)| PROJECT_VARS.contains(&k.as_str()))
+            {
+                untrusted.push(path);
+            }
+        } else if apply(&path, Some(&PROJECT_VARS)) {
+            loaded.push(path);
+        }
+    }
+    if let Some(path) = config_dir().map(|d| d.join(".env")) {
+        if apply(&path, None) {
+            loaded.push(path);
+        }
+    }
+    let _ = LOADED.set(loaded);
+    let _ = UNTRUSTED.set(untrusted);
+}
+
+// Files that set something, for `ss doctor`.
+pub fn loaded() -> &'static [PathBuf] {
+    LOADED.get().map(|v| v.as_slice()).unwrap_or_default()
+}
+
+// Project files with an API key or model that were skipped because they are not allowed
+// (or changed since).
+pub fn untrusted() -> &'static [PathBuf] {
+    UNTRUSTED.get().map(|v| v.as_slice()).unwrap_or_default()
+}
+
+// Once output flags are known: say why a project's key or model is not being used.
+pub fn note_untrusted() {
+    let files = untrusted();
+    if files.is_empty() || quiet() {
+        return;
+    }
+    let names: Vec<String> = files.iter().map(|p| p.display().to_string()).collect();
+    eprintln!(
+        "Note: not reading {} (new or changed); run `ss env allow` to use its API key and model.",
+        names.join(", ")
+    );
+}
+
+// Project env files from the current directory up, nearest first.
+fn project_files() -> Vec<PathBuf> {
+    let Ok(cwd) = std::env::current_dir() else {
+        return Vec::new();
+    };
+    let dirs = project_dirs(&cwd);
+    let mut files = Vec::new();
+    for name in PROJECT_FILES {
+        for d in &dirs {
+            let path = d.join(name);
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+// The current directory and its parents up to the repository root; outside a
+// repository, only the current directory.
+fn project_dirs(cwd: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for d in cwd.ancestors() {
+        dirs.push(d.to_path_buf());
+        if d.join(".git").exists() {
+            return dirs;
+        }
+    }
+    dirs.truncate(1);
+    dirs
+}
+
+fn trust_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("env-allow.json"))
+}
+
+// Allowed file path -> SHA-256 of its contents when it was allowed.
+fn load_trusted() -> BTreeMap<String, String> {
+    trust_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn store_trusted(trusted: &BTreeMap<String, String>) -> Result<()> {
+    let Some(path) = trust_path() else {
+        anyhow::bail!("could not determine the state directory");
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(trusted)? + "\n")?;
+    Ok(())
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    Some(
+        Sha256::digest(&bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    )
+}
+
+fn is_trusted(trusted: &BTreeMap<String, String>, path: &Path) -> bool {
+    trusted
+        .get(&path.display().to_string())
+        .is_some_and(|h| hash_file(path).as_ref() == Some(h))
+}
+
+fn read_vars(path: &Path) -> Vec<(String, String)> {
+    std::fs::read_to_string(path)
+        .map(|text| parse(&text))
+        .unwrap_or_default()
+}
+
+// Whether the file set at least one variable; `only` limits which ones it may set.
+fn apply(path: &Path, only: Option<&[&str]>) -> bool {
+    let mut any = false;
+    for (key, value) in read_vars(path) {
+        if only.is_some_and(|o| !o.contains(&key.as_str())) || std::env::var_os(&key).is_some() {
+            continue;
+        }
+        std::env::set_var(&key, value);
+        any = true;
+    }
+    any
+}
+
+// KEY=value lines, with an optional `export `, `#` comments, 'literal' and "escaped"
+// quoting, and ` #` comments after unquoted values.
+fn parse(text: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        let value = value.trim();
+        let value = if let Some(v) = value.strip_prefix('\'') {
+            v.split_once('\'').map(|(v, _)| v).unwrap_or(v).to_string()
+        } else if let Some(v) = value.strip_prefix('"') {
+            unescape(v)
+        } else {
+            value
+                .split_once(" #")
+                .map(|(v, _)| v)
+                .unwrap_or(value)
+                .trim_end()
+                .to_string()
+        };
+        out.push((key.to_string(), value));
+    }
+    out
+}
+
+// The inside of a double-quoted value, up to the closing quote.
+fn unescape(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => break,
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
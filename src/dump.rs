@@ -0,0 +1,112 @@
+// --dump-request / --dump-response: write the exact JSON exchanged with the API, so a
+// provider's schema quirks can be reported with real payloads. API keys are replaced by
+// their label (see llm::key_label) wherever they appear, and the values of headers added
+// from [openrouter.headers], which often carry gateway credentials, are left out.
+use clap::ArgMatches;
+use serde_json::Value as JsonValue;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crate::llm::key_label;
+use crate::output::plain;
+
+// Headers whose values are shown: the key is already relabeled, and attribution is public.
+const SHOWN_HEADERS: [&str; 3] = ["Authorization", "HTTP-Referer", "X-Title"];
+
+// Where a dump goes: stderr when the flag has no value (or `-`), else appended to a file.
+#[derive(Clone)]
+enum Target {
+    Stderr,
+    File(PathBuf),
+}
+
+struct Dumps {
+    request: Option<Target>,
+    response: Option<Target>,
+}
+
+static DUMPS: OnceLock<Dumps> = OnceLock::new();
+// Concurrent requests (batch, serve) must not interleave their dumps.
+static WRITE: Mutex<()> = Mutex::new(());
+
+pub fn init(matches: &ArgMatches) {
+    let target = |id: &str| {
+        matches.get_one::<PathBuf>(id).map(|p| {
+            if p.as_os_str() == "-" {
+                Target::Stderr
+            } else {
+                Target::File(p.clone())
+            }
+        })
+    };
+    let _ = DUMPS.set(Dumps {
+        request: target("dump-request"),
+        response: target("dump-response"),
+    });
+}
+
+pub fn request(url: &str, api_key: &str, headers: &[(String, String)], body: &JsonValue) {
+    let Some(target) = DUMPS.get().and_then(|d| d.request.clone()) else {
+        return;
+    };
+    let headers: serde_json::Map<String, JsonValue> = headers
+        .iter()
+        .map(|(k, v)| {
+            let value = if SHOWN_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(k)) {
+                v.clone()
+            } else {
+                "<redacted>".to_string()
+            };
+            (k.clone(), JsonValue::String(value))
+        })
+        .collect();
+    let dump = serde_json::json!({
+        "url": url,
+        "headers": headers,
+        "body": body,
+    });
+    write(&target, "request", api_key, &dump);
+}
+
+// `text` is the raw response body; it is shown parsed when it is JSON, verbatim otherwise.
+pub fn response(status: u16, api_key: &str, text: &str) {
+    let Some(target) = DUMPS.get().and_then(|d| d.response.clone()) else {
+        return;
+    };
+    let body = serde_json::from_str::<JsonValue>(text)
+        .unwrap_or_else(|_| JsonValue::String(text.to_string()));
+    let dump = serde_json::json!({
+        "status": status,
+        "body": body,
+    });
+    write(&target, "response", api_key, &dump);
+}
+
+fn write(target: &Target, what: &str, api_key: &str, dump: &JsonValue) {
+    let mut text = serde_json::to_string_pretty(dump).unwrap_or_default();
+    if !api_key.is_empty() {
+        text = text.replace(api_key, &key_label(api_key));
+    }
+    let _guard = WRITE.lock().unwrap_or_else(|e| e.into_inner());
+    let result = match target {
+        Target::Stderr if plain() => {
+            eprintln!("API {} dump follows.\n{}", what, text);
+            Ok(())
+        }
+        Target::Stderr => {
+            eprintln!("==== {} ====\n{}", what.to_uppercase(), text);
+            Ok(())
+        }
+        Target::File(path) => OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut f| writeln!(f, "{}", text)),
+    };
+    // Like auditing, dumping must never break generation.
+    if let Err(e) = result {
+        eprintln!("Failed to write {} dump: {}", what, e);
+    }
+}
@@ -0,0 +1,356 @@
+use clap::ArgMatches;
+use std::sync::OnceLock;
+
+use crate::config::config;
+
+pub fn detect_environment() -> String {
+    // macOS
+    if cfg!(target_os = "macos") {
+        return "macos".to_string();
+    }
+
+    // Windows
+    if cfg!(target_os = "windows") {
+        return "windows".to_string();
+    }
+
+    // Try to read /etc/os-release for Linux distros
+    if cfg!(target_os = "linux") {
+        if let Ok(s) = std::fs::read_to_string("/etc/os-release") {
+            let s_l = s.to_lowercase();
+            if s_l.contains("debian") || s_l.contains("ubuntu") {
+                return "linux (debian/ubuntu)".to_string();
+            }
+            if s_l.contains("fedora") {
+                return "linux (fedora)".to_string();
+            }
+            if s_l.contains("arch") {
+                return "linux (arch)".to_string();
+            }
+            // fallback for generic linux
+            return "linux".to_string();
+        }
+        return "linux".to_string();
+    }
+
+    // Unknown/fallback
+    "unknown".to_string()
+}
+
+// Where snapshell itself runs, beyond the OS. Each changes which commands make sense and
+// how the clipboard can be reached.
+pub struct Runtime {
+    // docker, podman, kubernetes, lxc, or plain "container" when the kind is unclear
+    pub container: Option<&'static str>,
+    pub wsl: bool,
+    pub ssh: bool,
+}
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+static HARDWARE: OnceLock<String> = OnceLock::new();
+
+pub fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        let set = |v: &str| std::env::var_os(v).is_some_and(|s| !s.is_empty());
+        let wsl = set("WSL_DISTRO_NAME")
+            || std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .is_ok_and(|r| r.to_lowercase().contains("microsoft"));
+        Runtime {
+            container: detect_container(),
+            wsl,
+            ssh: set("SSH_CONNECTION") || set("SSH_TTY"),
+        }
+    })
+}
+
+fn detect_container() -> Option<&'static str> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    if std::path::Path::new("/.dockerenv").exists() {
+        return Some("docker");
+    }
+    if std::path::Path::new("/run/.containerenv").exists() {
+        return Some("podman");
+    }
+    // Set by podman, systemd-nspawn, and LXC for the container's init.
+    if let Ok(kind) = std::env::var("container") {
+        return Some(match kind.as_str() {
+            "podman" => "podman",
+            "docker" => "docker",
+            "lxc" => "lxc",
+            _ => "container",
+        });
+    }
+    if std::env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+        return Some("kubernetes");
+    }
+    let cgroup = std::fs::read_to_string("/proc/1/cgroup").unwrap_or_default();
+    [
+        ("kubepods", "kubernetes"),
+        ("docker", "docker"),
+        ("libpod", "podman"),
+        ("lxc", "lxc"),
+        ("containerd", "container"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| cgroup.contains(marker))
+    .map(|(_, kind)| kind)
+}
+
+// CPU architecture, core count, and GPU, for compiler flags, docker --platform, and
+// ML tooling. Computed once; probing for a GPU may start a program.
+pub fn hardware() -> &'static str {
+    HARDWARE.get_or_init(|| {
+        let mut parts = vec![architecture()];
+        if let Ok(n) = std::thread::available_parallelism() {
+            let plural = if n.get() == 1 { "" } else { "s" };
+            parts.push(format!("{} CPU core{}", n, plural));
+        }
+        parts.push(gpu().unwrap_or_else(|| "no GPU detected".to_string()));
+        parts.join(", ")
+    })
+}
+
+// The machine's architecture, which differs from the binary's when an x86_64 build
+// runs under Rosetta on Apple Silicon.
+fn architecture() -> String {
+    let arch = std::env::consts::ARCH;
+    if cfg!(target_os = "macos") {
+        let arm = crate::modes::capture("sysctl", &["-n", "hw.optional.arm64"]);
+        if arch == "x86_64" && arm.as_deref() == Some("1") {
+            return "arm64 (Apple Silicon; this shell runs x86_64 under Rosetta)".to_string();
+        }
+        if arch == "aarch64" {
+            return "arm64 (Apple Silicon)".to_string();
+        }
+    }
+    arch.to_string()
+}
+
+fn gpu() -> Option<String> {
+    if cfg!(target_os = "macos") && std::env::consts::ARCH == "aarch64" {
+        return Some("Apple GPU (Metal, no CUDA)".to_string());
+    }
+    if std::path::Path::new("/proc/driver/nvidia/version").exists() || which("nvidia-smi").is_some()
+    {
+        let name = nvidia_model().filter(|n| !n.is_empty());
+        return Some(match name {
+            Some(n) => format!("NVIDIA GPU ({}, CUDA)", n),
+            None => "NVIDIA GPU (CUDA)".to_string(),
+        });
+    }
+    // /dev/kfd is the ROCm compute interface.
+    if std::path::Path::new("/dev/kfd").exists() {
+        return Some("AMD GPU (ROCm)".to_string());
+    }
+    None
+}
+
+const NVIDIA_SMI_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+// The first NVIDIA GPU's model name. The driver lists it under /proc on Linux; elsewhere
+// (WSL, no proc entries) nvidia-smi is asked, with a time limit, since it can take
+// seconds while the driver wakes the GPU.
+fn nvidia_model() -> Option<String> {
+    let from_proc = std::fs::read_dir("/proc/driver/nvidia/gpus")
+        .ok()
+        .and_then(|dirs| {
+            let mut dirs: Vec<_> = dirs.filter_map(|d| d.ok()).map(|d| d.path()).collect();
+            dirs.sort();
+            dirs.into_iter().find_map(|d| {
+                let info = std::fs::read_to_string(d.join("information")).ok()?;
+                info.lines()
+                    .find_map(|l| l.strip_prefix("Model:"))
+                    .map(|m| m.trim().to_string())
+            })
+        });
+    if from_proc.is_some() {
+        return from_proc;
+    }
+    let output = crate::modes::output_with_timeout(
+        std::process::Command::new("nvidia-smi")
+            .args(["--query-gpu=name", "--format=csv,noheader"]),
+        NVIDIA_SMI_TIMEOUT,
+    )?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+}
+
+// Start detecting the runtime, hardware and package manager on background threads, so the
+// GPU and PATH probes overlap the other startup work instead of running when the first
+// system prompt is built. Whoever needs a result first waits only for what is left.
+pub fn prefetch() {
+    std::thread::spawn(|| {
+        runtime();
+        detect_package_manager();
+    });
+    std::thread::spawn(|| {
+        hardware();
+    });
+}
+
+// What the model should know about the runtime, one sentence per fact.
+fn runtime_note() -> String {
+    let runtime = runtime();
+    let mut note = String::new();
+    if let Some(kind) = runtime.container {
+        note.push_str(&format!(" This shell runs inside a {} container: systemd and other service managers are usually absent (start processes directly), the user is often root without sudo, and changes outside mounted volumes are lost when the container stops.", kind));
+    }
+    if runtime.wsl {
+        note.push_str(" This is Linux under WSL: Windows drives are mounted under /mnt/c and so on, Windows programs can be run by their .exe name (explorer.exe, clip.exe), and systemd may be disabled.");
+    }
+    if runtime.ssh {
+        note.push_str(" The user is connected over SSH, so GUI programs cannot be opened and this machine's clipboard is not theirs.");
+    }
+    note
+}
+
+// Sentence appended to system instructions so the model tailors commands to the user's OS/distro
+pub fn env_note() -> String {
+    format!(
+        " Target environment: {}. Hardware: {}. Ensure generated commands are compatible with this environment.{}{}{}{}{}{}",
+        detect_environment(),
+        hardware(),
+        runtime_note(),
+        crate::project::note(),
+        crate::personalize::note(),
+        crate::collectors::note(),
+        crate::prompt::extra_note(),
+        language_note()
+    )
+}
+
+// Language codes as found in LANG=de_DE.UTF-8, mapped to the names given to the model.
+const LANGUAGES: [(&str, &str); 24] = [
+    ("ar", "Arabic"),
+    ("cs", "Czech"),
+    ("da", "Danish"),
+    ("de", "German"),
+    ("el", "Greek"),
+    ("es", "Spanish"),
+    ("fi", "Finnish"),
+    ("fr", "French"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("hu", "Hungarian"),
+    ("id", "Indonesian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("nb", "Norwegian"),
+    ("nl", "Dutch"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("zh", "Chinese"),
+];
+
+static LANGUAGE: OnceLock<Option<String>> = OnceLock::new();
+
+// Response language: --lang, then `lang` in config.toml, then the locale (LC_ALL,
+// LC_MESSAGES, LANG). English and the C/POSIX locales need no instruction.
+pub fn init_language(matches: &ArgMatches) {
+    let requested = matches
+        .get_one::<String>("lang")
+        .cloned()
+        .or_else(|| config().lang.clone())
+        .or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|v| std::env::var(v).ok())
+                .find(|v| !v.is_empty())
+        });
+    let _ = LANGUAGE.set(requested.and_then(|l| language_name(&l)));
+}
+
+pub fn language() -> Option<&'static str> {
+    LANGUAGE.get().and_then(|l| l.as_deref())
+}
+
+// "de_DE.UTF-8", "de", or "German" -> "German"; unknown names are passed through.
+fn language_name(value: &str) -> Option<String> {
+    let code = value
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .split(['_', '-'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    if code.is_empty() || code == "c" || code == "posix" || code == "en" || code == "english" {
+        return None;
+    }
+    match LANGUAGES.iter().find(|(c, _)| *c == code) {
+        Some((_, name)) => Some(name.to_string()),
+        None => Some(value.trim().to_string()),
+    }
+}
+
+// Sentence appended to instructions whose answers contain prose. Commands and the NOT ABLE
+// prefix stay as they are, because they are run and matched literally.
+pub fn language_note() -> String {
+    match language() {
+        Some(lang) => format!(" Write all explanations, replies, and NOT ABLE TO ANSWER reasons in {}, but never translate commands, flags, file names, code, or the literal (NOT ABLE TO ANSWER): prefix.", lang),
+        None => String::new(),
+    }
+}
+
+// Cut `s` to at most `max` bytes without splitting a UTF-8 character.
+pub fn truncate(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+// Locate an executable on PATH, like `command -v`.
+pub fn which(name: &str) -> Option<std::path::PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if cfg!(windows) {
+            let exe = dir.join(format!("{}.exe", name));
+            if exe.is_file() {
+                return Some(exe);
+            }
+        }
+        None
+    })
+}
+
+// First package manager found on PATH, in rough order of preference per platform.
+pub fn detect_package_manager() -> Option<&'static str> {
+    static MANAGER: OnceLock<Option<&'static str>> = OnceLock::new();
+    *MANAGER.get_or_init(find_package_manager)
+}
+
+fn find_package_manager() -> Option<&'static str> {
+    const MANAGERS: [&str; 12] = [
+        "brew",
+        "apt-get",
+        "dnf",
+        "yum",
+        "pacman",
+        "zypper",
+        "apk",
+        "xbps-install",
+        "emerge",
+        "nix-env",
+        "winget",
+        "scoop",
+    ];
+    MANAGERS.iter().copied().find(|m| which(m).is_some())
+}
@@ -0,0 +1,70 @@
+// --escalate: ask at low reasoning effort first and only pay for more when the answer
+// does not hold up. An answer fails when it is a NOT ABLE TO ANSWER, does not parse as
+// shell, or has shellcheck (or native lint) findings.
+use serde_json::Value as JsonValue;
+
+use crate::env::which;
+use crate::lint;
+use crate::llm::{complete, Completion, Settings};
+use crate::modes::run_with_stdin;
+use crate::output::is_not_able_response;
+
+const TIERS: [&str; 3] = ["low", "medium", "high"];
+
+// Why `answer` should be retried at a higher effort, or None when it passes.
+pub fn problem(answer: &str) -> Option<String> {
+    if is_not_able_response(answer) {
+        return Some("not able to answer".to_string());
+    }
+    if answer.trim().is_empty() {
+        return Some("empty answer".to_string());
+    }
+    let shell = if which("bash").is_some() {
+        "bash"
+    } else {
+        "sh"
+    };
+    if let Ok(out) = run_with_stdin(shell, &["-n"], answer) {
+        if !out.status.success() {
+            let err = String::from_utf8_lossy(&out.stderr);
+            let first = err.lines().next().unwrap_or("syntax error").trim();
+            return Some(format!("does not parse ({})", first));
+        }
+    }
+    let findings = lint::check(answer);
+    if !findings.is_empty() {
+        let codes: Vec<&str> = findings.iter().map(|f| f.code.as_str()).collect();
+        return Some(format!("lint {}", codes.join(", ")));
+    }
+    None
+}
+
+// Try each tier in turn and return the first answer that passes. When none does, the
+// high-effort answer is returned, since it is the best guess available.
+pub async fn complete_escalating(
+    settings: &Settings,
+    messages: &[JsonValue],
+) -> anyhow::Result<Completion> {
+    let mut last = None;
+    for tier in TIERS {
+        let mut tiered = settings.clone();
+        tiered.effort = tier.to_string();
+        tiered.retry_not_able = false;
+        let completion = complete(&tiered, messages).await?;
+        match problem(&completion.content) {
+            None => {
+                eprintln!("Escalation: answered at reasoning {}.", tier);
+                return Ok(completion);
+            }
+            Some(why) if tier != "high" => {
+                eprintln!("Escalation: reasoning {} failed ({}); retrying.", tier, why)
+            }
+            Some(why) => eprintln!(
+                "Escalation: reasoning high still failed ({}); keeping its answer.",
+                why
+            ),
+        }
+        last = Some(completion);
+    }
+    Ok(last.expect("at least one tier"))
+}
@@ -0,0 +1,152 @@
+// Running generated commands (--exec, install --run). Commands run through `sh -c`
+// only after the user confirms them; anything that needs root is confirmed again,
+// separately, before sudo is involved. sudo does its own authentication and nothing
+// here caches credentials.
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+use std::io::{self, Write};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::atuin;
+use crate::config::config;
+use crate::env::{detect_environment, language_note};
+use crate::history::{record_exec, MAX_EXEC_STDERR_BYTES};
+use crate::llm::{complete, Settings};
+use crate::modes::confirm;
+use crate::modes::watch::tee;
+use crate::output::deterministic;
+use crate::policy::policy;
+use crate::privilege;
+use crate::record::{self, Echo};
+
+// `explain_first` (--dry-explain) shows the explanation before the first prompt; `d`
+// at the prompt asks for it on demand.
+pub async fn run(settings: &Settings, command: &str, explain_first: bool) -> Result<()> {
+    let Some(command_to_run) = approve(settings, command, explain_first).await? else {
+        return Ok(());
+    };
+    let generated = command;
+    let command = command_to_run;
+
+    // The command gets the terminal as is, so colours, progress bars, and prompts work.
+    // With `capture_stderr` or --record, stderr goes through a pipe and is passed on as it
+    // arrives (and kept for the execution log); with --record stdout does as well.
+    let start = Instant::now();
+    let atuin_entry = atuin::start(&command);
+    let mut child = std::process::Command::new("sh");
+    child.arg("-c").arg(&command);
+    if config().capture_stderr || record::active() {
+        child.stderr(Stdio::piped());
+    }
+    if record::active() {
+        child.stdout(Stdio::piped());
+    }
+    let mut child = child.spawn()?;
+    let stderr = Arc::new(Mutex::new(Vec::new()));
+    let reader = tee(
+        child.stderr.take(),
+        Echo::new(io::stderr()),
+        stderr.clone(),
+        MAX_EXEC_STDERR_BYTES,
+    );
+    let stdout = tee(
+        child.stdout.take(),
+        Echo::new(io::stdout()),
+        Arc::new(Mutex::new(Vec::new())),
+        0,
+    );
+    let status = child.wait()?;
+    let _ = reader.join();
+    let _ = stdout.join();
+    let stderr = String::from_utf8_lossy(&stderr.lock().expect("stderr lock")).to_string();
+    record_exec(generated, &command, status.code(), start.elapsed(), &stderr);
+    if let Some(entry) = atuin_entry {
+        atuin::end(entry, status.code());
+    }
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+// Ask before `command` runs, and separately before sudo is involved. Returns what to run,
+// which may now be wrapped in sudo, or None when the user said no.
+pub async fn approve(
+    settings: &Settings,
+    command: &str,
+    explain_first: bool,
+) -> Result<Option<String>> {
+    policy().check_exec()?;
+    if deterministic() {
+        eprintln!("Not run: --deterministic answers every confirmation with no.");
+        return Ok(None);
+    }
+    if explain_first {
+        print_explanation(settings, command).await;
+    }
+    loop {
+        let question = format!("Run `{}`? [y/N/d=explain] ", command);
+        eprint!("{}", question);
+        let _ = io::stderr().flush();
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).is_err() {
+            return Ok(None);
+        }
+        record::answered(&question, &line);
+        match line.trim().to_lowercase().as_str() {
+            "y" | "yes" => break,
+            "d" => print_explanation(settings, command).await,
+            _ => return Ok(None),
+        }
+    }
+
+    let mut command = command.to_string();
+    if let Some(reason) = privilege::requirement(&command) {
+        if privilege::uses_sudo(&command) {
+            if !confirm("The command invokes sudo. Let it ask for elevated privileges?") {
+                eprintln!("Not run.");
+                return Ok(None);
+            }
+        } else if !privilege::is_root()
+            && confirm(&format!(
+                "It probably needs root ({}). Run it with sudo?",
+                reason
+            ))
+        {
+            command = format!("sudo sh -c {}", shell_quote(&command));
+        }
+    }
+    Ok(Some(command))
+}
+
+// A second, cheap request describing what the command will do before it runs.
+pub async fn explain(settings: &Settings, command: &str) -> Option<String> {
+    let mut cheap = settings.clone();
+    cheap.effort = "low".to_string();
+    cheap.retry_not_able = false;
+    let messages: Vec<JsonValue> = vec![
+        serde_json::json!({"role": "system", "content": format!("You explain shell commands to someone about to run them. In plain text, no markdown, at most 6 short lines, cover: what the command does; files and directories it reads, writes, or deletes; any network access; whether it is destructive or irreversible. Be concrete and do not suggest alternatives.{}", language_note())}),
+        serde_json::json!({"role": "user", "content": format!("Environment: {}\nCommand:\n{}", detect_environment(), command)}),
+    ];
+    let c = complete(&cheap, &messages).await.ok()?;
+    Some(c.content.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+async fn print_explanation(settings: &Settings, command: &str) {
+    match explain(settings, command).await {
+        Some(text) => {
+            for line in text.lines() {
+                eprintln!("  {}", line);
+                record::output(&format!("  {}\n", line));
+            }
+        }
+        None => eprintln!("Could not get an explanation."),
+    }
+}
+
+// Single-quote `s` for sh.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
@@ -0,0 +1,438 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::{Deserialize, Serialize};
+use std::fs::{create_dir_all, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::config::config;
+use crate::llm::Settings;
+use crate::paths::state_dir;
+use crate::personalize;
+
+#[derive(Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: String,
+    pub prompt: String,
+    pub command: String,
+}
+
+pub fn command() -> Command {
+    Command::new("history")
+        .about(
+            "Show the history of prompts and generated commands, or repair a damaged history file",
+        )
+        .after_help("Examples:\n  ss history\n  ss history --failed\n  ss history repair\n  ss history digest --refresh")
+        .args_conflicts_with_subcommands(true)
+        .arg(
+            Arg::new("failed")
+                .long("failed")
+                .help("Show commands run with --exec that failed, with their exit code and stderr")
+                .action(ArgAction::SetTrue),
+        )
+        .subcommand(Command::new("repair").about(
+            "Rewrite history.jsonl without malformed lines, which are moved to a quarantine file",
+        ))
+        .subcommand(
+            Command::new("digest")
+                .about("Show the summary of your habits sent with each request when personalize = true")
+                .arg(
+                    Arg::new("refresh")
+                        .long("refresh")
+                        .help("Write a new digest from recent history now")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("clear")
+                        .long("clear")
+                        .help("Delete the digest; it is written again on the next request")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("refresh"),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("repair", _)) => repair(),
+        Some(("digest", sub)) => digest(sub).await,
+        _ if matches.get_flag("failed") => print_failed(),
+        _ => print_history(),
+    }
+}
+
+// One command run with --exec (or install --run), linked to the history entry that
+// produced it when there is one.
+#[derive(Serialize, Deserialize)]
+pub struct ExecRecord {
+    pub timestamp: String,
+    pub command: String,
+    // None when the command was killed by a signal
+    pub exit_code: Option<i32>,
+    pub duration_ms: u128,
+    // The end of stderr, where the error usually is
+    pub stderr: String,
+    // Timestamp and prompt of the generating history entry
+    pub history: Option<String>,
+    pub prompt: Option<String>,
+}
+
+// Only the end of stderr is kept per execution.
+pub const MAX_EXEC_STDERR_BYTES: usize = 2000;
+
+async fn digest(matches: &ArgMatches) -> Result<()> {
+    if matches.get_flag("clear") {
+        return personalize::clear();
+    }
+    let digest = if matches.get_flag("refresh") {
+        let settings = Settings::from_matches(matches);
+        match personalize::regenerate(&settings).await {
+            Some(d) => Some(d),
+            None => bail!("could not write a digest (no history, or the request failed)"),
+        }
+    } else {
+        personalize::load()
+    };
+    match digest {
+        Some(d) => println!(
+            "{}\n  (written {} from {} entries)",
+            d.summary, d.generated, d.entries
+        ),
+        None => println!("no digest yet"),
+    }
+    if !config().personalize {
+        eprintln!("Note: personalize is off in config.toml, so the digest is not sent.");
+    }
+    Ok(())
+}
+
+pub fn exec_log_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("executions.jsonl"))
+}
+
+static HISTORY_FILE: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+// --history-file, then SNAPSHELL_HISTORY_FILE, e.g. a tmpfs in an ephemeral container or
+// a file shared by a team.
+pub fn init(matches: &ArgMatches) {
+    let path = matches
+        .get_one::<PathBuf>("history-file")
+        .cloned()
+        .or_else(|| {
+            std::env::var_os("SNAPSHELL_HISTORY_FILE")
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+        });
+    let _ = HISTORY_FILE.set(path);
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    if let Some(Some(path)) = HISTORY_FILE.get() {
+        return Some(path.clone());
+    }
+    state_dir().map(|d| d.join("history.jsonl"))
+}
+
+// Non-UTF-8 bytes (a line cut mid-character, a file edited in another encoding) only
+// spoil the entry they are in, not the whole history.
+// A shared lock keeps a line that is being appended from being read half-written.
+fn read_lossy(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    file.lock_shared()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+pub fn save_history(prompt: &str, command: &str) -> Result<()> {
+    if let Some(path) = history_path() {
+        let entry = HistoryEntry {
+            timestamp: Utc::now().to_rfc3339(),
+            prompt: prompt.to_string(),
+            command: command.to_string(),
+        };
+        append_line(&path, &serde_json::to_string(&entry)?)?;
+    }
+    Ok(())
+}
+
+// Append one JSONL line under an exclusive lock, so concurrent `ss` processes (shell
+// widgets in split panes, batch runs) never interleave partial lines. The line goes out
+// in a single write; the lock is released when the file is closed.
+pub fn append_line(path: &Path, line: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.lock()?;
+    file.write_all(format!("{}\n", line).as_bytes())?;
+    Ok(())
+}
+
+pub fn print_history() -> Result<()> {
+    if let Some(path) = history_path() {
+        if !path.exists() {
+            println!("no history");
+            return Ok(());
+        }
+        let (entries, bad) = parse(&read_lossy(&path)?);
+        for entry in entries {
+            println!(
+                "{} -> {}\n  {}",
+                entry.timestamp, entry.prompt, entry.command
+            );
+        }
+        if bad > 0 {
+            eprintln!(
+                "Skipped {} malformed history line(s); run `ss history repair` to clean them up.",
+                bad
+            );
+        }
+    } else {
+        println!("no history");
+    }
+    Ok(())
+}
+
+// Every readable history entry, oldest first.
+pub fn load_history() -> Result<Vec<HistoryEntry>> {
+    let path = match history_path() {
+        Some(p) if p.exists() => p,
+        _ => return Ok(Vec::new()),
+    };
+    Ok(parse(&read_lossy(&path)?).0)
+}
+
+// Most recent readable history entry, used by --regen and --refine.
+pub fn last_entry() -> Result<Option<HistoryEntry>> {
+    let path = match history_path() {
+        Some(p) if p.exists() => p,
+        _ => return Ok(None),
+    };
+    Ok(parse(&read_lossy(&path)?).0.pop())
+}
+
+// Every entry in `text`, and how many lines were not entirely readable.
+fn parse(text: &str) -> (Vec<HistoryEntry>, usize) {
+    let mut entries = Vec::new();
+    let mut bad = 0;
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        let (found, clean) = parse_line(line);
+        entries.extend(found);
+        if !clean {
+            bad += 1;
+        }
+    }
+    (entries, bad)
+}
+
+// The entries in one line. Besides the normal one-entry line, this recovers lines that
+// unlocked concurrent writers glued together, and entries after a torn one; `clean` is
+// false when any part of the line could not be read.
+fn parse_line(line: &str) -> (Vec<HistoryEntry>, bool) {
+    const START: &str = "{\"timestamp\"";
+    let mut entries = Vec::new();
+    let mut clean = true;
+    let mut pos = 0;
+    while pos < line.len() {
+        let rest = &line[pos..];
+        let skipped = rest.len() - rest.trim_start().len();
+        let mut stream = serde_json::Deserializer::from_str(rest.trim_start()).into_iter();
+        match stream.next() {
+            Some(Ok(entry)) => {
+                entries.push(entry);
+                pos += skipped + stream.byte_offset();
+            }
+            None => break,
+            Some(Err(_)) => {
+                clean = false;
+                // Step past the current character, which may be more than one byte
+                let step = rest.chars().next().map_or(1, char::len_utf8);
+                match line[pos + step..].find(START) {
+                    Some(next) => pos += step + next,
+                    None => break,
+                }
+            }
+        }
+    }
+    (entries, clean)
+}
+
+// Keep every readable entry, move the unreadable lines to <history>.corrupt, and
+// keep the original as <history>.bak. The file is rewritten in place under the
+// lock, so an `ss` waiting to append still writes to the repaired file.
+fn repair() -> Result<()> {
+    let Some(path) = history_path().filter(|p| p.exists()) else {
+        println!("no history");
+        return Ok(());
+    };
+    let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+    file.lock()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    let mut clean = String::new();
+    let mut corrupt = String::new();
+    let mut recovered = 0;
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        let (entries, ok) = parse_line(line);
+        recovered += entries.len();
+        for entry in &entries {
+            clean.push_str(&serde_json::to_string(entry)?);
+            clean.push('\n');
+        }
+        if !ok {
+            corrupt.push_str(line);
+            corrupt.push('\n');
+        }
+    }
+    if corrupt.is_empty() && clean.len() == bytes.len() {
+        println!("History is clean: {} entries.", recovered);
+        return Ok(());
+    }
+
+    // Suffixes are appended so a --history-file with any name keeps its own pair.
+    let with_suffix = |suffix: &str| {
+        let mut name = path.clone().into_os_string();
+        name.push(suffix);
+        PathBuf::from(name)
+    };
+    let backup = with_suffix(".bak");
+    std::fs::write(&backup, &bytes)?;
+    let quarantine = with_suffix(".corrupt");
+    if !corrupt.is_empty() {
+        let mut q = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&quarantine)?;
+        q.write_all(corrupt.as_bytes())?;
+    }
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(clean.as_bytes())?;
+    file.sync_all()?;
+
+    println!("Recovered {} entries.", recovered);
+    if !corrupt.is_empty() {
+        println!(
+            "Moved {} malformed line(s) to {}.",
+            corrupt.lines().count(),
+            quarantine.display()
+        );
+    }
+    println!("The original file is saved as {}.", backup.display());
+    Ok(())
+}
+
+// Best effort, like the audit log: a run is never failed over its record. `generated` is
+// the command as generated, before any sudo wrapper, to find its history entry.
+pub fn record_exec(
+    generated: &str,
+    command: &str,
+    exit_code: Option<i32>,
+    duration: Duration,
+    stderr: &str,
+) {
+    let Some(path) = exec_log_path() else {
+        return;
+    };
+    let generated_by = last_entry()
+        .ok()
+        .flatten()
+        .filter(|e| e.command == generated);
+    let mut start = stderr.len().saturating_sub(MAX_EXEC_STDERR_BYTES);
+    while !stderr.is_char_boundary(start) {
+        start += 1;
+    }
+    let record = ExecRecord {
+        timestamp: Utc::now().to_rfc3339(),
+        command: command.to_string(),
+        exit_code,
+        duration_ms: duration.as_millis(),
+        stderr: stderr[start..].to_string(),
+        history: generated_by.as_ref().map(|e| e.timestamp.clone()),
+        prompt: generated_by.map(|e| e.prompt),
+    };
+    let result = serde_json::to_string(&record)
+        .map_err(anyhow::Error::from)
+        .and_then(|line| append_line(&path, &line));
+    if let Err(e) = result {
+        eprintln!("Failed to write execution log: {}", e);
+    }
+}
+
+// Every readable execution record, oldest first.
+pub fn load_exec_log() -> Result<Vec<ExecRecord>> {
+    Ok(match exec_log_path().filter(|p| p.exists()) {
+        Some(path) => read_lossy(&path)?
+            .lines()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect(),
+        None => Vec::new(),
+    })
+}
+
+fn print_failed() -> Result<()> {
+    let records = load_exec_log()?;
+    let failed: Vec<&ExecRecord> = records.iter().filter(|r| r.exit_code != Some(0)).collect();
+    if failed.is_empty() {
+        println!("no failed executions");
+        return Ok(());
+    }
+    for r in failed {
+        let status = match r.exit_code {
+            Some(code) => format!("exit {}", code),
+            None => "killed by a signal".to_string(),
+        };
+        println!(
+            "{} {} after {:.1}s\n  {}",
+            r.timestamp,
+            status,
+            r.duration_ms as f64 / 1000.0,
+            r.command
+        );
+        if let Some(prompt) = &r.prompt {
+            println!("  prompt: {}", prompt);
+        }
+        for line in r.stderr.trim().lines() {
+            println!("  | {}", line);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRY: &str =
+        r#"{"timestamp":"2025-01-01T00:00:00Z","prompt":"list files","command":"ls"}"#;
+
+    #[test]
+    fn recovers_entries_after_non_ascii_garbage() {
+        for garbage in ["é garbage", "\u{FFFD}\u{FFFD}"] {
+            let (entries, clean) = parse_line(&format!("{}{}", garbage, ENTRY));
+            assert!(!clean);
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].command, "ls");
+        }
+    }
+
+    #[test]
+    fn skips_a_non_ascii_line_without_entries() {
+        let (entries, clean) = parse_line("é");
+        assert!(!clean);
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn splits_glued_entries() {
+        let (entries, clean) = parse_line(&format!("{}{}", ENTRY, ENTRY));
+        assert!(clean);
+        assert_eq!(entries.len(), 2);
+    }
+}
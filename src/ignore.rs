@@ -0,0 +1,173 @@
+// .gitignore and .snapshellignore rules for context: directory listings and file contents
+// attached to a request leave out whatever these files ignore, so build output, vendored
+// trees, and secrets are never uploaded. .snapshellignore uses the same syntax and is for
+// files git tracks but the model should not see.
+use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ::ignore::Match;
+use std::path::{Path, PathBuf};
+
+const FILES: [&str; 2] = [".gitignore", ".snapshellignore"];
+
+pub struct Ignore {
+    // One matcher per directory with ignore files, innermost first
+    levels: Vec<Gitignore>,
+}
+
+impl Ignore {
+    // Rules from `dir` and its parents up to the top of the git repository (or only `dir`
+    // outside one). Deeper files take precedence, like in git.
+    pub fn for_dir(dir: &Path) -> Ignore {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let mut dirs = Vec::new();
+        for d in dir.ancestors() {
+            dirs.push(d.to_path_buf());
+            if d.join(".git").exists() {
+                break;
+            }
+        }
+        if !dirs.last().is_some_and(|d| d.join(".git").exists()) {
+            dirs.truncate(1);
+        }
+        let mut levels = Vec::new();
+        for d in &dirs {
+            let mut builder = GitignoreBuilder::new(d);
+            for file in FILES {
+                let path = d.join(file);
+                if path.is_file() {
+                    // A bad line is skipped; the rest of the file still applies.
+                    let _ = builder.add(path);
+                }
+            }
+            if let Ok(matcher) = builder.build() {
+                if !matcher.is_empty() {
+                    levels.push(matcher);
+                }
+            }
+        }
+        Ignore { levels }
+    }
+
+    // Whether `path` (absolute, or relative to the current directory) is ignored, either
+    // itself or through one of its parent directories.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.levels.is_empty() {
+            return false;
+        }
+        let path = absolute(path);
+        for level in &self.levels {
+            if !path.starts_with(level.path()) || path == level.path() {
+                continue;
+            }
+            match level.matched_path_or_any_parents(&path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+
+    // Keep only the entries of `dir` that are not ignored; returns how many were dropped.
+    pub fn retain_in(&self, dir: &Path, names: &mut Vec<String>) -> usize {
+        let before = names.len();
+        names.retain(|n| {
+            let name = n.trim_end_matches('/');
+            let path = dir.join(name);
+            !self.is_ignored(&path, n.ends_with('/') || path.is_dir())
+        });
+        before - names.len()
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let cwd = cwd.canonicalize().unwrap_or(cwd);
+    cwd.join(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A scratch repository with the given ignore file, and its matcher.
+    fn repo(gitignore: &str) -> (tempfile::TempDir, Ignore) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), gitignore).unwrap();
+        let ignore = Ignore::for_dir(dir.path());
+        (dir, ignore)
+    }
+
+    fn ignored(ignore: &Ignore, root: &Path, rel: &str, is_dir: bool) -> bool {
+        let root = root.canonicalize().unwrap();
+        ignore.is_ignored(&root.join(rel), is_dir)
+    }
+
+    #[test]
+    fn unanchored_names_match_at_any_depth() {
+        let (dir, ig) = repo("*.log\n");
+        assert!(ignored(&ig, dir.path(), "a.log", false));
+        assert!(ignored(&ig, dir.path(), "src/deep/b.log", false));
+        assert!(!ignored(&ig, dir.path(), "a.txt", false));
+    }
+
+    #[test]
+    fn anchored_patterns_match_from_the_ignore_file() {
+        let (dir, ig) = repo("/build\ndocs/*.html\n");
+        assert!(ignored(&ig, dir.path(), "build", true));
+        assert!(!ignored(&ig, dir.path(), "src/build", true));
+        assert!(ignored(&ig, dir.path(), "docs/index.html", false));
+        assert!(!ignored(&ig, dir.path(), "docs/api/index.html", false));
+    }
+
+    #[test]
+    fn negation_reincludes() {
+        let (dir, ig) = repo("*.env\n!example.env\n");
+        assert!(ignored(&ig, dir.path(), "prod.env", false));
+        assert!(!ignored(&ig, dir.path(), "example.env", false));
+    }
+
+    #[test]
+    fn double_star_crosses_directories() {
+        let (dir, ig) = repo("**/fixtures\nlogs/**/*.gz\n");
+        assert!(ignored(&ig, dir.path(), "fixtures", true));
+        assert!(ignored(&ig, dir.path(), "a/b/fixtures/x.json", false));
+        assert!(ignored(&ig, dir.path(), "logs/2024/01/app.gz", false));
+        assert!(ignored(&ig, dir.path(), "logs/app.gz", false));
+    }
+
+    #[test]
+    fn dir_only_patterns_skip_files() {
+        let (dir, ig) = repo("target/\n");
+        assert!(ignored(&ig, dir.path(), "target", true));
+        assert!(ignored(&ig, dir.path(), "target/debug/ss", false));
+        assert!(!ignored(&ig, dir.path(), "target", false));
+    }
+
+    #[test]
+    fn character_classes() {
+        let (dir, ig) = repo("*.[oa]\nlog[!s]\n");
+        assert!(ignored(&ig, dir.path(), "main.o", false));
+        assert!(ignored(&ig, dir.path(), "lib.a", false));
+        assert!(!ignored(&ig, dir.path(), "main.c", false));
+        assert!(ignored(&ig, dir.path(), "log1", false));
+        assert!(!ignored(&ig, dir.path(), "logs", false));
+    }
+
+    #[test]
+    fn deeper_files_take_precedence() {
+        let (dir, _) = repo("*.json\n");
+        std::fs::create_dir(dir.path().join("config")).unwrap();
+        std::fs::write(
+            dir.path().join("config/.snapshellignore"),
+            "!settings.json\n",
+        )
+        .unwrap();
+        let ig = Ignore::for_dir(&dir.path().join("config"));
+        assert!(!ignored(&ig, dir.path(), "config/settings.json", false));
+        assert!(ignored(&ig, dir.path(), "config/other.json", false));
+    }
+}
@@ -0,0 +1,130 @@
+// --image / --screenshot: attach pictures to the prompt for vision-capable models, e.g. a
+// screenshot of a tutorial or of a TUI error that cannot be copied as text. Images are
+// sent inline as base64 data URLs in OpenAI's multimodal message format.
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use clap::ArgMatches;
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Larger files are refused; providers reject them anyway, after a slow upload.
+const MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+// Region-selection screenshot tools, tried in order. Each writes the image to the path
+// appended as its last argument.
+const SCREENSHOTERS: [(&str, &[&str]); 5] = [
+    ("screencapture", &["-i"]),
+    ("gnome-screenshot", &["-a", "-f"]),
+    ("spectacle", &["-r", "-b", "-n", "-o"]),
+    ("scrot", &["-s", "-o"]),
+    ("import", &[]),
+];
+
+fn mime(path: &Path) -> Result<&'static str> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    Ok(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        _ => bail!(
+            "{}: unsupported image type; use png, jpeg, webp, or gif",
+            path.display()
+        ),
+    })
+}
+
+// One image_url content part holding the file as a data URL.
+fn part(path: &Path) -> Result<JsonValue> {
+    let mime = mime(path)?;
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("reading {}", path.display()))?
+        .len();
+    if size > MAX_BYTES {
+        bail!(
+            "{} is {} bytes; images over {} bytes are refused",
+            path.display(),
+            size,
+            MAX_BYTES
+        );
+    }
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+    eprintln!(
+        "Image: attaching {} ({} KB).",
+        path.display(),
+        size.div_ceil(1024)
+    );
+    Ok(serde_json::json!({
+        "type": "image_url",
+        "image_url": {"url": format!("data:{};base64,{}", mime, data)}
+    }))
+}
+
+// Let the user select a screen region and return the file it was saved to. On Wayland
+// grim needs the region from slurp, so it is handled apart from the table above.
+fn screenshot() -> Result<PathBuf> {
+    let path =
+        std::env::temp_dir().join(format!("snapshell-screenshot-{}.png", std::process::id()));
+    let captured = |status: std::io::Result<std::process::ExitStatus>| {
+        status.is_ok_and(|s| s.success()) && path.exists()
+    };
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        if let Ok(region) = Command::new("slurp").output() {
+            let region = String::from_utf8_lossy(&region.stdout).trim().to_string();
+            if !region.is_empty()
+                && captured(
+                    Command::new("grim")
+                        .args(["-g", &region])
+                        .arg(&path)
+                        .status(),
+                )
+            {
+                return Ok(path);
+            }
+        }
+    }
+    for (program, args) in SCREENSHOTERS {
+        if captured(Command::new(program).args(args).arg(&path).status()) {
+            return Ok(path);
+        }
+    }
+    bail!("could not take a screenshot; install grim and slurp (Wayland), or gnome-screenshot, spectacle, scrot, or ImageMagick's import")
+}
+
+// The image parts requested on the command line, screenshot last.
+pub fn from_matches(matches: &ArgMatches) -> Result<Vec<JsonValue>> {
+    let mut parts = Vec::new();
+    for path in matches.get_many::<PathBuf>("image").into_iter().flatten() {
+        parts.push(part(path)?);
+    }
+    if matches.get_flag("screenshot") {
+        eprintln!("Select the screen region to send...");
+        let path = screenshot()?;
+        let attached = part(&path);
+        let _ = std::fs::remove_file(&path);
+        parts.push(attached?);
+    }
+    Ok(parts)
+}
+
+// A user message content with the images after the text.
+pub fn content(text: &str, images: Vec<JsonValue>) -> JsonValue {
+    if images.is_empty() {
+        return JsonValue::String(text.to_string());
+    }
+    let mut parts = vec![serde_json::json!({"type": "text", "text": text})];
+    parts.extend(images);
+    JsonValue::Array(parts)
+}
+
+pub fn has_image(message: &JsonValue) -> bool {
+    message["content"]
+        .as_array()
+        .is_some_and(|parts| parts.iter().any(|p| p["type"] == "image_url"))
+}
@@ -0,0 +1,85 @@
+// --alfred / --raycast: print the result as the item list a launcher script expects on
+// stdout instead of copying it. Refusals and NOT ABLE answers become items that cannot
+// be actioned, so the launcher shows why rather than failing silently.
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+
+use crate::history::save_history;
+use crate::output::{parse_not_able, refusal};
+
+#[derive(Clone, Copy)]
+pub enum Launcher {
+    Alfred,
+    Raycast,
+}
+
+pub fn from_matches(matches: &clap::ArgMatches) -> Option<Launcher> {
+    if matches.get_flag("alfred") {
+        Some(Launcher::Alfred)
+    } else if matches.get_flag("raycast") {
+        Some(Launcher::Raycast)
+    } else {
+        None
+    }
+}
+
+pub fn emit(launcher: Launcher, prompt: &str, out: &str) -> Result<()> {
+    let item = if let Some(reason) = parse_not_able(out) {
+        blocked_item(launcher, "Not able to answer", &reason)
+    } else if let Some(reason) = refusal(out) {
+        blocked_item(launcher, "Refused", &format!("command {}", reason))
+    } else {
+        save_history(prompt, out)?;
+        command_item(launcher, prompt, out)
+    };
+    println!("{}", serde_json::json!({ "items": [item] }));
+    Ok(())
+}
+
+// Alfred Script Filter item: ↵ passes `arg` on with action=copy, ⌘↵ with action=paste, so
+// the workflow can route it to "Copy to Clipboard" with or without pasting into the
+// frontmost app.
+fn command_item(launcher: Launcher, prompt: &str, command: &str) -> JsonValue {
+    match launcher {
+        Launcher::Alfred => serde_json::json!({
+            "uid": "snapshell",
+            "title": command,
+            "subtitle": format!("↵ copy  ⌘↵ paste into terminal  ·  {}", prompt),
+            "arg": command,
+            "valid": true,
+            "variables": {"action": "copy"},
+            "mods": {
+                "cmd": {
+                    "arg": command,
+                    "subtitle": "Paste into the frontmost terminal",
+                    "variables": {"action": "paste"}
+                }
+            },
+            "text": {"copy": command, "largetype": command}
+        }),
+        Launcher::Raycast => serde_json::json!({
+            "title": command,
+            "subtitle": prompt,
+            "arg": command,
+            "actions": [
+                {"type": "copy", "title": "Copy Command", "content": command},
+                {"type": "paste", "title": "Paste into Frontmost App", "content": command}
+            ]
+        }),
+    }
+}
+
+fn blocked_item(launcher: Launcher, title: &str, reason: &str) -> JsonValue {
+    match launcher {
+        Launcher::Alfred => serde_json::json!({
+            "title": title,
+            "subtitle": reason,
+            "valid": false
+        }),
+        Launcher::Raycast => serde_json::json!({
+            "title": title,
+            "subtitle": reason,
+            "actions": []
+        }),
+    }
+}
@@ -0,0 +1,405 @@
+// Static quoting and injection checks for generated shell commands. Uses shellcheck
+// when it is installed; otherwise a native subset covers the common hazards: unquoted
+// expansions (SC2086, SC2046, SC2068), globs that can become options or are expanded
+// before find sees them (SC2035, SC2061), find | xargs on raw names (SC2038), looping
+// over ls (SC2045), and data re-parsed by eval or sh -c.
+use crate::config::config;
+use crate::env::which;
+use crate::modes::run_with_stdin;
+use crate::output::{is_not_able_response, quiet};
+
+// Words that can precede the command name in a segment.
+const KEYWORDS: [&str; 9] = [
+    "if", "then", "else", "elif", "while", "until", "do", "!", "time",
+];
+
+pub struct Finding {
+    pub code: String,
+    pub message: String,
+}
+
+fn finding(code: &str, message: String) -> Finding {
+    Finding {
+        code: code.to_string(),
+        message,
+    }
+}
+
+#[derive(Default)]
+struct Word {
+    raw: String,
+    // Variable expansions outside any quotes, as written (`$f`, `${name}`)
+    vars: Vec<String>,
+    // Unquoted $(...) or `...`, as written
+    substs: Vec<String>,
+    glob: bool,
+    // Contains `$` inside double quotes
+    quoted_expansion: bool,
+}
+
+// Consume a balanced (...) group starting at chars[i] == '('; returns the index after it.
+fn skip_group(chars: &[char], mut i: usize) -> usize {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    while i < chars.len() {
+        let c = chars[i];
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return i + 1;
+                    }
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+    i
+}
+
+// Split a command into segments of words, recording which expansions are unquoted.
+fn parse(command: &str) -> Vec<Vec<Word>> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut segments = vec![Vec::new()];
+    let mut word = Word::default();
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+    let finish = |word: &mut Word, segments: &mut Vec<Vec<Word>>| {
+        if !word.raw.is_empty() {
+            segments.last_mut().unwrap().push(std::mem::take(word));
+        }
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        if quote == Some('\'') {
+            word.raw.push(c);
+            if c == '\'' {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        if c == '\\' && i + 1 < chars.len() {
+            word.raw.push(c);
+            word.raw.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if c == '$' && i + 1 < chars.len() {
+            let start = i;
+            let next = chars[i + 1];
+            let (end, kind) = if next == '(' && chars.get(i + 2) == Some(&'(') {
+                (skip_group(&chars, i + 1), "arith")
+            } else if next == '(' {
+                (skip_group(&chars, i + 1), "subst")
+            } else if next == '{' {
+                let close = chars[i..].iter().position(|&c| c == '}');
+                (close.map(|p| i + p + 1).unwrap_or(chars.len()), "var")
+            } else if next.is_ascii_alphabetic() || next == '_' {
+                let len = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+                    .count();
+                (i + 1 + len, "var")
+            } else if next.is_ascii_digit() || next == '@' || next == '*' {
+                (i + 2, "var")
+            } else {
+                (i + 2, "special")
+            };
+            let text: String = chars[start..end.min(chars.len())].iter().collect();
+            if quote == Some('"') {
+                word.quoted_expansion = true;
+            } else if kind == "var" {
+                word.vars.push(text.clone());
+            } else if kind == "subst" {
+                word.substs.push(text.clone());
+            }
+            word.raw.push_str(&text);
+            i = end;
+            continue;
+        }
+        if quote == Some('"') {
+            word.raw.push(c);
+            if c == '"' {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            '\'' | '"' => {
+                quote = Some(c);
+                word.raw.push(c);
+            }
+            '`' => {
+                let close = chars[i + 1..].iter().position(|&c| c == '`');
+                let end = close.map(|p| i + p + 2).unwrap_or(chars.len());
+                let text: String = chars[i..end].iter().collect();
+                word.substs.push(text.clone());
+                word.raw.push_str(&text);
+                i = end;
+                continue;
+            }
+            '*' | '?' | '[' => {
+                word.glob = true;
+                word.raw.push(c);
+            }
+            ';' | '|' | '&' | '\n' => {
+                finish(&mut word, &mut segments);
+                if !segments.last().unwrap().is_empty() {
+                    segments.push(Vec::new());
+                }
+            }
+            c if c.is_whitespace() => finish(&mut word, &mut segments),
+            c => word.raw.push(c),
+        }
+        i += 1;
+    }
+    finish(&mut word, &mut segments);
+    segments.retain(|s| !s.is_empty());
+    segments
+}
+
+fn is_assignment(raw: &str) -> bool {
+    match raw.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+// The native subset of checks.
+pub fn native_check(command: &str) -> Vec<Finding> {
+    let mut out: Vec<Finding> = Vec::new();
+    let mut push = |f: Finding| {
+        if !out
+            .iter()
+            .any(|o| o.code == f.code && o.message == f.message)
+        {
+            out.push(f);
+        }
+    };
+
+    if command.contains("find ")
+        && command.contains("xargs")
+        && !command.contains("-print0")
+        && !command.contains("-0")
+    {
+        push(finding(
+            "SC2038",
+            "find | xargs splits names on whitespace; use find -print0 | xargs -0, or find -exec."
+                .to_string(),
+        ));
+    }
+
+    for words in parse(command) {
+        let Some(program_idx) = words
+            .iter()
+            .position(|w| !is_assignment(&w.raw) && !KEYWORDS.contains(&w.raw.as_str()))
+        else {
+            continue;
+        };
+        let program = words[program_idx].raw.as_str();
+        let args = &words[program_idx + 1..];
+
+        if program == "for" {
+            if let Some(in_pos) = args.iter().position(|w| w.raw == "in") {
+                if args[in_pos + 1..]
+                    .iter()
+                    .any(|w| w.substs.iter().any(|s| s.contains("ls")))
+                {
+                    push(finding("SC2045", "Iterating over ls output breaks on odd filenames; loop over a glob instead.".to_string()));
+                }
+            }
+            continue;
+        }
+        if program == "case" {
+            continue;
+        }
+        if program == "eval"
+            && args
+                .iter()
+                .any(|w| !w.vars.is_empty() || !w.substs.is_empty() || w.quoted_expansion)
+        {
+            push(finding("eval", "eval re-parses expanded data as code; anything in the variables can inject commands.".to_string()));
+        }
+        if matches!(program, "sh" | "bash" | "zsh") {
+            if let Some(script) = args.iter().skip_while(|w| w.raw != "-c").nth(1) {
+                if script.quoted_expansion || !script.vars.is_empty() {
+                    push(finding("sh-c", "Variables are interpolated into the sh -c script; pass them as arguments ($1, $2) instead.".to_string()));
+                }
+            }
+        }
+
+        let mut in_test = program == "[[";
+        let mut after_dashdash = false;
+        for (i, w) in args.iter().enumerate() {
+            match w.raw.as_str() {
+                "[[" => in_test = true,
+                "]]" => in_test = false,
+                "--" => after_dashdash = true,
+                _ => {}
+            }
+            if in_test || is_assignment(&w.raw) {
+                continue;
+            }
+            for v in &w.vars {
+                if v == "$@" || v == "$*" {
+                    push(finding(
+                        "SC2068",
+                        format!("Double quote {} to keep arguments from being re-split.", v),
+                    ));
+                } else {
+                    push(finding(
+                        "SC2086",
+                        format!("Double quote {} to prevent globbing and word splitting.", v),
+                    ));
+                }
+            }
+            for s in &w.substs {
+                push(finding(
+                    "SC2046",
+                    format!("Quote {} to prevent word splitting.", s),
+                ));
+            }
+            if w.glob && w.vars.is_empty() {
+                let prev = i.checked_sub(1).map(|p| args[p].raw.as_str());
+                if program == "find"
+                    && matches!(prev, Some("-name" | "-iname" | "-path" | "-ipath"))
+                {
+                    push(finding("SC2061", format!("Quote the pattern {} so the shell does not expand it before find sees it.", w.raw)));
+                } else if w.raw.starts_with('*') && !after_dashdash && program != "find" {
+                    push(finding(
+                        "SC2035",
+                        format!(
+                            "Use ./{} so filenames starting with - are not taken as options.",
+                            w.raw
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+    out
+}
+
+// Run shellcheck on the command; None when it is not installed or cannot run.
+fn shellcheck(command: &str) -> Option<Vec<Finding>> {
+    which("shellcheck")?;
+    let output = run_with_stdin("shellcheck", &["-s", "bash", "-f", "gcc", "-"], command).ok()?;
+    // gcc format: -:1:6: warning: Double quote to prevent globbing and word splitting. [SC2086]
+    let findings = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(": ")?;
+            let (severity, rest) = rest.split_once(": ")?;
+            if severity == "style" {
+                return None;
+            }
+            let (message, code) = rest.rsplit_once(" [")?;
+            Some(finding(code.trim_end_matches(']'), message.to_string()))
+        })
+        .collect();
+    Some(findings)
+}
+
+pub fn check(command: &str) -> Vec<Finding> {
+    shellcheck(command).unwrap_or_else(|| native_check(command))
+}
+
+// Report quoting and injection hazards on stderr. Disabled with `lint = false` in config.toml.
+pub fn warn(command: &str) {
+    if cfg!(windows) || quiet() || !config().lint.unwrap_or(true) || is_not_able_response(command) {
+        return;
+    }
+    for f in check(command) {
+        eprintln!("Lint [{}]: {}", f.code, f.message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(command: &str) -> Vec<String> {
+        native_check(command).into_iter().map(|f| f.code).collect()
+    }
+
+    fn raws(command: &str) -> Vec<Vec<String>> {
+        parse(command)
+            .into_iter()
+            .map(|s| s.into_iter().map(|w| w.raw).collect())
+            .collect()
+    }
+
+    #[test]
+    fn splits_segments_on_operators_and_newlines() {
+        assert_eq!(
+            raws("cd /tmp && ls -la | wc -l; echo 'a; b'\ntrue"),
+            vec![
+                vec!["cd", "/tmp"],
+                vec!["ls", "-la"],
+                vec!["wc", "-l"],
+                vec!["echo", "'a; b'"],
+                vec!["true"],
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_quotes_escapes_and_substitutions_in_one_word() {
+        assert_eq!(
+            raws(r#"echo "a b" c\ d $(date +%F) `id -u`"#),
+            vec![vec!["echo", "\"a b\"", "c\\ d", "$(date +%F)", "`id -u`"]]
+        );
+    }
+
+    #[test]
+    fn records_only_unquoted_expansions() {
+        let words = parse(r#"rm $f "$g" '$h' ${dir}/x"#).remove(0);
+        assert_eq!(words[1].vars, vec!["$f"]);
+        assert!(words[2].vars.is_empty() && words[2].quoted_expansion);
+        assert!(words[3].vars.is_empty() && !words[3].quoted_expansion);
+        assert_eq!(words[4].vars, vec!["${dir}"]);
+    }
+
+    #[test]
+    fn flags_unquoted_variables_and_substitutions() {
+        assert_eq!(codes("rm $file"), vec!["SC2086"]);
+        assert_eq!(codes("cp $@ /tmp"), vec!["SC2068"]);
+        assert_eq!(codes("kill $(pgrep node)"), vec!["SC2046"]);
+        assert!(codes(r#"rm "$file""#).is_empty());
+        assert!(codes("[[ -f $file ]] && echo yes").is_empty());
+        assert!(codes("x=$y; echo $((1 + 2))").is_empty());
+    }
+
+    #[test]
+    fn flags_globs_that_reach_find_or_options() {
+        assert_eq!(codes("find . -name *.log"), vec!["SC2061"]);
+        assert!(codes("find . -name '*.log'").is_empty());
+        assert_eq!(codes("rm *.tmp"), vec!["SC2035"]);
+        assert!(codes("rm -- *.tmp").is_empty());
+        assert!(codes("rm ./*.tmp").is_empty());
+    }
+
+    #[test]
+    fn flags_xargs_ls_loops_and_reparsing() {
+        assert_eq!(codes("find . -type f | xargs rm"), vec!["SC2038"]);
+        assert!(codes("find . -type f -print0 | xargs -0 rm").is_empty());
+        assert_eq!(
+            codes("for f in $(ls); do echo \"$f\"; done"),
+            vec!["SC2045"]
+        );
+        assert!(codes(r#"eval "$cmd""#).contains(&"eval".to_string()));
+        assert_eq!(codes(r#"sh -c "rm $1""#), vec!["sh-c"]);
+    }
+}
@@ -0,0 +1,798 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use crate::config::config;
+use crate::context::estimate_tokens;
+use crate::output::{is_not_able_response, plain};
+use crate::policy::policy;
+use crate::redact::Redactor;
+use crate::{audit, breaker, cache, dump, image, models, ratelimit, reasoning, stream, tools};
+
+#[derive(Deserialize)]
+pub struct OpenRouterChoiceMessage {
+    // Null when the model only calls tools
+    #[serde(default)]
+    pub content: Option<String>,
+    // Reasoning arrives in any of these, depending on the model and provider (see reasoning.rs)
+    pub reasoning: Option<JsonValue>,
+    #[serde(default)]
+    pub reasoning_content: Option<JsonValue>,
+    #[serde(default)]
+    pub reasoning_details: Option<JsonValue>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "function_type")]
+    pub kind: String,
+    pub function: ToolFunction,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ToolFunction {
+    pub name: String,
+    // JSON-encoded arguments
+    #[serde(default)]
+    pub arguments: String,
+}
+
+fn function_type() -> String {
+    "function".to_string()
+}
+
+// Requests estimated above this many tokens are refused: far beyond any real instruction,
+// and usually a whole log or file pasted into the prompt by accident.
+const DEFAULT_MAX_PROMPT_TOKENS: usize = 32_000;
+
+// Seed sent with --deterministic, so repeated runs ask for the same sample.
+const SEED: u64 = 42;
+
+// Rounds of tool calls allowed before the model must answer.
+const MAX_TOOL_ROUNDS: usize = 5;
+
+#[derive(Deserialize)]
+pub struct OpenRouterChoice {
+    pub message: OpenRouterChoiceMessage,
+    // "length" when the answer was cut off by max_tokens
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct Usage {
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    // Credits charged, when usage accounting is requested
+    pub cost: Option<f64>,
+}
+
+impl Usage {
+    // Sum the rounds of a tool-calling exchange (or a clarified request); a field stays None
+    // only if every round lacks it.
+    pub fn add(&mut self, other: &Usage) {
+        fn sum<T: std::ops::Add<Output = T> + Copy>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            }
+        }
+        self.prompt_tokens = sum(self.prompt_tokens, other.prompt_tokens);
+        self.completion_tokens = sum(self.completion_tokens, other.completion_tokens);
+        self.cost = sum(self.cost, other.cost);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct OpenRouterResponse {
+    pub choices: Vec<OpenRouterChoice>,
+    // Upstream provider that served the request (OpenRouter routes across several)
+    pub provider: Option<String>,
+    // Model that actually answered, which can differ from the one asked for
+    pub model: Option<String>,
+    pub usage: Option<Usage>,
+}
+
+pub const OPENROUTER_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+// Key metadata (label, spend, limit, rate limit) and account credits, used by `ss status`.
+pub const AUTH_KEY_URL: &str = "https://openrouter.ai/api/v1/auth/key";
+pub const CREDITS_URL: &str = "https://openrouter.ai/api/v1/credits";
+
+// Connection and model settings shared by every mode that talks to the LLM.
+#[derive(Clone)]
+pub struct Settings {
+    // Tried in order; a key that is rate limited or out of credit falls over to the next
+    pub api_keys: Vec<String>,
+    pub model: String,
+    pub effort: String,
+    // Retry once on NOT ABLE TO ANSWER, with this model if set, else at high effort
+    pub retry_not_able: bool,
+    pub fallback_model: Option<String>,
+    // Mask hostnames, IPs, usernames, and emails in outbound messages
+    pub redact: bool,
+    // Serve only from the response cache; never send a request
+    pub offline: bool,
+    // Local tools offered to the model (--tools); empty disables tool calling
+    pub tools: Vec<String>,
+    // Latency budget per request (--max-wait), and the model to fall back to when it runs out
+    pub max_wait: Option<Duration>,
+    pub fast_model: Option<String>,
+    // Refuse models OpenRouter does not list as free (--free-only)
+    pub free_only: bool,
+    // Cap on response tokens (--max-tokens), and on the estimated size of a request
+    pub max_tokens: Option<u32>,
+    pub max_prompt_tokens: usize,
+    // --deterministic: temperature 0 and a fixed seed
+    pub deterministic: bool,
+    // -S with a person watching: stream the response and show reasoning as it arrives
+    pub stream_reasoning: bool,
+}
+
+impl Settings {
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        // Model selection priority: CLI flag (-m) > model picked with `ss models pick` >
+        // [models] entry for the mode > SNAPSHELL_OPENROUTER_MODEL env var > built-in default
+        let model = matches
+            .get_one::<String>("model")
+            .filter(|s| *s != "?")
+            .map(|s| s.to_string())
+            .or_else(models::picked)
+            .or_else(mode_model)
+            .or_else(|| std::env::var("SNAPSHELL_OPENROUTER_MODEL").ok())
+            .unwrap_or_else(|| "openai/gpt-oss-120b".to_string());
+
+        // Keys come from the selected profile, or else SNAPSHELL_OPENROUTER_API_KEY (comma-separated
+        // for several) followed by the `keys` pool in config.toml.
+        let profile = matches
+            .get_one::<String>("profile")
+            .cloned()
+            .or_else(|| std::env::var("SNAPSHELL_PROFILE").ok())
+            .filter(|p| !p.is_empty());
+        let api_keys: Vec<String> = match &profile {
+            Some(name) => match config().profiles.get(name) {
+                Some(p) => p.keys.clone(),
+                None => {
+                    eprintln!("No profile `{}` in config.toml.", name);
+                    std::process::exit(1);
+                }
+            },
+            None => std::env::var("SNAPSHELL_OPENROUTER_API_KEY")
+                .unwrap_or_default()
+                .split(',')
+                .map(|k| k.trim().to_string())
+                .chain(config().keys.iter().cloned())
+                .filter(|k| !k.is_empty())
+                .collect(),
+        };
+        let offline = matches.get_flag("offline")
+            || std::env::var("SNAPSHELL_OFFLINE").is_ok_and(|v| !v.is_empty() && v != "0");
+        if api_keys.is_empty() && !offline {
+            eprintln!("Set SNAPSHELL_OPENROUTER_API_KEY env var for OpenRouter integration.");
+        }
+
+        // Determine reasoning settings (OpenAI-style 'effort')
+        let effort = matches
+            .get_one::<String>("reasoning")
+            .cloned()
+            .unwrap_or_else(|| "low".to_string());
+
+        let fallback_model = matches
+            .get_one::<String>("fallback-model")
+            .cloned()
+            .or_else(|| std::env::var("SNAPSHELL_FALLBACK_MODEL").ok());
+
+        let tools = tools_from(matches);
+        Settings {
+            api_keys,
+            model,
+            effort,
+            retry_not_able: matches.get_flag("retry"),
+            fallback_model,
+            // The admin policy can force redaction on; nothing here turns it off.
+            redact: matches.get_flag("redact")
+                || config().redact.enabled
+                || policy().force_redaction,
+            offline,
+            tools: tools.clone(),
+            max_wait: matches
+                .get_one::<Duration>("max-wait")
+                .copied()
+                .or_else(|| {
+                    config()
+                        .max_wait
+                        .as_deref()
+                        .and_then(|s| parse_duration(s).ok())
+                }),
+            fast_model: std::env::var("SNAPSHELL_FAST_MODEL")
+                .ok()
+                .or_else(|| config().fast_model.clone()),
+            free_only: matches.get_flag("free-only") || config().free_only,
+            max_tokens: matches
+                .get_one::<u32>("max-tokens")
+                .copied()
+                .or(config().max_tokens),
+            max_prompt_tokens: matches
+                .get_one::<usize>("max-prompt-tokens")
+                .copied()
+                .or(config().max_prompt_tokens)
+                .unwrap_or(DEFAULT_MAX_PROMPT_TOKENS),
+            deterministic: matches.get_flag("deterministic"),
+            // Tool rounds are not streamed, so neither is reasoning when tools are on.
+            stream_reasoning: matches.get_flag("show-reasoning")
+                && tools.is_empty()
+                && !plain()
+                && std::io::stdout().is_terminal()
+                && std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+static MODE: OnceLock<Vec<String>> = OnceLock::new();
+
+// Record which [models] keys apply to this run, most specific first: the subcommand
+// name (batch also falls back to single/multiline), or chat, multiline, or single for
+// the top-level command.
+pub fn init_mode(matches: &ArgMatches) {
+    let shape = |multiline: bool| if multiline { "multiline" } else { "single" };
+    let keys = match matches.subcommand() {
+        Some(("batch", sub)) => vec!["batch", shape(sub.get_flag("multiline"))],
+        Some((name, _)) => vec![name],
+        None if matches.get_flag("all") => vec!["chat"],
+        None => vec![shape(
+            matches.get_flag("multiline")
+                || matches.contains_id("output")
+                || matches.get_flag("annotate"),
+        )],
+    };
+    let _ = MODE.set(keys.into_iter().map(String::from).collect());
+}
+
+fn mode_model() -> Option<String> {
+    let models = &config().models;
+    MODE.get()?
+        .iter()
+        .find_map(|k| models.get(k))
+        .filter(|m| !m.is_empty())
+        .cloned()
+}
+
+// "3s", "500ms", "1m", "2h", "1d", or plain seconds ("2.5").
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, scale) = if let Some(n) = s.strip_suffix("ms") {
+        (n, 0.001)
+    } else if let Some(n) = s.strip_suffix('s') {
+        (n, 1.0)
+    } else if let Some(n) = s.strip_suffix('m') {
+        (n, 60.0)
+    } else if let Some(n) = s.strip_suffix('h') {
+        (n, 3600.0)
+    } else if let Some(n) = s.strip_suffix('d') {
+        (n, 86400.0)
+    } else {
+        (s, 1.0)
+    };
+    match number.trim().parse::<f64>() {
+        Ok(v) if v > 0.0 && v.is_finite() => Ok(Duration::from_secs_f64(v * scale)),
+        _ => Err(format!("invalid duration `{}` (try 3s or 500ms)", s)),
+    }
+}
+
+// --tools alone enables the `tools` allowlist from config.toml, or every tool when it is
+// unset; --tools=which,help picks tools explicitly (still limited to the allowlist).
+fn tools_from(matches: &ArgMatches) -> Vec<String> {
+    let Some(requested) = matches.get_many::<String>("tools") else {
+        return Vec::new();
+    };
+    let allowed: Vec<String> = config()
+        .tools
+        .clone()
+        .unwrap_or_else(|| tools::ALL.iter().map(|t| t.to_string()).collect());
+    let requested: Vec<&String> = requested.filter(|t| !t.is_empty()).collect();
+    if requested.is_empty() {
+        return allowed;
+    }
+    requested
+        .into_iter()
+        .filter(|t| {
+            let ok = allowed.contains(t);
+            if !ok {
+                eprintln!(
+                    "Tool `{}` is unknown or not in the `tools` allowlist; skipping it.",
+                    t
+                );
+            }
+            ok
+        })
+        .cloned()
+        .collect()
+}
+
+// The first choice of a completion, flattened.
+pub struct Completion {
+    pub content: String,
+    pub reasoning: Option<JsonValue>,
+    // Tokens and credits of every round behind it; empty when nothing was sent
+    pub usage: Usage,
+    // Model and provider that served the last round; None when nothing was sent
+    pub model: Option<String>,
+    pub provider: Option<String>,
+    // Taken from the response cache instead of the API
+    pub cached: bool,
+}
+
+pub async fn complete(settings: &Settings, messages: &[JsonValue]) -> Result<Completion> {
+    let size: usize = messages
+        .iter()
+        .filter_map(|m| m.get("content").and_then(|c| c.as_str()))
+        .map(estimate_tokens)
+        .sum();
+    if size > settings.max_prompt_tokens {
+        bail!(
+            "the prompt is ~{} tokens, over the limit of {}; nothing was sent. If the prompt is meant to be this long, raise the limit with --max-prompt-tokens or max_prompt_tokens in config.toml",
+            size,
+            settings.max_prompt_tokens
+        );
+    }
+    let cache_key = cache::key(settings, messages);
+    if settings.offline {
+        return cache::get(&cache_key)
+            .ok_or_else(|| anyhow!("offline and no cached response for this request"));
+    }
+
+    if let Some(left) = breaker::open_for(&settings.model) {
+        return short_circuit(settings, messages, left, &cache_key).await;
+    }
+
+    // Waiting for a rate-limit slot is local: it is not part of the --max-wait budget and
+    // says nothing about the model's health.
+    ratelimit::acquire().await?;
+    let completion = match settings.max_wait {
+        None => breaker::watch(&settings.model, request(settings, messages).await)?,
+        Some(budget) => match timeout(budget, request(settings, messages)).await {
+            Ok(result) => breaker::watch(&settings.model, result)?,
+            Err(_) => {
+                breaker::failure(&settings.model);
+                over_budget(settings, messages, budget, &cache_key).await?
+            }
+        },
+    };
+    if !completion.content.is_empty() && !is_not_able_response(&completion.content) {
+        cache::put(&cache_key, &completion);
+    }
+    Ok(completion)
+}
+
+// The request was cancelled after --max-wait: try the fast model within the same budget,
+// then the response cache, before giving up with TimedOut.
+async fn over_budget(
+    settings: &Settings,
+    messages: &[JsonValue],
+    budget: Duration,
+    cache_key: &str,
+) -> Result<Completion> {
+    if let Some(fast) = settings.fast_model.clone().filter(|m| *m != settings.model) {
+        eprintln!("No answer within {:?}; falling back to {}...", budget, fast);
+        let mut quick = settings.clone();
+        quick.model = fast;
+        if ratelimit::acquire().await.is_ok() {
+            if let Ok(Ok(c)) = timeout(budget, request(&quick, messages)).await {
+                return Ok(c);
+            }
+        }
+    }
+    if let Some(c) = cache::get(cache_key) {
+        eprintln!("No answer within {:?}; using the cached answer.", budget);
+        return Ok(c);
+    }
+    Err(TimedOut(budget).into())
+}
+
+// The model's circuit is open: go to the fallback model, then the fast model, then the
+// response cache, without trying the model itself.
+async fn short_circuit(
+    settings: &Settings,
+    messages: &[JsonValue],
+    left: Duration,
+    cache_key: &str,
+) -> Result<Completion> {
+    let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+    let alternates = [&settings.fallback_model, &settings.fast_model];
+    for m in alternates.into_iter().flatten() {
+        if *m == settings.model || breaker::open_for(m).is_some() {
+            continue;
+        }
+        eprintln!(
+            "{} is failing (skipped for another {}s); using {}...",
+            settings.model, secs, m
+        );
+        let mut other = settings.clone();
+        other.model = m.clone();
+        ratelimit::acquire().await?;
+        if let Ok(c) = breaker::watch(m, request(&other, messages).await) {
+            return Ok(c);
+        }
+    }
+    if let Some(c) = cache::get(cache_key) {
+        eprintln!(
+            "{} is failing (skipped for another {}s); using the cached answer.",
+            settings.model, secs
+        );
+        return Ok(c);
+    }
+    // Nothing to fall back to: one more try beats failing outright.
+    eprintln!(
+        "{} is failing (skipped for another {}s) and there is nothing to fall back to; trying it anyway...",
+        settings.model, secs
+    );
+    ratelimit::acquire().await?;
+    breaker::watch(&settings.model, request(settings, messages).await).with_context(|| {
+        format!(
+            "{} is failing; set --fallback-model or SNAPSHELL_FAST_MODEL to have something to fall back to",
+            settings.model
+        )
+    })
+}
+
+// A 429 from the provider behind OpenRouter, as opposed to a limit on the key.
+#[derive(Debug)]
+pub struct ProviderBusy(pub String);
+
+impl std::fmt::Display for ProviderBusy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is rate limiting the model (429)", self.0)
+    }
+}
+
+impl std::error::Error for ProviderBusy {}
+
+// Returned when neither the model nor the fallbacks answered within --max-wait.
+#[derive(Debug)]
+pub struct TimedOut(pub Duration);
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no answer within {:?}", self.0)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+// The caller takes the rate-limit slot for the first call, before any timer starts; each
+// further tool round takes its own.
+async fn request(settings: &Settings, messages: &[JsonValue]) -> Result<Completion> {
+    if settings.free_only {
+        models::ensure_free(&settings.model).await?;
+    }
+    if messages.iter().any(image::has_image) {
+        models::ensure_vision(&settings.model).await?;
+    }
+    // With redaction, the substitution table lives only for this request.
+    let mut redactor = Redactor::default();
+    let mut redact = |text: &str| -> String {
+        if settings.redact {
+            redactor.redact(text)
+        } else {
+            text.to_string()
+        }
+    };
+    let mut conversation: Vec<JsonValue> = messages
+        .iter()
+        .map(|m| {
+            let mut m = m.clone();
+            if let Some(text) = m.get("content").and_then(|c| c.as_str()) {
+                m["content"] = JsonValue::String(redact(text));
+            } else if let Some(parts) = m.get_mut("content").and_then(|c| c.as_array_mut()) {
+                // Multimodal messages: only the text parts can be redacted.
+                for part in parts {
+                    if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                        part["text"] = JsonValue::String(redact(text));
+                    }
+                }
+            }
+            m
+        })
+        .collect();
+    let tool_defs = tools::definitions(&settings.tools);
+
+    let mut round = 0;
+    let mut usage = Usage::default();
+    let mut served_by;
+    let mut provider;
+    let message = loop {
+        // Include top-level reasoning object following OpenRouter's API (e.g. { "reasoning": { "effort": "high" } })
+        // usage.include asks OpenRouter to report the cost of each call (shown by `ss cost`)
+        let mut body = serde_json::json!({"model": settings.model, "messages": conversation, "reasoning": {"effort": settings.effort}, "usage": {"include": true}});
+        if let Some(max) = settings.max_tokens {
+            body["max_tokens"] = max.into();
+        }
+        // Providers without seed support ignore it; temperature 0 still narrows sampling.
+        if settings.deterministic {
+            body["temperature"] = 0.into();
+            body["seed"] = SEED.into();
+        }
+        // The last round withholds tools so the model has to answer.
+        if !tool_defs.is_empty() && round < MAX_TOOL_ROUNDS {
+            body["tools"] = JsonValue::Array(tool_defs.clone());
+        } else if settings.stream_reasoning && tool_defs.is_empty() {
+            body["stream"] = true.into();
+        }
+        if round > 0 {
+            ratelimit::acquire().await?;
+        }
+        let out = query_openrouter(&settings.api_keys, &body).await?;
+        if let Some(u) = &out.usage {
+            usage.add(u);
+        }
+        served_by = out.model.or_else(|| Some(settings.model.clone()));
+        provider = out.provider;
+        // The API returns choices[].message.content and may include choices[].message.reasoning
+        let Some(choice) = out.choices.into_iter().next() else {
+            break None;
+        };
+        if choice.message.tool_calls.is_empty() || round >= MAX_TOOL_ROUNDS {
+            if choice.finish_reason.as_deref() == Some("length") {
+                eprintln!(
+                    "Warning: the answer was cut off at the response limit{}; it may be incomplete. Raise it with --max-tokens or max_tokens in config.toml.",
+                    settings
+                        .max_tokens
+                        .map(|m| format!(" of {} tokens", m))
+                        .unwrap_or_default()
+                );
+            }
+            break Some(choice.message);
+        }
+        conversation.push(serde_json::json!({"role": "assistant", "content": choice.message.content, "tool_calls": choice.message.tool_calls}));
+        for call in &choice.message.tool_calls {
+            let result = tools::call(
+                &call.function.name,
+                &call.function.arguments,
+                &settings.tools,
+            );
+            conversation.push(serde_json::json!({"role": "tool", "tool_call_id": call.id, "content": redact(&result)}));
+        }
+        round += 1;
+    };
+
+    let completion = match message {
+        Some(m) => {
+            let (content, reasoning) = reasoning::normalize(&m);
+            Completion {
+                content: redactor.restore(&content),
+                reasoning: reasoning.map(|r| JsonValue::String(redactor.restore(&r))),
+                usage,
+                model: served_by,
+                provider,
+                cached: false,
+            }
+        }
+        None => Completion {
+            content: String::new(),
+            reasoning: None,
+            usage,
+            model: served_by,
+            provider,
+            cached: false,
+        },
+    };
+    Ok(completion)
+}
+
+// Same as `complete`, but reports the failure and exits like the CLI always has.
+// With --retry, a NOT ABLE TO ANSWER is retried once with a stronger setup.
+pub async fn complete_or_exit(settings: &Settings, messages: &[JsonValue]) -> Completion {
+    complete_or_else(settings, messages, || None).await
+}
+
+// Like `complete_or_exit`, with `on_timeout` as a last resort when --max-wait runs out.
+pub async fn complete_or_else(
+    settings: &Settings,
+    messages: &[JsonValue],
+    on_timeout: impl FnOnce() -> Option<Completion>,
+) -> Completion {
+    let exit_on_err = |e: anyhow::Error| -> Completion {
+        eprintln!("LLM request failed: {}", e);
+        std::process::exit(1);
+    };
+    let first = match complete(settings, messages).await {
+        Ok(c) => c,
+        Err(e) if e.is::<TimedOut>() => on_timeout().unwrap_or_else(|| exit_on_err(e)),
+        Err(e) => exit_on_err(e),
+    };
+    if !settings.retry_not_able || !is_not_able_response(&first.content) {
+        return first;
+    }
+
+    let mut stronger = settings.clone();
+    stronger.retry_not_able = false;
+    if let Some(m) = settings
+        .fallback_model
+        .clone()
+        .filter(|m| *m != settings.model)
+    {
+        eprintln!("Model could not answer; retrying with {}...", m);
+        stronger.model = m;
+    } else if settings.effort != "high" {
+        eprintln!("Model could not answer; retrying with reasoning high...");
+        stronger.effort = "high".to_string();
+    } else {
+        return first;
+    }
+    complete(&stronger, messages)
+        .await
+        .unwrap_or_else(exit_on_err)
+}
+
+pub async fn query_openrouter(
+    api_keys: &[String],
+    body: &serde_json::Value,
+) -> Result<OpenRouterResponse> {
+    let no_key = [String::new()];
+    let keys = if api_keys.is_empty() {
+        &no_key[..]
+    } else {
+        api_keys
+    };
+    let mut keys = keys.iter().peekable();
+    loop {
+        let key = keys.next().expect("at least one key");
+        let result = send(key, body).await;
+        // Every outbound call is audited, failures included
+        audit::record(OPENROUTER_URL, &key_label(key), body, &result);
+        match &result {
+            Err(e) if keys.peek().is_some() && key_exhausted(e) => {
+                eprintln!(
+                    "Key {} failed ({}); trying the next key.",
+                    key_label(key),
+                    e
+                );
+            }
+            _ => return result,
+        }
+    }
+}
+
+// A one-token request, used by `ss doctor` to prove a key and model work.
+pub async fn ping(api_key: &str, model: &str) -> Result<()> {
+    let body = serde_json::json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "ping"}],
+        "max_tokens": 1,
+    });
+    let result = send(api_key, &body).await;
+    audit::record(OPENROUTER_URL, &key_label(api_key), &body, &result);
+    result.map(|_| ())
+}
+
+// An authenticated GET of one of OpenRouter's account endpoints, with the response
+// headers (for rate-limit headroom) and how long it took.
+pub async fn get_json(
+    url: &str,
+    api_key: &str,
+) -> Result<(JsonValue, reqwest::header::HeaderMap, Duration)> {
+    policy().check_endpoint(url)?;
+    let start = std::time::Instant::now();
+    let resp = client()
+        .get(url)
+        .bearer_auth(api_key)
+        .send()
+        .await?
+        .error_for_status()?;
+    let elapsed = start.elapsed();
+    let headers = resp.headers().clone();
+    Ok((resp.json().await?, headers, elapsed))
+}
+
+// Errors that another key may not hit: rate limits, missing credit, revoked keys.
+fn key_exhausted(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .map(|s| matches!(s.as_u16(), 401 | 402 | 403 | 429))
+        .unwrap_or(false)
+}
+
+// Identifies a key in logs and `ss cost` without revealing it.
+pub fn key_label(key: &str) -> String {
+    if key.is_empty() {
+        return "none".to_string();
+    }
+    let tail: String = key
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("...{}", tail)
+}
+
+// One client per process, so the connection opened by warm_up is the one requests reuse.
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+// Start the DNS lookup and TLS handshake in the background while context is gathered.
+pub fn warm_up(settings: &Settings) {
+    if settings.offline
+        || policy()
+            .check_request(OPENROUTER_URL, &settings.model)
+            .is_err()
+    {
+        return;
+    }
+    tokio::spawn(async {
+        let _ = client().head(OPENROUTER_URL).send().await;
+    });
+}
+
+async fn send(api_key: &str, body: &serde_json::Value) -> Result<OpenRouterResponse> {
+    let policy = policy();
+    let model = body
+        .get("model")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default();
+    policy.check_request(OPENROUTER_URL, model)?;
+
+    // Pin OpenRouter's routing to the providers the policy allows.
+    let mut body = body.clone();
+    if let Some(providers) = &policy.allowed_providers {
+        body["provider"] = serde_json::json!({"only": providers, "allow_fallbacks": false});
+    }
+
+    let mut headers = Vec::new();
+    if !api_key.is_empty() {
+        headers.push(("Authorization".to_string(), format!("Bearer {}", api_key)));
+    }
+    let attribution = &config().openrouter;
+    if let Some(referer) = attribution.referer.as_deref().filter(|r| !r.is_empty()) {
+        headers.push(("HTTP-Referer".to_string(), referer.to_string()));
+    }
+    let title = attribution.title.as_deref().unwrap_or("snapshell");
+    if !title.is_empty() {
+        headers.push(("X-Title".to_string(), title.to_string()));
+    }
+    for (name, value) in &attribution.headers {
+        headers.push((name.clone(), value.clone()));
+    }
+    dump::request(OPENROUTER_URL, api_key, &headers, &body);
+
+    let mut req = client().post(OPENROUTER_URL).json(&body);
+    for (name, value) in &headers {
+        req = req.header(name.as_str(), value.as_str());
+    }
+    let resp = req.send().await?;
+    // Read the body before checking the status so error payloads can be dumped too.
+    let status = resp.status();
+    let failed = resp.error_for_status_ref().err();
+    let text = if failed.is_none() && body["stream"] == true {
+        stream::read(resp).await?
+    } else {
+        resp.text().await?
+    };
+    dump::response(status.as_u16(), api_key, &text);
+    if let Some(e) = failed {
+        // OpenRouter names the provider when the 429 is theirs rather than this key's.
+        let upstream = serde_json::from_str::<JsonValue>(&text).ok().and_then(|v| {
+            v.pointer("/error/metadata/provider_name")
+                .and_then(|p| p.as_str())
+                .map(str::to_string)
+        });
+        if let Some(provider) = upstream.filter(|_| status.as_u16() == 429) {
+            return Err(anyhow::Error::from(e).context(ProviderBusy(provider)));
+        }
+        return Err(e.into());
+    }
+    let out = serde_json::from_str::<OpenRouterResponse>(&text)?;
+    policy.check_provider(out.provider.as_deref())?;
+    Ok(out)
+}
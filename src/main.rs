@@ -1,117 +1,174 @@
-use anyhow::Result;
-use chrono::Utc;
-use clap::{Arg, ArgAction, Command};
-use directories::ProjectDirs;
-use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
-use std::fs::{create_dir_all, OpenOptions};
-use std::io::{self, Read, Write};
+mod atuin;
+mod audit;
+mod batch;
+mod breaker;
+mod cache;
+mod candidates;
+mod chat;
+mod clarify;
+mod cli;
+mod clipboard;
+mod clipring;
+mod collectors;
+mod confidence;
+mod config;
+mod context;
+mod cost;
+mod diff;
+mod doctor;
+mod dotenv;
+mod dump;
+mod env;
+mod escalate;
+mod exec;
+mod history;
+mod ignore;
+mod image;
+mod launcher;
+mod lint;
+mod llm;
+mod models;
+mod modes;
+mod offline;
+mod output;
+mod paste;
+mod pathcheck;
+mod paths;
+mod personalize;
+mod policy;
+mod privilege;
+mod project;
+mod prompt;
+mod ratelimit;
+mod reasoning;
+mod record;
+mod redact;
+mod refine;
+mod registry;
+mod runbook;
+mod schedule;
+mod script;
+mod selfupdate;
+mod serve;
+mod session;
+mod snippet;
+mod status;
+mod stream;
+mod template;
+mod term;
+mod tools;
+mod typo;
+mod widget;
+
+use anyhow::{bail, Result};
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
-#[derive(Serialize, Deserialize)]
-struct HistoryEntry {
-    timestamp: String,
-    prompt: String,
-    command: String,
-}
+use crate::history::{last_entry, print_history, save_history};
+use crate::llm::{complete_or_else, Settings};
+use crate::output::{
+    emit_command, is_not_able_response, print_reasoning, refuse_if_blocked, write_output_file,
+};
+use crate::prompt::{chat_system, command_system, few_shot};
 
-#[derive(Deserialize)]
-struct OpenRouterChoiceMessage {
-    content: String,
-    // OpenRouter may include a reasoning object on the message
-    reasoning: Option<JsonValue>,
-}
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::load();
+    let matches = cli::build().get_matches();
+    paths::migrate();
+    dump::init(&matches);
+    history::init(&matches);
+    output::init(&matches);
+    term::init(&matches);
+    if !matches!(matches.subcommand(), Some(("env", _))) {
+        dotenv::note_untrusted();
+    }
 
-#[derive(Deserialize)]
-struct OpenRouterChoice {
-    message: OpenRouterChoiceMessage,
-}
+    // doctor reports broken config and policy files instead of stopping on them.
+    if let Some(("doctor", sub)) = matches.subcommand() {
+        return doctor::run(sub).await;
+    }
 
-#[derive(Deserialize)]
-struct OpenRouterResponse {
-    choices: Vec<OpenRouterChoice>,
-}
+    // Load the admin policy and user config up front so a broken file fails before any request.
+    policy::policy();
+    config::command_rules();
+    env::init_language(&matches);
+    llm::init_mode(&matches);
+    prompt::init_extra(&matches);
+    // Environment detection, the --auto-context directory summary, and collectors do not
+    // depend on each other, so they run side by side.
+    env::prefetch();
+    std::thread::scope(|s| {
+        s.spawn(|| project::init(&matches));
+        collectors::init(&matches);
+    });
+
+    // -m ?: pick the model before anything is sent; with nothing else to do, stop there.
+    if matches.get_one::<String>("model").is_some_and(|m| m == "?") {
+        models::pick().await?;
+        if matches.subcommand().is_none()
+            && !matches.contains_id("input")
+            && !matches.get_flag("all")
+        {
+            return Ok(());
+        }
+    }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let matches = Command::new("snapshell")
-        .about("Snappy shell command generation (minimal)")
-        .arg(Arg::new("input").help("Command instruction or chat text").index(1).num_args(1).required(false))
-        .arg(
-            Arg::new("history")
-                .short('H')
-                .long("history")
-                .help("Show history of prompts and generated commands")
-                .action(ArgAction::SetTrue),
-        )
-        .arg(
-            Arg::new("all")
-                .short('a')
-                .long("ask")
-                .help("Interactive LLM chat mode (prints conversation)")
-                .action(ArgAction::SetTrue),
-        )
-        .arg(
-            Arg::new("reasoning")
-                .short('r')
-                .long("reasoning")
-                .help("Reasoning effort: low, medium, or high (default: low)")
-                .num_args(1),
-        )
-        .arg(
-            Arg::new("model")
-                .short('m')
-                .long("model")
-                .help("Model to use (defaults to openai/gpt-oss-120b)")
-                .num_args(1),
-        )
-        .arg(
-            Arg::new("multiline")
-                .short('L')
-                .long("multiline")
-                .help("Allow multiline/multi-line shell script output instead of forcing a single-line command")
-                .action(ArgAction::SetTrue),
-        )
-        .arg(
-            Arg::new("pt")
-                .long("pt")
-                .help("Prettify table output: true|false (default: true). When true, instruct model to format tables in psql-style ASCII tables (no markdown). Use --pt=false to disable.")
-                .num_args(1)
-                .value_parser(["true", "false"])
-                .default_value("true"),
-        )
-        .arg(
-            Arg::new("system")
-                .short('s')
-                .long("system")
-                .help("Custom system instruction (overrides defaults). Can be used for both single- and multiline modes unless specific flags are provided.")
-                .num_args(1),
-        )
-        .arg(
-            Arg::new("system-single")
-                .long("system-single")
-                .help("Custom system instruction for single-line mode")
-                .num_args(1),
-        )
-        .arg(
-            Arg::new("system-multiline")
-                .long("system-multiline")
-                .help("Custom system instruction for multiline mode")
-                .num_args(1),
-        )
-        .arg(
-            Arg::new("show-reasoning")
-                .short('S')
-                .long("show-reasoning")
-                .help("Include model reasoning in output as a trailing JSON object {\"reasoning\": \"...\"}")
-                .action(ArgAction::SetTrue),
-        )
-        .get_matches();
-
-    let prompt = matches
-        .get_one::<String>("input")
-        .cloned()
-        .or_else(|| std::env::args().nth(1));
+    match matches.subcommand() {
+        Some(("alias", sub)) => return modes::alias::run(sub).await,
+        Some(("audit", sub)) => return audit::run(sub),
+        Some(("batch", sub)) => return batch::run(sub, &matches).await,
+        Some(("chat", sub)) => return session::run(sub),
+        Some(("cheat", sub)) => return modes::cheat::run(sub).await,
+        Some(("clip", sub)) => return clipring::run(sub),
+        Some(("cloud", sub)) => return modes::cloud::run(sub).await,
+        Some(("cost", sub)) => return cost::run(sub),
+        Some(("doctor", _)) => unreachable!("handled above"),
+        Some(("docker", sub)) => return modes::docker::run(sub).await,
+        Some(("env", sub)) => return dotenv::run(sub),
+        Some(("ffmpeg", sub)) => return modes::ffmpeg::run(sub).await,
+        Some(("find", sub)) => return modes::find::run(sub).await,
+        Some(("git", sub)) => return modes::git::run(sub).await,
+        Some(("history", sub)) => return history::run(sub).await,
+        Some(("http", sub)) => return modes::http::run(sub).await,
+        Some(("init", sub)) => return widget::run(sub),
+        Some(("install", sub)) => return modes::install::run(sub).await,
+        Some(("jq", sub)) => return modes::jq::run(sub).await,
+        Some(("k8s", sub)) => return modes::k8s::run(sub).await,
+        Some(("man", sub)) => return modes::man::run(sub).await,
+        Some(("models", sub)) => return models::run(sub).await,
+        Some(("runbook", sub)) => return runbook::run(sub).await,
+        Some(("self-update", sub)) => return selfupdate::run(sub).await,
+        Some(("serve", sub)) => return serve::run(sub, &matches).await,
+        Some(("snippet", sub)) => return snippet::run(sub).await,
+        Some(("sql", sub)) => return modes::sql::run(sub).await,
+        Some(("status", sub)) => return status::run(sub).await,
+        Some(("systemd", sub)) => return modes::systemd::run(sub).await,
+        Some(("text", sub)) => return modes::text::run(sub).await,
+        Some(("tf", sub)) => return modes::tf::run(sub).await,
+        Some(("watch", sub)) => return modes::watch::run(sub).await,
+        Some((name, _)) => bail!("subcommand {} is not handled", name),
+        None => {}
+    }
+
+    let prompt = matches.get_one::<String>("input").cloned().or_else(|| {
+        std::env::args_os()
+            .nth(1)
+            .map(|a| a.to_string_lossy().into_owned())
+    });
+
+    let candidate_count = matches
+        .get_one::<u8>("candidates")
+        .map_or(1, |n| *n as usize);
+    // The widget's key pressed again on a candidate it put in the buffer: the next one.
+    if widget::driving() {
+        if let Some((next, i, total)) = prompt.as_deref().and_then(candidates::next) {
+            if !output::quiet() {
+                eprintln!("Candidate {}/{}", i, total);
+            }
+            return output::deliver(&next);
+        }
+    }
 
     let interactive = matches.get_flag("all");
     let show_history = matches.get_flag("history");
@@ -124,329 +181,246 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
-    let prompt = match prompt {
-        Some(p) => p,
-        None => {
+    // --regen and --refine start from the most recent history entry instead of a new prompt.
+    let refine_instruction = matches.get_one::<String>("refine").cloned();
+    let previous = if matches.get_flag("regen") || refine_instruction.is_some() {
+        match last_entry()? {
+            Some(e) => Some(e),
+            None => {
+                eprintln!("No history entry to regenerate or refine.");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let prompt = match (&previous, prompt) {
+        (Some(prev), _) => prev.prompt.clone(),
+        // The positional argument, if any, becomes the instruction for the clipboard.
+        (None, _) if matches.get_flag("from-clipboard") => {
+            let typed = match matches.get_one::<String>("input") {
+                Some(p) => Some(template::apply(&snippet::resolve(p)?, &matches)?),
+                None => None,
+            };
+            clipboard::prompt(&matches, typed.as_deref())?
+        }
+        (None, Some(p)) => typo::correct(template::apply(&snippet::resolve(&p)?, &matches)?),
+        (None, None) => {
             eprintln!("Usage: ss 'command instructions'  (or ss -a 'ask something')");
             std::process::exit(1);
         }
     };
 
-    // Model selection priority: CLI flag (-m) > SNAPSHELL_OPENROUTER_MODEL env var > built-in default
-    let model = matches
-        .get_one::<String>("model")
-        .map(|s| s.to_string())
-        .or_else(|| std::env::var("SNAPSHELL_OPENROUTER_MODEL").ok())
-        .unwrap_or_else(|| "openai/gpt-oss-120b".to_string());
-
-    // Read SNAPSHELL_OPENROUTER_API_KEY from env or config (intentionally not backwards-compatible)
-    let api_key = std::env::var("SNAPSHELL_OPENROUTER_API_KEY").unwrap_or_default();
-    if api_key.is_empty() {
-        eprintln!("Set SNAPSHELL_OPENROUTER_API_KEY env var for OpenRouter integration.");
-    }
+    let settings = Settings::from_matches(&matches);
+    personalize::init(&settings).await;
+    // Open the API connection while the prompt and environment note are put together.
+    llm::warm_up(&settings);
 
     // Build request payload with support for configurable system instructions.
-    let allow_multiline = matches.get_flag("multiline");
-
-    // Read optional custom system instructions from CLI or env vars.
-    let cli_system = matches.get_one::<String>("system").map(|s| s.as_str());
-    let cli_system_single = matches
-        .get_one::<String>("system-single")
-        .map(|s| s.as_str());
-    let cli_system_multi = matches
-        .get_one::<String>("system-multiline")
-        .map(|s| s.as_str());
-
-    let env_system = std::env::var("SNAPSHELL_SYSTEM").ok();
-    let env_system_single = std::env::var("SNAPSHELL_SYSTEM_SINGLE").ok();
-    let env_system_multi = std::env::var("SNAPSHELL_SYSTEM_MULTILINE").ok();
-
-    // Prepare messages vector. If not interactive, choose a system instruction using priority:
-    // CLI specific > CLI generic > ENV specific > ENV generic > built-in default.
-    let mut messages = Vec::new();
+    // Writing a script file or annotating one implies multiline output.
+    let output_path = matches.get_one::<PathBuf>("output");
+    let annotate = matches.get_flag("annotate");
+    let allow_multiline = matches.get_flag("multiline") || output_path.is_some() || annotate;
+    let show_reasoning = matches.get_flag("show-reasoning");
 
-    if !interactive {
-        let default_single = "You are a strict shell command generator. OUTPUT ONLY shell commands or shell syntax in plain text with no explanations, no commentary, and no additional prose. DO NOT output any markdown, code fences, backticks, or formatting of any kind. The entire response MUST be a single-line shell command with no extra text. Never add numbering, bullets, examples, or any text before or after the command. If you do NOT know the correct command, respond exactly with the following format and nothing else: (NOT ABLE TO ANSWER): <one-sentence reason> — the reason should be a single short sentence explaining why the command cannot be provided. Always respond only with the shell command(s) or the one-line failure phrase in the format above.";
-        let default_multi = "You are a strict shell command generator. OUTPUT ONLY shell commands or shell syntax in plain text with no explanations, no commentary, and no additional prose. DO NOT output any markdown, code fences, backticks, or formatting of any kind. Multi-line shell scripts are allowed when necessary. Never add numbering, bullets, examples, or any text before or after the command. If you do NOT know the correct command, respond exactly with the following format and nothing else: (NOT ABLE TO ANSWER): <one-sentence reason> — the reason should be a single short sentence explaining why the command cannot be provided. Always respond only with the shell command(s) or the one-line failure phrase in the format above.";
-
-        let mut sys = if let Some(s) = cli_system {
-            s.to_string()
-        } else if allow_multiline {
-            if let Some(s) = cli_system_multi {
-                s.to_string()
-            } else if let Some(s) = env_system_multi {
-                s
-            } else if let Some(s) = env_system.clone() {
-                s
-            } else {
-                default_multi.to_string()
-            }
-        } else {
-            if let Some(s) = cli_system_single {
-                s.to_string()
-            } else if let Some(s) = env_system_single.clone() {
-                s
-            } else if let Some(s) = env_system.clone() {
-                s
-            } else {
-                default_single.to_string()
-            }
-        };
+    let mut messages = Vec::new();
 
-        // Append detected environment note so the model tailors commands to the user's OS/distro
-        let env_note = format!(" Target environment: {}. Ensure generated commands are compatible with this environment.", detect_environment());
-        sys.push_str(&env_note);
+    // detect prettify table flag (default true) - only used in interactive mode
+    let prettify_table = matches
+        .get_one::<String>("pt")
+        .map(|s| s.as_str() == "true")
+        .unwrap_or(true);
+    let persona = matches
+        .get_one::<String>("persona")
+        .cloned()
+        .or_else(|| config::config().persona.clone());
 
+    if interactive {
+        // Interactive mode gets a terse system instruction that constrains length and optionally requests psql-style tables
+        let persona_text = persona.as_deref().map(prompt::persona).transpose()?;
+        messages.push(serde_json::json!({"role": "system", "content": chat_system(prettify_table, persona_text.as_deref())}));
+    } else {
+        let mut sys = command_system(&matches, allow_multiline);
+        if output_path.is_some() {
+            sys.push_str(script::SCRIPT_NOTE);
+        }
+        if annotate {
+            sys.push_str(script::ANNOTATE_NOTE);
+        }
+        if matches.get_flag("clarify") {
+            sys.push_str(clarify::CLARIFY_NOTE);
+        }
+        if candidate_count > 1 {
+            sys.push_str(&candidates::note(candidate_count));
+        }
         messages.push(serde_json::json!({"role": "system", "content": sys}));
+        messages.extend(few_shot(allow_multiline));
+    }
+
+    // Append the initial user prompt
+    let images = image::from_matches(&matches)?;
+    messages.push(serde_json::json!({"role": "user", "content": image::content(&prompt, images)}));
+
+    // --refine continues from the previous answer; history records the refinement too.
+    let mut history_prompt = prompt.clone();
+    if let (Some(prev), Some(instruction)) = (&previous, &refine_instruction) {
+        messages.push(serde_json::json!({"role": "assistant", "content": prev.command}));
+        messages.push(serde_json::json!({"role": "user", "content": instruction}));
+        history_prompt = format!("{} (refined: {})", prompt, instruction);
     }
 
-    // If interactive mode is enabled, provide a terse system instruction that constrains length and optionally requests psql-style tables
     if interactive {
-        // detect prettify table flag (default true) - only used in interactive mode
-        let prettify_table = matches
-            .get_one::<String>("pt")
-            .map(|s| s.as_str() == "true")
-            .unwrap_or(true);
-
-        let mut inter_sys = String::from("You are an assistant for a CLI tool. Keep replies very brief (max 1-2 short paragraphs). Prioritize clarity and simplicity. Use concise sentences and avoid unnecessary explanation.");
-        if prettify_table {
-            inter_sys.push_str(" When showing tabular data, use compact psql-style ASCII tables (no markdown or code fences) so output fits in a terminal.");
+        if settings.deterministic {
+            bail!("--deterministic cannot start a chat; drop -a or ask one question at a time");
         }
-        messages.push(serde_json::json!({"role": "system", "content": inter_sys}));
+        return chat::run(&settings, messages, prettify_table, persona).await;
     }
 
-    // Determine reasoning settings (OpenAI-style 'effort')
-    let effort = matches
-        .get_one::<String>("reasoning")
-        .map(|s| s.as_str())
-        .unwrap_or("low");
-    let show_reasoning = matches.get_flag("show-reasoning");
-
-    // Append the initial user prompt
-    messages.push(serde_json::json!({"role": "user", "content": prompt}));
+    if let Some(path) = matches.get_one::<PathBuf>("record") {
+        record::start(path, matches.get_flag("force"), &prompt)?;
+    }
 
-    if interactive {
-        // Interactive loop: keep conversation messages and prompt user after each model response.
-        println!("Entering interactive chat mode. Type '/exit' or empty line to quit.");
-        // messages already contains any system instructions (none in interactive) and the first user prompt
-        loop {
-            // Include top-level reasoning object following OpenRouter's API (e.g. { "reasoning": { "effort": "high" } })
-            let body = serde_json::json!({"model": model, "messages": messages, "reasoning": {"effort": effort}});
-            let cli_output = query_openrouter(&api_key, &body).await.unwrap_or_else(|e| {
+    let start = std::time::Instant::now();
+    let mut completion = if settings.offline {
+        offline::complete_offline(&settings, &messages, &prompt).await
+    } else if matches.get_flag("escalate") {
+        escalate::complete_escalating(&settings, &messages)
+            .await
+            .unwrap_or_else(|e| {
                 eprintln!("LLM request failed: {}", e);
                 std::process::exit(1);
-            });
-
-            let response = cli_output
-                .choices
-                .get(0)
-                .map(|c| c.message.content.clone())
-                .unwrap_or_default();
-
-            // Print assistant response
-            println!("{}", response.trim());
-
-            // If show_reasoning is requested, the model may include a trailing reasoning field; print nothing here — interactive mode shows full assistant response.
-
-            // Append assistant message to conversation
-            messages.push(serde_json::json!({"role": "assistant", "content": response}));
-
-            // Prompt for next user input
-            print!("> ");
-            let _ = io::stdout().flush();
-            let mut line = String::new();
-            if let Err(_) = io::stdin().read_line(&mut line) {
-                break;
-            }
-            let line = line.trim().to_string();
-            if line.is_empty() || line == "/exit" {
-                break;
-            }
-            // add user message and continue loop
-            messages.push(serde_json::json!({"role": "user", "content": line}));
-        }
+            })
     } else {
-        // Include top-level reasoning object following OpenRouter's API
-        let body = serde_json::json!({"model": model, "messages": messages, "reasoning": {"effort": effort}});
-
-        let cli_output = query_openrouter(&api_key, &body).await.unwrap_or_else(|e| {
-            eprintln!("LLM request failed: {}", e);
-            std::process::exit(1);
-        });
-        // The API returns choices[].message.content and may include choices[].message.reasoning
-        let choice = cli_output.choices.get(0);
-        let command = choice
-            .map(|c| c.message.content.clone())
-            .unwrap_or_default();
-
-        // Grab reasoning from the parsed response if available
-        let reasoning_json = if show_reasoning {
-            choice.and_then(|c| c.message.reasoning.clone())
-        } else {
-            None
-        };
-
-        let out = command.trim().to_string();
-
-        // Minimal: print only the command (out was derived above)
-        if is_not_able_response(&out) {
-            // Uncopyable message: print but do not copy to clipboard or save to history
-            println!("{}", out);
-        } else {
-            println!("{}", out);
-
-            // Copy to clipboard on macOS
-            #[cfg(target_os = "macos")]
-            {
-                if let Ok(child) = std::process::Command::new("pbcopy")
-                    .stdin(std::process::Stdio::piped())
-                    .spawn()
-                {
-                    if let Some(mut stdin) = child.stdin {
-                        let _ = stdin.write_all(out.as_bytes());
-                    }
+        // Past --max-wait, local answers beat a stalled terminal.
+        complete_or_else(&settings, &messages, || offline::heuristics(&prompt)).await
+    };
+    if matches.get_flag("clarify") {
+        completion = clarify::resolve(&settings, &mut messages, completion).await;
+    }
+    output::stats_footer(&completion, start.elapsed());
+
+    // --loop: let the user refine the command before anything is printed, copied, or saved.
+    if matches.get_flag("loop")
+        && !settings.deterministic
+        && !is_not_able_response(&completion.content)
+    {
+        if std::io::stdin().is_terminal() {
+            match refine::run(&settings, &mut messages, completion).await {
+                Some(c) => completion = c,
+                None => {
+                    eprintln!("Discarded.");
+                    return Ok(());
                 }
             }
-
-            // Save history
-            save_history(&prompt, &out)?;
+        } else {
+            eprintln!("--loop needs an interactive terminal; skipping refinement.");
         }
+    }
 
-        if let Some(js_val) = reasoning_json {
-            // Normalize the reasoning output to the canonical form: {"reasoning": "..."}
-            // If the model returned a string, wrap it. If it returned an object that includes
-            // a `reasoning` key, prefer that. Otherwise stringify the object and wrap it.
-            let final_obj = if js_val.is_string() {
-                let s = js_val.as_str().unwrap_or_default();
-                serde_json::json!({"reasoning": s})
-            } else if js_val.is_object() {
-                // If it already contains a `reasoning` key, use as-is
-                if js_val.get("reasoning").is_some() {
-                    js_val
-                } else {
-                    // Fallback: stringify the object and place under `reasoning`
-                    let s = serde_json::to_string(&js_val).unwrap_or_else(|_| js_val.to_string());
-                    serde_json::json!({"reasoning": s})
-                }
-            } else {
-                // Other types (numbers, arrays, etc.) - stringify and wrap
-                let s = serde_json::to_string(&js_val).unwrap_or_else(|_| js_val.to_string());
-                serde_json::json!({"reasoning": s})
-            };
+    // Grab reasoning from the parsed response if available; a person who watched it
+    // stream by gets only the command.
+    let reasoning_json = if show_reasoning && !settings.stream_reasoning {
+        completion.reasoning
+    } else {
+        None
+    };
 
-            // Print compact single-line JSON to match README examples
-            if let Ok(s) = serde_json::to_string(&final_obj) {
-                println!("{}", s);
-            } else {
-                println!("{}", final_obj);
-            }
+    let mut out = completion.content.trim().to_string();
+    let mut alternatives = Vec::new();
+    if candidate_count > 1 && !is_not_able_response(&out) {
+        alternatives = candidates::split(&out, candidate_count);
+        if let Some(first) = alternatives.first() {
+            out = first.clone();
         }
     }
-
-    Ok(())
-}
-
-async fn query_openrouter(api_key: &str, body: &serde_json::Value) -> Result<OpenRouterResponse> {
-    let client = reqwest::Client::new();
-    let mut req = client
-        .post("https://openrouter.ai/api/v1/chat/completions")
-        .json(body);
-
-    if !api_key.is_empty() {
-        req = req.header("Authorization", format!("Bearer {}", api_key));
+    if let Some(prev) = &previous {
+        if !is_not_able_response(&out) {
+            eprint!("{}", diff::render(&prev.command, &out));
+        }
     }
-
-    let resp = req.send().await?.error_for_status()?;
-    let out = resp.json::<OpenRouterResponse>().await?;
-    Ok(out)
-}
-
-fn history_path() -> Option<PathBuf> {
-    ProjectDirs::from("com", "snapshell", "snapshell")
-        .map(|d| d.data_local_dir().join("history.jsonl"))
-}
-
-fn save_history(prompt: &str, command: &str) -> Result<()> {
-    if let Some(path) = history_path() {
-        if let Some(dir) = path.parent() {
-            create_dir_all(dir)?;
+    if matches.get_flag("strip-comments") && !is_not_able_response(&out) {
+        out = script::strip_comments(&out);
+    }
+    if let Some(mode) = matches.get_one::<String>("check-paths") {
+        if !is_not_able_response(&out) {
+            out = pathcheck::review(&out, mode == "fix");
         }
-        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
-        let entry = HistoryEntry {
-            timestamp: Utc::now().to_rfc3339(),
-            prompt: prompt.to_string(),
-            command: command.to_string(),
-        };
-        let line = serde_json::to_string(&entry)? + "\n";
-        file.write_all(line.as_bytes())?;
     }
-    Ok(())
-}
 
-fn print_history() -> Result<()> {
-    if let Some(path) = history_path() {
-        if !path.exists() {
-            println!("no history");
-            return Ok(());
-        }
-        let mut s = String::new();
-        let mut f = std::fs::File::open(&path)?;
-        f.read_to_string(&mut s)?;
-        for line in s.lines() {
-            if let Ok(entry) = serde_json::from_str::<HistoryEntry>(line) {
-                println!(
-                    "{} -> {}\n  {}",
-                    entry.timestamp, entry.prompt, entry.command
-                );
+    // --confidence: a cheap second pass rates the command before it is used.
+    if matches.get_flag("confidence") && !is_not_able_response(&out) {
+        match confidence::rate(&settings, &prompt, &out, &env::detect_environment()).await {
+            Some(r) => {
+                eprintln!("Confidence: {}/100", r.confidence);
+                confidence::warn_rating(&r);
             }
+            None => eprintln!("Could not get a confidence rating."),
         }
-    } else {
-        println!("no history");
     }
-    Ok(())
-}
 
-fn detect_environment() -> String {
-    // macOS
-    if cfg!(target_os = "macos") {
-        return "macos".to_string();
+    if matches.get_flag("eval") && !is_not_able_response(&out) {
+        if let Some(why) = output::eval_problem(&out) {
+            eprintln!("Refused for --eval: {}.", why);
+            std::process::exit(output::EXIT_BLOCKED);
+        }
     }
 
-    // Windows
-    if cfg!(target_os = "windows") {
-        return "windows".to_string();
+    if let Some(l) = launcher::from_matches(&matches) {
+        return launcher::emit(l, &history_prompt, &out);
+    }
+    if let Some(name) = matches.get_one::<String>("as-abbr") {
+        return modes::alias::emit_abbr(name, &history_prompt, &out);
+    }
+    if matches.get_flag("json") || matches.get_flag("deterministic") {
+        return output::emit_json(&history_prompt, &settings.model, &out, reasoning_json);
     }
 
-    // Try to read /etc/os-release for Linux distros
-    if cfg!(target_os = "linux") {
-        if let Ok(s) = std::fs::read_to_string("/etc/os-release") {
-            let s_l = s.to_lowercase();
-            if s_l.contains("debian") || s_l.contains("ubuntu") {
-                return "linux (debian/ubuntu)".to_string();
-            }
-            if s_l.contains("fedora") {
-                return "linux (fedora)".to_string();
+    // With --output the script goes to the file instead of stdout/clipboard.
+    if let Some(path) = output_path {
+        if !is_not_able_response(&out) {
+            refuse_if_blocked(&out);
+            lint::warn(&out);
+            let preamble =
+                script::preamble(matches.get_one::<String>("preamble").map(|s| s.as_str()));
+            let script = script::render(&out, &preamble);
+            write_output_file(path, &script, matches.get_flag("force"), true)?;
+            eprintln!(
+                "Wrote {} ({} lines).",
+                path.display(),
+                script.lines().count()
+            );
+            save_history(&history_prompt, &out)?;
+            if let Some(r) = reasoning_json {
+                print_reasoning(r);
             }
-            if s_l.contains("arch") {
-                return "linux (arch)".to_string();
-            }
-            // fallback for generic linux
-            return "linux".to_string();
+            return Ok(());
         }
-        return "linux".to_string();
     }
 
-    // Unknown/fallback
-    "unknown".to_string()
-}
+    // --schedule: the wrapper is what gets printed, copied, and saved.
+    let schedule = matches.get_one::<String>("schedule");
+    if let Some(when) = schedule.filter(|_| !is_not_able_response(&out)) {
+        refuse_if_blocked(&out);
+        let s = schedule::wrap(&settings, when, &out).await?;
+        eprintln!("{}", s.summary);
+        out = s.command;
+    }
 
-fn is_not_able_response(s: &str) -> bool {
-    // Expect format: (NOT ABLE TO ANSWER): <reason>
-    let s = s.trim();
-    if s.len() < 22 {
-        return false;
+    emit_command(&history_prompt, &out, reasoning_json)?;
+    if schedule.is_some() && std::io::stdin().is_terminal() {
+        exec::run(&settings, &out, false).await?;
+    }
+    if candidate_count > 1 {
+        candidates::show_rest(&alternatives);
+        if widget::driving() {
+            candidates::save(&alternatives)?;
+        }
     }
-    // Case-insensitive check for the prefix
-    let lower = s.to_lowercase();
-    lower.starts_with("(not able to answer):")
+    if matches.get_flag("exec") {
+        record::output(&format!("{}\n", out));
+        exec::run(&settings, &out, matches.get_flag("dry-explain")).await?;
+    }
+
+    Ok(())
 }
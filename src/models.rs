@@ -0,0 +1,312 @@
+// OpenRouter's model list with pricing, cached on disk for a day so --free-only does not
+// add a request to every run. `ss models pick` (or `-m ?`) chooses a default from it.
+use anyhow::{bail, Context, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::env::which;
+use crate::llm::OPENROUTER_URL;
+use crate::modes::run_with_stdin;
+use crate::paths::{cache_dir, state_dir};
+use crate::policy::policy;
+
+pub const MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Model {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub context_length: Option<u64>,
+    #[serde(default)]
+    pub pricing: Pricing,
+    #[serde(default)]
+    pub architecture: Option<Architecture>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Architecture {
+    // What the model accepts: "text", "image", "file", ...
+    #[serde(default)]
+    pub input_modalities: Vec<String>,
+}
+
+// USD per token (per request or image for those fields), as decimal strings.
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Pricing {
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub completion: Option<String>,
+    #[serde(default)]
+    pub request: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+impl Model {
+    // Free when every listed price is zero.
+    pub fn is_free(&self) -> bool {
+        let p = &self.pricing;
+        [&p.prompt, &p.completion, &p.request, &p.image]
+            .into_iter()
+            .flatten()
+            .all(|price| price.trim().parse::<f64>().is_ok_and(|v| v == 0.0))
+    }
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<Model>,
+}
+
+fn cache_path() -> Option<PathBuf> {
+    cache_dir().map(|d| d.join("models.json"))
+}
+
+// The cached list when it is fresh, else a new one from OpenRouter. A stale cache is
+// still used when the fetch fails.
+pub async fn list() -> Result<Vec<Model>> {
+    let path = cache_path();
+    let cached = path.as_ref().and_then(|p| {
+        let age = std::fs::metadata(p)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())?;
+        let models: Vec<Model> = serde_json::from_slice(&std::fs::read(p).ok()?).ok()?;
+        Some((age, models))
+    });
+    if let Some((age, models)) = &cached {
+        if *age < MAX_AGE {
+            return Ok(models.clone());
+        }
+    }
+    match fetch().await {
+        Ok(models) => {
+            if let Some(p) = &path {
+                if let Some(dir) = p.parent() {
+                    let _ = std::fs::create_dir_all(dir);
+                }
+                let _ = std::fs::write(p, serde_json::to_vec(&models)?);
+            }
+            Ok(models)
+        }
+        Err(e) => match cached {
+            Some((_, models)) => Ok(models),
+            None => Err(e),
+        },
+    }
+}
+
+async fn fetch() -> Result<Vec<Model>> {
+    policy().check_endpoint(MODELS_URL)?;
+    let list: ModelList = reqwest::get(MODELS_URL)
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("reading the OpenRouter model list")?;
+    Ok(list.data)
+}
+
+// Credits for a request from the listed per-token prices, for responses that did not
+// report their cost. None when the model or its prices are unknown.
+pub async fn estimate_cost(model: &str, prompt_tokens: u64, completion_tokens: u64) -> Option<f64> {
+    let models = list().await.ok()?;
+    let pricing = &models.iter().find(|m| m.id == model)?.pricing;
+    let price = |p: &Option<String>| p.as_deref()?.trim().parse::<f64>().ok();
+    Some(
+        price(&pricing.prompt)? * prompt_tokens as f64
+            + price(&pricing.completion)? * completion_tokens as f64,
+    )
+}
+
+// --image / --screenshot: refuse a model OpenRouter lists as text-only, before the image
+// is uploaded. Models missing from the list, or listed without modalities, get the benefit
+// of the doubt.
+pub async fn ensure_vision(model: &str) -> Result<()> {
+    let Ok(models) = list().await else {
+        return Ok(());
+    };
+    let Some(arch) = models
+        .iter()
+        .find(|m| m.id == model)
+        .and_then(|m| m.architecture.as_ref())
+    else {
+        return Ok(());
+    };
+    if arch.input_modalities.is_empty() || arch.input_modalities.iter().any(|m| m == "image") {
+        return Ok(());
+    }
+    bail!(
+        "{} does not accept images (input: {}); pick a vision model with -m, e.g. openai/gpt-4o-mini",
+        model,
+        arch.input_modalities.join(", ")
+    )
+}
+
+// --free-only: refuse a model unless OpenRouter lists it at zero cost. Without pricing
+// data only `:free` variants are trusted.
+pub async fn ensure_free(model: &str) -> Result<()> {
+    let models = match list().await {
+        Ok(models) => models,
+        Err(_) if model.ends_with(":free") => return Ok(()),
+        Err(e) => {
+            return Err(e.context(format!(
+                "--free-only could not check the price of {}; refusing to send",
+                model
+            )))
+        }
+    };
+    match models.iter().find(|m| m.id == model) {
+        Some(m) if m.is_free() => Ok(()),
+        Some(m) => {
+            let free = format!("{}:free", model.split(':').next().unwrap_or(model));
+            let hint = if models.iter().any(|m| m.id == free && m.is_free()) {
+                format!("use {}", free)
+            } else {
+                "pick a model ending in :free".to_string()
+            };
+            bail!(
+                "{} is a paid model (prompt {} / completion {} USD per token) and --free-only is set; {}",
+                model,
+                m.pricing.prompt.as_deref().unwrap_or("?"),
+                m.pricing.completion.as_deref().unwrap_or("?"),
+                hint
+            )
+        }
+        None => bail!(
+            "{} is not in OpenRouter's model list, so --free-only cannot confirm it is free",
+            model
+        ),
+    }
+}
+
+pub fn command() -> Command {
+    Command::new("models")
+        .about("List OpenRouter's models, or pick the default one with fzf")
+        .after_help("Examples:\n  ss models pick\n  ss -m ? \"list listening ports\"\n  ss models list | grep -i claude\n  ss models pick --clear")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("list")
+                .about("Print each model with its context length and prices per million tokens"),
+        )
+        .subcommand(
+            Command::new("pick")
+                .about("Choose the model used when -m is not given (same as `ss -m ?`)")
+                .arg(
+                    Arg::new("clear")
+                        .long("clear")
+                        .help("Forget the picked model and go back to config.toml and the built-in default")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", _)) => {
+            for row in table(&list().await?) {
+                println!("{}", row);
+            }
+        }
+        Some(("pick", m)) if m.get_flag("clear") => {
+            if let Some(path) = picked_path().filter(|p| p.exists()) {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("removing {}", path.display()))?;
+            }
+            eprintln!("Forgot the picked model.");
+        }
+        Some(("pick", _)) => {
+            pick().await?;
+        }
+        _ => unreachable!("subcommand_required"),
+    }
+    Ok(())
+}
+
+// The model chosen with `ss models pick` or `-m ?`, used whenever -m is not given.
+fn picked_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("model"))
+}
+
+pub fn picked() -> Option<String> {
+    let s = std::fs::read_to_string(picked_path()?).ok()?;
+    Some(s.trim().to_string()).filter(|m| !m.is_empty())
+}
+
+// Let the user choose from the catalog with fzf and remember the choice. Models the
+// admin policy does not allow are left out.
+pub async fn pick() -> Result<String> {
+    if which("fzf").is_none() {
+        bail!("the model picker needs fzf (https://github.com/junegunn/fzf); pass -m <id> instead, or look through `ss models list`");
+    }
+    let models: Vec<Model> = list()
+        .await?
+        .into_iter()
+        .filter(|m| policy().check_request(OPENROUTER_URL, &m.id).is_ok())
+        .collect();
+    if models.is_empty() {
+        bail!("no model in OpenRouter's list is allowed by the policy");
+    }
+    let mut rows = table(&models);
+    let header = rows.remove(0);
+    let mut args = vec!["--header", &header, "--prompt", "model> ", "--no-multi"];
+    let current = picked();
+    if let Some(m) = &current {
+        args.extend(["--query", m.as_str()]);
+    }
+    // fzf draws on the terminal itself, so only the chosen line comes back on stdout.
+    let out = run_with_stdin("fzf", &args, &rows.join("\n"))?;
+    let line = String::from_utf8_lossy(&out.stdout);
+    let Some(id) = line.split_whitespace().next() else {
+        bail!("no model picked; the default is unchanged");
+    };
+    let path = picked_path().context("no state directory to remember the model in")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, format!("{}\n", id))
+        .with_context(|| format!("writing {}", path.display()))?;
+    eprintln!(
+        "Default model is now {}; -m still overrides it, and `ss models pick --clear` forgets it.",
+        id
+    );
+    Ok(id.to_string())
+}
+
+// A header line, then one aligned line per model: id, context length, and the prompt and
+// completion prices in USD per million tokens.
+fn table(models: &[Model]) -> Vec<String> {
+    let width = models.iter().map(|m| m.id.len()).max().unwrap_or(0).max(5);
+    let per_million = |p: &Option<String>| match p.as_deref().map(|p| p.trim().parse::<f64>()) {
+        Some(Ok(0.0)) => "free".to_string(),
+        // The auto router lists -1: the price depends on the model it routes to.
+        Some(Ok(v)) if v < 0.0 => "varies".to_string(),
+        Some(Ok(v)) => format!("${:.2}", v * 1_000_000.0),
+        _ => "?".to_string(),
+    };
+    let mut rows = vec![format!(
+        "{:<width$}  {:>7}  {:>8}  {:>10}",
+        "MODEL", "CONTEXT", "PROMPT/M", "COMPLETE/M"
+    )];
+    for m in models {
+        let context = match m.context_length {
+            Some(n) if n >= 1000 => format!("{}k", n / 1000),
+            Some(n) => n.to_string(),
+            None => "?".to_string(),
+        };
+        rows.push(format!(
+            "{:<width$}  {:>7}  {:>8}  {:>10}",
+            m.id,
+            context,
+            per_million(&m.pricing.prompt),
+            per_million(&m.pricing.completion)
+        ));
+    }
+    rows
+}
@@ -0,0 +1,323 @@
+use anyhow::{bail, Result};
+use chrono::Local;
+use clap::{Arg, ArgMatches, Command};
+use serde::Deserialize;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+
+use super::{confirm, input, strip_code_fences};
+use crate::env::{env_note, which};
+use crate::exec::shell_quote;
+use crate::history::save_history;
+use crate::llm::{complete_or_exit, Settings};
+use crate::output::{deliver, exit_not_able, parse_not_able, refuse_if_blocked};
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+// How many times the model may pick another name after a collision.
+const MAX_RENAMES: usize = 2;
+const SHELLS: [&str; 3] = ["zsh", "bash", "fish"];
+// Builtins and keywords that `which` cannot see.
+const BUILTINS: [&str; 14] = [
+    "cd", "echo", "exit", "export", "alias", "source", "type", "set", "unset", "test", "read",
+    "eval", "exec", "history",
+];
+
+pub fn command() -> Command {
+    Command::new("alias")
+        .about("Generate an alias or shell function for your shell and offer to add it to your rc file")
+        .after_help("Examples:\n  ss alias \"shortcut to fuzzy-switch git branches\"\n  ss alias --shell fish \"mkdir and cd into it\"")
+        .arg(
+            Arg::new("input")
+                .help("What the shortcut should do")
+                .index(1)
+                .required(true),
+        )
+        .arg(
+            Arg::new("shell")
+                .long("shell")
+                .help("Shell to generate for (default: from $SHELL)")
+                .num_args(1)
+                .value_parser(SHELLS),
+        )
+}
+
+#[derive(Deserialize)]
+struct AliasPlan {
+    name: String,
+    code: String,
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let shell = matches
+        .get_one::<String>("shell")
+        .cloned()
+        .unwrap_or_else(detect_shell);
+    let rc = rc_file(&shell);
+    let rc_text = rc
+        .as_ref()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .unwrap_or_default();
+    let settings = Settings::from_matches(matches);
+
+    let system = format!("You write {} aliases and shell functions. Prefer an alias for a simple command and a function when arguments, pipes, or logic are needed. Pick a short, memorable name that is not a common command. OUTPUT ONLY a JSON object with no markdown: {{\"name\": \"<alias or function name>\", \"code\": \"<the complete definition, ready to paste into the rc file>\"}}. {}{}", shell, NOT_ABLE_CLAUSE, env_note());
+    let mut messages = vec![
+        serde_json::json!({"role": "system", "content": system}),
+        serde_json::json!({"role": "user", "content": prompt}),
+    ];
+
+    let mut attempts = 0;
+    let plan = loop {
+        let completion = complete_or_exit(&settings, &messages).await;
+        let answer = strip_code_fences(&completion.content);
+        if let Some(reason) = parse_not_able(&answer) {
+            exit_not_able(&reason);
+        }
+        let Some(plan) = parse_plan(&answer) else {
+            eprintln!("Could not parse the model's answer:\n{}", answer);
+            std::process::exit(1);
+        };
+
+        // What the code defines is what lands in the rc file, whatever `name` says.
+        let Some((name, collision)) = names(&plan)
+            .into_iter()
+            .find_map(|n| collision(&n, &rc_text).map(|c| (n, c)))
+        else {
+            break plan;
+        };
+        if attempts >= MAX_RENAMES {
+            eprintln!("Warning: `{}` {}.", name, collision);
+            break plan;
+        }
+        attempts += 1;
+        eprintln!("`{}` {}; asking for another name...", name, collision);
+        messages.push(serde_json::json!({"role": "assistant", "content": answer}));
+        messages.push(serde_json::json!({"role": "user", "content": format!("The name `{}` {}. Reply with a corrected JSON object using a different name.", name, collision)}));
+    };
+
+    let code = plan.code.trim().to_string();
+    refuse_if_blocked(&code);
+    println!("{}", code);
+    deliver(&code)?;
+    save_history(&prompt, &code)?;
+
+    if let Some(rc) = rc {
+        if std::io::stdin().is_terminal()
+            && confirm(&format!(
+                "Append `{}` to {}?",
+                names(&plan).join("`, `"),
+                rc.display()
+            ))
+        {
+            append_to_rc(&rc, Some(&prompt), &code)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn detect_shell() -> String {
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    let name = shell.rsplit('/').next().unwrap_or_default();
+    if SHELLS.contains(&name) {
+        name.to_string()
+    } else {
+        "bash".to_string()
+    }
+}
+
+pub fn rc_file(shell: &str) -> Option<PathBuf> {
+    let home = directories::BaseDirs::new()?.home_dir().to_path_buf();
+    Some(match shell {
+        "zsh" => std::env::var_os("ZDOTDIR")
+            .map(PathBuf::from)
+            .unwrap_or(home)
+            .join(".zshrc"),
+        "fish" => home.join(".config").join("fish").join("config.fish"),
+        _ => home.join(".bashrc"),
+    })
+}
+
+fn parse_plan(answer: &str) -> Option<AliasPlan> {
+    let start = answer.find('{')?;
+    let end = answer.rfind('}')?;
+    serde_json::from_str(answer.get(start..=end)?).ok()
+}
+
+// The names the plan's code defines; its `name` when none can be made out.
+fn names(plan: &AliasPlan) -> Vec<String> {
+    let names = defined_names(&plan.code);
+    if names.is_empty() {
+        vec![plan.name.clone()]
+    } else {
+        names
+    }
+}
+
+// Names of aliases and functions defined in an rc file (bash, zsh, or fish syntax).
+fn defined_names(rc_text: &str) -> Vec<String> {
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':');
+    rc_text
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let rest = line
+                .strip_prefix("alias ")
+                .or_else(|| line.strip_prefix("function "))
+                .unwrap_or(line)
+                .trim_start();
+            let name: String = rest.chars().take_while(|c| is_name_char(*c)).collect();
+            let after = rest[name.len()..].trim_start();
+            let is_definition = line.starts_with("alias ")
+                || line.starts_with("function ")
+                || after.starts_with("()");
+            (is_definition && !name.is_empty()).then_some(name)
+        })
+        .collect()
+}
+
+// Why `name` is already taken, if it is: defined in the rc file, a builtin, or on PATH.
+fn collision(name: &str, rc_text: &str) -> Option<String> {
+    if defined_names(rc_text).iter().any(|n| n == name) {
+        return Some("is already defined in your rc file".to_string());
+    }
+    if BUILTINS.contains(&name) {
+        return Some("is a shell builtin".to_string());
+    }
+    which(name).map(|p| format!("would shadow {}", p.display()))
+}
+
+// Back up the rc file next to itself, then append the definition, under a comment with
+// its prompt when the file's format allows one. A multi-line prompt goes on one comment
+// line; its later lines would otherwise become code.
+fn append_to_rc(rc: &PathBuf, prompt: Option<&str>, code: &str) -> Result<()> {
+    if rc.exists() {
+        let mut backup = rc.clone().into_os_string();
+        backup.push(format!(".bak-{}", Local::now().format("%Y%m%d%H%M%S")));
+        std::fs::copy(rc, &backup)?;
+        eprintln!(
+            "Backed up {} to {}.",
+            rc.display(),
+            PathBuf::from(&backup).display()
+        );
+    } else if let Some(dir) = rc.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(rc)?;
+    match prompt {
+        Some(p) => write!(
+            file,
+            "\n# snapshell: {}\n{}\n",
+            p.split(['\r', '\n']).collect::<Vec<_>>().join(" "),
+            code
+        )?,
+        None => writeln!(file, "{}", code)?,
+    }
+    eprintln!(
+        "Added to {}; open a new shell or source it to use it.",
+        rc.display()
+    );
+    Ok(())
+}
+
+// --as-abbr NAME: print the generated command as an abbreviation for zsh-abbr or fish,
+// which expands in place when typed, and offer to save it. zsh-abbr keeps abbreviations
+// in a file of its own and fish in config.fish; bash has no abbreviations.
+pub fn emit_abbr(name: &str, prompt: &str, command: &str) -> Result<()> {
+    if let Some(reason) = parse_not_able(command) {
+        exit_not_able(&reason);
+    }
+    refuse_if_blocked(command);
+    if command.lines().count() > 1 {
+        bail!("abbreviations expand to one line; drop -L or use `ss alias` for a shell function");
+    }
+    let shell = detect_shell();
+    let (code, file) = match shell.as_str() {
+        "zsh" => (
+            format!("abbr {}={}", name, shell_quote(command)),
+            abbr_file(),
+        ),
+        "fish" => (
+            format!("abbr -a -- {} {}", name, fish_quote(command)),
+            rc_file("fish"),
+        ),
+        _ => bail!(
+            "{} has no abbreviations; use zsh with zsh-abbr or fish, or `ss alias` for an alias",
+            shell
+        ),
+    };
+    let text = file
+        .as_ref()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .unwrap_or_default();
+    let defined = text.lines().any(|l| {
+        let l = l.trim_start();
+        l.starts_with(&format!("abbr {}=", name))
+            || l.starts_with(&format!("abbr \"{}\"=", name))
+            || l.starts_with(&format!("abbr -a -- {} ", name))
+            || l.starts_with(&format!("abbr -a {} ", name))
+    });
+    if let Some(why) = collision(name, "") {
+        eprintln!("Warning: `{}` {}.", name, why);
+    }
+    println!("{}", code);
+    deliver(&code)?;
+    save_history(prompt, command)?;
+
+    if let Some(file) = file {
+        if defined {
+            eprintln!(
+                "`{}` is already an abbreviation in {}; not saving.",
+                name,
+                file.display()
+            );
+        } else if std::io::stdin().is_terminal()
+            && confirm(&format!(
+                "Save the `{}` abbreviation to {}?",
+                name,
+                file.display()
+            ))
+        {
+            // zsh-abbr reads its file line by line, so no comment goes in.
+            let comment = (shell == "fish").then_some(prompt);
+            append_to_rc(&file, comment, &code)?;
+            if shell == "zsh" {
+                eprintln!("Run `abbr load` in open shells to pick it up.");
+            }
+        }
+    }
+    Ok(())
+}
+
+// Value parser for --as-abbr, so a bad name fails before anything is generated. The name
+// goes unquoted into `abbr` lines, so only plain characters are allowed.
+pub fn abbr_name(name: &str) -> Result<String, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("use letters, digits, - or _".to_string());
+    }
+    Ok(name.to_string())
+}
+
+// zsh-abbr's user abbreviations: $ABBR_USER_ABBREVIATIONS_FILE, else under the XDG
+// config dir.
+fn abbr_file() -> Option<PathBuf> {
+    if let Some(p) = std::env::var_os("ABBR_USER_ABBREVIATIONS_FILE").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(p));
+    }
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(directories::BaseDirs::new()?.home_dir().join(".config")))?;
+    Some(config.join("zsh-abbr").join("user-abbreviations"))
+}
+
+// Single-quote `s` for fish, where backslashes inside single quotes are escapes too.
+fn fish_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
@@ -0,0 +1,120 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+
+use super::{ask_with, capture, input};
+use crate::context::{self, Probe};
+use crate::env::{env_note, which};
+use crate::llm::Settings;
+use crate::output::emit_command;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const PROVIDERS: [&str; 3] = ["aws", "gcloud", "az"];
+
+pub fn command() -> Command {
+    Command::new("cloud")
+        .about("Generate aws/gcloud/az CLI commands using the active profile, project, and region")
+        .after_help("Examples:\n  ss cloud \"list buckets created this year with their sizes\"\n  ss cloud --provider gcloud \"resize the web instance group to 5\"")
+        .arg(
+            Arg::new("input")
+                .help("What you want to do in the cloud")
+                .index(1)
+                .required(true),
+        )
+        .arg(
+            Arg::new("provider")
+                .long("provider")
+                .help("Cloud CLI to target (default: every installed one is described)")
+                .num_args(1)
+                .value_parser(PROVIDERS),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let providers: Vec<&'static str> = match matches.get_one::<String>("provider") {
+        Some(p) => PROVIDERS.into_iter().filter(|&c| c == p).collect(),
+        None => PROVIDERS
+            .into_iter()
+            .filter(|p| which(p).is_some())
+            .collect(),
+    };
+    if providers.is_empty() {
+        eprintln!("No aws, gcloud, or az CLI found; generating without account details.");
+    }
+
+    // Each CLI can take a second or more to start, so they are asked concurrently.
+    let probes: Vec<Probe> = providers
+        .iter()
+        .map(|&p| -> Probe { (p, Box::new(move || cloud_context(p))) })
+        .collect();
+    let settings = Settings::from_matches(matches);
+    let ctx = context::gather(&settings, probes).await;
+
+    let system = format!("You are a strict cloud CLI command generator. OUTPUT ONLY shell commands using the aws, gcloud, or az CLIs in plain text with no explanations, no markdown, code fences, or backticks. The entire response MUST be a single line; chain steps with && when several are needed. Rely on the configured profile, project, subscription, and region rather than repeating them unless the task names different ones. {}{}", NOT_ABLE_CLAUSE, env_note());
+    let user = if ctx.is_empty() {
+        prompt.clone()
+    } else {
+        format!(
+            "Cloud CLI context:\n{}\nTask: {}",
+            context::fit(matches, "cloud CLI context", &ctx),
+            prompt
+        )
+    };
+
+    let (command, reasoning) = ask_with(&settings, matches, &system, &user).await;
+    emit_command(&prompt, &command, reasoning)
+}
+
+// Describe the active identity for one CLI, from local config only (no API calls).
+pub fn cloud_context(provider: &str) -> String {
+    let mut out = String::new();
+    match provider {
+        "aws" => {
+            if let Some(v) = capture("aws", &["--version"]) {
+                out.push_str(&format!("aws cli: {}\n", v));
+            }
+            let profile = std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string());
+            out.push_str(&format!("aws profile: {}\n", profile));
+            if let Some(region) = std::env::var("AWS_REGION")
+                .ok()
+                .or_else(|| capture("aws", &["configure", "get", "region"]))
+            {
+                out.push_str(&format!("aws region: {}\n", region));
+            }
+        }
+        "gcloud" => {
+            for (key, label) in [
+                ("core/project", "gcloud project"),
+                ("core/account", "gcloud account"),
+                ("compute/region", "gcloud region"),
+            ] {
+                if let Some(v) = capture("gcloud", &["config", "get-value", key]) {
+                    if !v.is_empty() && v != "(unset)" {
+                        out.push_str(&format!("{}: {}\n", label, v));
+                    }
+                }
+            }
+        }
+        "az" => {
+            if let Some(v) = capture("az", &["account", "show", "--query", "name", "-o", "tsv"]) {
+                out.push_str(&format!("az subscription: {}\n", v));
+            }
+            if let Some(v) = capture(
+                "az",
+                &[
+                    "config",
+                    "get",
+                    "defaults.location",
+                    "--query",
+                    "value",
+                    "-o",
+                    "tsv",
+                ],
+            ) {
+                out.push_str(&format!("az default location: {}\n", v));
+            }
+        }
+        _ => {}
+    }
+    out
+}
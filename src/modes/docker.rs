@@ -0,0 +1,94 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+
+use super::{ask, input, strip_code_fences};
+use crate::context;
+use crate::env::env_note;
+use crate::history::save_history;
+use crate::output::{
+    emit_text, is_not_able_response, print_reasoning, refuse_if_blocked, write_output_file,
+};
+use crate::project;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const MAX_MANIFEST_BYTES: usize = 3000;
+
+pub fn command() -> Command {
+    Command::new("docker")
+        .about("Generate a Dockerfile or compose file from the project in the current directory")
+        .after_help("Examples:\n  ss docker \"containerize this node app with multi-stage build\" -o Dockerfile\n  ss docker --compose \"app plus postgres and redis for local dev\"")
+        .arg(Arg::new("input").help("What the container setup should do").index(1).required(true))
+        .arg(
+            Arg::new("compose")
+                .long("compose")
+                .help("Generate a docker compose file instead of a Dockerfile (implied when --output names a compose file)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Write the generated file to this path instead of printing it")
+                .num_args(1)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .help("Overwrite the --output file if it already exists")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let output = matches.get_one::<PathBuf>("output");
+    let compose = matches.get_flag("compose")
+        || output
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().contains("compose"))
+            .unwrap_or(false);
+
+    let project = project::detect(&std::env::current_dir()?, MAX_MANIFEST_BYTES);
+    if project.is_empty() {
+        eprintln!("No project manifest found in the current directory; generating from the description only.");
+    }
+
+    let kind = if compose {
+        "docker compose file (compose.yaml format)"
+    } else {
+        "Dockerfile"
+    };
+    let system = format!("You are a strict container configuration generator. OUTPUT ONLY the complete contents of a {} in plain text with no explanations before or after it and no markdown or code fences. Comments inside the file are allowed. Base it on the project files provided: use the right base images, the package manager implied by the lockfiles, and the real build and start commands. {}{}", kind, NOT_ABLE_CLAUSE, env_note());
+    let user = if project.is_empty() {
+        prompt.clone()
+    } else {
+        format!(
+            "Project in the current directory:\n{}\nTask: {}",
+            context::fit(matches, "project files", &project.describe()),
+            prompt
+        )
+    };
+
+    let (answer, reasoning) = ask(matches, &system, &user).await;
+    let content = strip_code_fences(&answer);
+
+    match output {
+        Some(path) if !is_not_able_response(&content) => {
+            refuse_if_blocked(&content);
+            write_output_file(path, &content, matches.get_flag("force"), false)?;
+            eprintln!(
+                "Wrote {} ({} lines).",
+                path.display(),
+                content.lines().count()
+            );
+            save_history(&prompt, &content)?;
+            if let Some(r) = reasoning {
+                print_reasoning(r);
+            }
+            Ok(())
+        }
+        _ => emit_text(&prompt, &content, reasoning),
+    }
+}
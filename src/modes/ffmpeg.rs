@@ -0,0 +1,134 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use serde_json::Value as JsonValue;
+use std::path::Path;
+
+use super::{ask, capture, input};
+use crate::env::env_note;
+use crate::output::emit_command;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+pub fn command() -> Command {
+    Command::new("ffmpeg")
+        .about("Generate an ffmpeg command, probing the input media with ffprobe for accurate settings")
+        .after_help("Examples:\n  ss ffmpeg \"make this 720p h264 under 50MB\" input.mov\n  ss ffmpeg \"extract the audio as 192k mp3\" talk.mkv")
+        .arg(Arg::new("input").help("What to do with the media").index(1).required(true))
+        .arg(
+            Arg::new("files")
+                .help("Input media file(s) to probe")
+                .index(2)
+                .num_args(0..),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let files: Vec<String> = matches
+        .get_many::<String>("files")
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default();
+
+    let mut context = String::new();
+    for file in &files {
+        if !Path::new(file).exists() {
+            eprintln!(
+                "{} does not exist; it will be referenced without probing.",
+                file
+            );
+            context.push_str(&format!(
+                "Input file {} (not found locally, not probed)\n",
+                file
+            ));
+            continue;
+        }
+        match probe(file) {
+            Some(summary) => context.push_str(&summary),
+            None => {
+                eprintln!(
+                    "ffprobe unavailable or failed on {}; generating without media details.",
+                    file
+                );
+                context.push_str(&format!("Input file {} (could not be probed)\n", file));
+            }
+        }
+    }
+
+    let system = format!("You are a strict ffmpeg command generator. OUTPUT ONLY a single-line shell command (ffmpeg, possibly chained with ffprobe or shell arithmetic) in plain text with no explanations, no markdown, code fences, or backticks. When a target file size is requested, compute the bitrate from the probed duration, leaving headroom for audio and container overhead. Use the exact input file names given. {}{}", NOT_ABLE_CLAUSE, env_note());
+    let user = if context.is_empty() {
+        prompt.clone()
+    } else {
+        format!(
+            "Probed input media:\n{}\nTask: {}",
+            crate::context::fit(matches, "probed media", &context),
+            prompt
+        )
+    };
+
+    let (command, reasoning) = ask(matches, &system, &user).await;
+    emit_command(&prompt, &command, reasoning)
+}
+
+// Summarize ffprobe's JSON into a few lines: container facts first, then one line per stream.
+fn probe(file: &str) -> Option<String> {
+    let raw = capture(
+        "ffprobe",
+        &[
+            "-v",
+            "error",
+            "-show_entries",
+            "format=format_name,duration,size,bit_rate:stream=index,codec_type,codec_name,profile,width,height,r_frame_rate,pix_fmt,bit_rate,sample_rate,channels",
+            "-of",
+            "json",
+            file,
+        ],
+    )?;
+    let v: JsonValue = serde_json::from_str(&raw).ok()?;
+
+    let field = |obj: &JsonValue, key: &str| -> Option<String> {
+        match obj.get(key)? {
+            JsonValue::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    };
+
+    let mut out = format!("Input file {}:", file);
+    if let Some(fmt) = v.get("format") {
+        for (key, label) in [
+            ("format_name", "container"),
+            ("duration", "duration_s"),
+            ("size", "size_bytes"),
+            ("bit_rate", "bitrate_bps"),
+        ] {
+            if let Some(val) = field(fmt, key) {
+                out.push_str(&format!(" {}={}", label, val));
+            }
+        }
+    }
+    out.push('\n');
+
+    if let Some(streams) = v.get("streams").and_then(|s| s.as_array()) {
+        for s in streams {
+            let mut line = String::from("  stream");
+            for key in [
+                "index",
+                "codec_type",
+                "codec_name",
+                "profile",
+                "width",
+                "height",
+                "r_frame_rate",
+                "pix_fmt",
+                "bit_rate",
+                "sample_rate",
+                "channels",
+            ] {
+                if let Some(val) = field(s, key) {
+                    line.push_str(&format!(" {}={}", key, val));
+                }
+            }
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    Some(out)
+}
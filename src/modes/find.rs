@@ -0,0 +1,311 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::io::{BufRead, BufReader};
+use std::process::Stdio;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{ask, input};
+use crate::env::{env_note, which};
+use crate::exec::shell_quote;
+use crate::output::{emit_command, is_not_able_response, refuse_if_blocked};
+use crate::policy::policy;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(5);
+const PREVIEW_PATHS: usize = 5;
+
+// The dry run only takes a single plain find, fd, rg, or grep whose options are all known
+// to be read-only. Anything else (fd -x, rg --pre, find -exec, pipes, redirects,
+// substitutions) is not previewed.
+const FIND_TESTS: &str = "-name -iname -path -ipath -wholename -iwholename -regex -iregex \
+    -regextype -type -xtype -size -mtime -mmin -atime -amin -ctime -cmin -newer -empty -user \
+    -group -perm -maxdepth -mindepth -links -not -o -or -a -and -print -print0 -prune -xdev \
+    -readable -writable -executable -depth -nouser -nogroup -true -false -follow -L -H -P";
+// Tests whose next word is a value, which may itself start with `-` (`-mtime -1`).
+const FIND_VALUES: &str = "-name -iname -path -ipath -wholename -iwholename -regex -iregex \
+    -regextype -type -xtype -size -mtime -mmin -atime -amin -ctime -cmin -newer -user -group \
+    -perm -maxdepth -mindepth -links";
+// Short options that may be clustered (`-HI`), then the long ones.
+const FD_FLAGS: &str = "HIuisgFaLp0dteESc1";
+const FD_LONG: &str = "--hidden --no-ignore --no-ignore-vcs --unrestricted --ignore-case \
+    --case-sensitive --glob --regex --fixed-strings --absolute-path --follow --full-path \
+    --print0 --max-depth --min-depth --exact-depth --type --extension --exclude --size \
+    --changed-within --changed-before --owner --color --max-results --search-path";
+const RG_FLAGS: &str = "iSswxFvnNlcgtTuLmdABCeoUH0";
+const RG_LONG: &str = "--ignore-case --smart-case --case-sensitive --word-regexp \
+    --line-regexp --fixed-strings --invert-match --line-number --no-line-number --files \
+    --files-with-matches --files-without-match --count --glob --iglob --type --type-not \
+    --hidden --no-ignore --follow --max-count --max-depth --regexp --only-matching \
+    --multiline --no-heading --with-filename --color --null --sort --max-filesize \
+    --unrestricted --json";
+const GREP_FLAGS: &str = "rRilLncvwxEFPosqhHmABCeIz";
+const GREP_LONG: &str = "--recursive --ignore-case --files-with-matches \
+    --files-without-match --line-number --count --include --exclude --exclude-dir --color \
+    --binary-files";
+// Short options above that take a value, and long ones whose value may be the next word.
+const FD_VALUES: &str = "dteESc";
+const RG_VALUES: &str = "gtTmdABCe";
+const GREP_VALUES: &str = "mABCe";
+const LONG_VALUES: &str = "--glob --iglob --type --type-not --max-count --max-depth \
+    --min-depth --exact-depth --regexp --color --sort --max-filesize --extension --exclude \
+    --size --changed-within --changed-before --owner --max-results --search-path";
+
+pub fn command() -> Command {
+    Command::new("find")
+        .about("Generate a find/grep/rg command, optionally previewing its matches in a read-only dry run")
+        .after_help("Examples:\n  ss find \"files over 100MB in my home directory\"\n  ss find --preview \"rust files changed in the last day, excluding target\"")
+        .arg(Arg::new("input").help("What to search for").index(1).required(true))
+        .arg(
+            Arg::new("preview")
+                .long("preview")
+                .help("Run the search read-only first and show the match count and first paths on stderr")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+
+    let available: Vec<&str> = ["rg", "fd", "fdfind"]
+        .into_iter()
+        .filter(|t| which(t).is_some())
+        .collect();
+    let tools = if available.is_empty() {
+        "find and grep only".to_string()
+    } else {
+        format!("find, grep, and also {}", available.join(", "))
+    };
+
+    let system = format!("You are a strict file search command generator. OUTPUT ONLY a single-line shell command in plain text with no explanations, no markdown, code fences, or backticks. Available tools: {}. Searches are relative to the current directory unless the task names another. Print one path or match per line. {}{}", tools, NOT_ABLE_CLAUSE, env_note());
+    let (command, reasoning) = ask(matches, &system, &prompt).await;
+
+    // A blocked command is refused before the preview can run it.
+    if !is_not_able_response(&command) {
+        refuse_if_blocked(&command);
+    }
+    if matches.get_flag("preview") && !is_not_able_response(&command) {
+        match preview_form(&command) {
+            _ if policy().check_exec().is_err() => {
+                eprintln!("Preview skipped: running generated commands is disabled by policy.")
+            }
+            Some(safe) => preview(&safe),
+            None => {
+                eprintln!("Preview skipped: only a plain find, fd, rg, or grep with read-only options is previewed.")
+            }
+        }
+    }
+
+    emit_command(&prompt, &command, reasoning)
+}
+
+// The read-only version of `command`, if there is one. A plain `find ... -delete` is
+// previewed without the -delete so the match set can still be checked.
+fn preview_form(command: &str) -> Option<String> {
+    let words = plain_words(command.trim())?;
+    let (program, rest) = words.split_first()?;
+    let read_only = match program.as_str() {
+        "find" => find_read_only(rest),
+        "fd" | "fdfind" => flags_read_only(rest, FD_FLAGS, FD_VALUES, FD_LONG),
+        "rg" => flags_read_only(rest, RG_FLAGS, RG_VALUES, RG_LONG),
+        "grep" => flags_read_only(rest, GREP_FLAGS, GREP_VALUES, GREP_LONG),
+        _ => false,
+    };
+    if !read_only {
+        return None;
+    }
+    if program == "find" && rest.iter().any(|w| w == "-delete") {
+        // Words hold no shell syntax, so re-quoting them gives the same command.
+        let kept: Vec<String> = words
+            .iter()
+            .filter(|w| *w != "-delete")
+            .map(|w| shell_quote(w))
+            .collect();
+        return Some(kept.join(" "));
+    }
+    Some(command.trim().to_string())
+}
+
+// The words of a single simple command, with quotes and escapes removed. None when the
+// shell would see anything more: pipes, lists, redirects, subshells, substitutions,
+// variables, or more than one line.
+fn plain_words(command: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '$' | '`' | '\\' => return None,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                let next = chars.next()?;
+                if next == '\n' {
+                    return None;
+                }
+                in_word = true;
+                word.push(next);
+            }
+            ' ' | '\t' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '|' | '&' | ';' | '<' | '>' | '(' | ')' | '$' | '`' | '\n' | '\r' => return None,
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Some(words)
+}
+
+// Every predicate is a test or a plain print; `-delete` is dropped by the caller.
+fn find_read_only(words: &[String]) -> bool {
+    let mut words = words.iter();
+    while let Some(w) = words.next() {
+        if listed(FIND_VALUES, w) {
+            if words.next().is_none() {
+                return false;
+            }
+        } else if w.starts_with('-') && !listed(FIND_TESTS, w) && w != "-delete" {
+            return false;
+        }
+    }
+    true
+}
+
+// Every option is a known read-only one: a long option from `long` (with or without
+// `=value`), or a cluster of short ones from `short`. An option from `values` takes the
+// next word, unless its value is attached (`-d3`, `-tf`).
+fn flags_read_only(words: &[String], short: &str, values: &str, long: &str) -> bool {
+    let mut words = words.iter();
+    while let Some(w) = words.next() {
+        if w == "--" {
+            // Everything after is a pattern or path.
+            return true;
+        }
+        if w.starts_with("--") {
+            let (flag, attached) = match w.split_once('=') {
+                Some((f, _)) => (f, true),
+                None => (w.as_str(), false),
+            };
+            if !listed(long, flag) {
+                return false;
+            }
+            if !attached && listed(LONG_VALUES, flag) && words.next().is_none() {
+                return false;
+            }
+        } else if let Some(cluster) = w.strip_prefix('-').filter(|c| !c.is_empty()) {
+            for (i, c) in cluster.char_indices() {
+                if !short.contains(c) {
+                    return false;
+                }
+                if values.contains(c) {
+                    // The rest of the word is the value, or else the next word is.
+                    if i + c.len_utf8() == cluster.len() && words.next().is_none() {
+                        return false;
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    true
+}
+
+fn listed(list: &str, word: &str) -> bool {
+    list.split_whitespace().any(|w| w == word)
+}
+
+fn preview(command: &str) {
+    // exec, so the kill on timeout reaches the search itself rather than only the shell.
+    let mut child = match std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("exec {}", command))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Preview failed to run: {}", e);
+            return;
+        }
+    };
+
+    // Read on a thread so a huge result cannot block the child on a full pipe. The thread
+    // is never joined: anything still holding the pipe open would keep it reading.
+    let stdout = child.stdout.take();
+    let seen = Arc::new(Mutex::new((0usize, Vec::new())));
+    let (done_tx, done_rx) = mpsc::channel();
+    let sink = seen.clone();
+    std::thread::spawn(move || {
+        if let Some(out) = stdout {
+            for line in BufReader::new(out).lines().map_while(|l| l.ok()) {
+                let mut seen = sink.lock().expect("preview lock");
+                if seen.1.len() < PREVIEW_PATHS {
+                    seen.1.push(line);
+                }
+                seen.0 += 1;
+            }
+        }
+        let _ = done_tx.send(());
+    });
+
+    let deadline = Instant::now() + PREVIEW_TIMEOUT;
+    let mut timed_out = false;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                timed_out = true;
+                break;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(_) => break,
+        }
+    }
+    // Output still in the pipe after the exit
+    let _ = done_rx.recv_timeout(Duration::from_millis(200));
+
+    let (count, first) = std::mem::take(&mut *seen.lock().expect("preview lock"));
+    if count == 0 {
+        eprintln!("Preview: no matches. Check the directory, glob, or pattern.");
+        return;
+    }
+    let suffix = if timed_out {
+        "+ (stopped after 5s)"
+    } else {
+        ""
+    };
+    eprintln!("Preview: {}{} match(es), first results:", count, suffix);
+    for l in first {
+        eprintln!("  {}", l);
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::Result;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use super::{ask, ask_with, capture};
+use crate::context::{self, Probe};
+use crate::env::{env_note, truncate};
+use crate::llm::Settings;
+use crate::output::{emit_command, emit_text};
+use crate::prompt::NOT_ABLE_CLAUSE;
+use crate::{snippet, template};
+
+const DEFAULT_MAX_DIFF_BYTES: usize = 12000;
+const STATUS_LINES: usize = 20;
+
+const COMMIT_TYPES: [&str; 11] = [
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+pub fn command() -> Command {
+    Command::new("git")
+        .about("Generate git commands using repository context, or a commit message from the staged diff")
+        .after_help("Examples:\n  ss git \"rebase onto main keeping my merge commits\"\n  ss git commit\n  ss git commit --type fix --amend")
+        .arg(Arg::new("input").help("What you want to do in this repository").index(1))
+        .subcommand_negates_reqs(true)
+        .subcommand(
+            Command::new("commit")
+                .about("Generate a Conventional Commits message from the staged diff")
+                .arg(
+                    Arg::new("amend")
+                        .long("amend")
+                        .help("Describe the last commit together with the staged changes, for `git commit --amend`")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("type")
+                        .long("type")
+                        .help("Force the commit type")
+                        .num_args(1)
+                        .value_parser(COMMIT_TYPES),
+                )
+                .arg(
+                    Arg::new("max-diff-bytes")
+                        .long("max-diff-bytes")
+                        .help("Maximum size of the diff sent to the model (default: 12000)")
+                        .num_args(1)
+                        .value_parser(value_parser!(usize)),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    if capture("git", &["rev-parse", "--is-inside-work-tree"]).is_none() {
+        eprintln!("Not inside a git repository.");
+        std::process::exit(1);
+    }
+
+    if let Some(("commit", sub)) = matches.subcommand() {
+        return commit_message(sub).await;
+    }
+
+    let prompt = match matches.get_one::<String>("input") {
+        Some(p) => template::apply(&snippet::resolve(p)?, matches)?,
+        None => {
+            eprintln!("Usage: ss git 'what you want to do'  (or ss git commit)");
+            std::process::exit(1);
+        }
+    };
+
+    let system = format!("You are a strict git command generator. OUTPUT ONLY git/shell commands in plain text with no explanations, no markdown, code fences, or backticks. The entire response MUST be a single line; chain steps with && when several are needed. Use the real branch and remote names from the repository context. {}{}", NOT_ABLE_CLAUSE, env_note());
+    let settings = Settings::from_matches(matches);
+    let user = format!(
+        "Repository context:\n{}\nTask: {}",
+        context::fit(
+            matches,
+            "repository context",
+            &repo_context(&settings).await
+        ),
+        prompt
+    );
+
+    let (command, reasoning) = ask_with(&settings, matches, &system, &user).await;
+    emit_command(&prompt, &command, reasoning)
+}
+
+async fn commit_message(matches: &ArgMatches) -> Result<()> {
+    let amend = matches.get_flag("amend");
+    let max_bytes = matches
+        .get_one::<usize>("max-diff-bytes")
+        .copied()
+        .unwrap_or(DEFAULT_MAX_DIFF_BYTES);
+
+    // For --amend, diff against the parent of HEAD so the message covers the whole amended
+    // commit. A root commit has no parent, so it is diffed against the empty tree instead.
+    let base = if !amend {
+        None
+    } else if capture("git", &["rev-parse", "--verify", "--quiet", "HEAD~1"]).is_some() {
+        Some("HEAD~1".to_string())
+    } else {
+        capture("git", &["hash-object", "-t", "tree", "/dev/null"])
+    };
+    let mut stat_args = vec!["diff", "--cached", "--stat"];
+    let mut diff_args = vec!["diff", "--cached"];
+    if let Some(base) = &base {
+        stat_args.push(base);
+        diff_args.push(base);
+    }
+
+    let stat = capture("git", &stat_args).unwrap_or_default();
+    let diff = capture("git", &diff_args).unwrap_or_default();
+    if diff.is_empty() {
+        eprintln!("No staged changes; stage files with `git add` first.");
+        std::process::exit(1);
+    }
+
+    let diff = context::fit(matches, "staged diff", &diff);
+    let diff_ctx = if diff.len() > max_bytes {
+        eprintln!(
+            "Staged diff is {} bytes; sending the first {} (raise with --max-diff-bytes).",
+            diff.len(),
+            max_bytes
+        );
+        format!("{}\n... (diff truncated)", truncate(&diff, max_bytes))
+    } else {
+        diff
+    };
+
+    let mut system = String::from("You write git commit messages following the Conventional Commits specification. OUTPUT ONLY the commit message in plain text with no markdown, code fences, or quotes. First line: `type(optional scope): summary` in the imperative mood, at most 72 characters. If the change needs explanation, add a blank line and a short body wrapped at 72 characters describing what changed and why.");
+    if let Some(t) = matches.get_one::<String>("type") {
+        system.push_str(&format!(" The type MUST be `{}`.", t));
+    }
+
+    let mut user = format!("Diff stat:\n{}\n\nDiff:\n{}", stat, diff_ctx);
+    if amend {
+        let previous = capture("git", &["log", "-1", "--format=%B"]).unwrap_or_default();
+        user = format!(
+            "The message replaces this previous commit message (git commit --amend):\n{}\n\n{}",
+            previous, user
+        );
+    }
+
+    let (message, reasoning) = ask(matches, &system, &user).await;
+    let label = if amend {
+        "git commit --amend"
+    } else {
+        "git commit"
+    };
+    emit_text(label, &message, reasoning)
+}
+
+// Branch, upstream, remotes, working-tree status, recent commits, and any operation in
+// progress, each collected concurrently.
+async fn repo_context(settings: &Settings) -> String {
+    let probes: Vec<Probe> = vec![
+        ("branch", Box::new(branch_context)),
+        ("remotes", Box::new(remote_context)),
+        ("operation in progress", Box::new(operation_context)),
+        ("status", Box::new(status_context)),
+        ("log", Box::new(log_context)),
+    ];
+    context::gather(settings, probes).await
+}
+
+fn branch_context() -> String {
+    let mut ctx = String::new();
+    if let Some(branch) = capture("git", &["rev-parse", "--abbrev-ref", "HEAD"]) {
+        ctx.push_str(&format!("current branch: {}\n", branch));
+    }
+    if let Some(upstream) = capture(
+        "git",
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+    ) {
+        ctx.push_str(&format!("upstream: {}\n", upstream));
+    }
+    ctx
+}
+
+fn remote_context() -> String {
+    let mut ctx = String::new();
+    if let Some(remotes) = capture("git", &["remote"]) {
+        if !remotes.is_empty() {
+            ctx.push_str(&format!(
+                "remotes: {}\n",
+                remotes.lines().collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+    if let Some(branches) = capture("git", &["branch", "--format=%(refname:short)"]) {
+        let names: Vec<&str> = branches.lines().take(30).collect();
+        ctx.push_str(&format!("local branches: {}\n", names.join(", ")));
+    }
+    ctx
+}
+
+fn operation_context() -> String {
+    let mut ctx = String::new();
+    if let Some(git_dir) = capture("git", &["rev-parse", "--git-dir"]) {
+        let dir = std::path::Path::new(&git_dir);
+        for (marker, op) in [
+            ("rebase-merge", "rebase"),
+            ("rebase-apply", "rebase/am"),
+            ("MERGE_HEAD", "merge"),
+            ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ] {
+            if dir.join(marker).exists() {
+                ctx.push_str(&format!("operation in progress: {}\n", op));
+            }
+        }
+    }
+    ctx
+}
+
+fn status_context() -> String {
+    match capture("git", &["status", "--short"]) {
+        Some(status) if !status.is_empty() => {
+            let lines: Vec<&str> = status.lines().take(STATUS_LINES).collect();
+            format!("status:\n{}\n", lines.join("\n"))
+        }
+        _ => String::new(),
+    }
+}
+
+fn log_context() -> String {
+    capture(
+        "git",
+        &["log", "--oneline", "--decorate", "--graph", "-n", "10"],
+    )
+    .map(|log| format!("recent commits:\n{}\n", log))
+    .unwrap_or_default()
+}
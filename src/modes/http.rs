@@ -0,0 +1,143 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use serde_json::Value as JsonValue;
+use std::path::PathBuf;
+
+use super::{ask, input};
+use crate::context;
+use crate::env::{env_note, truncate};
+use crate::output::emit_command;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const MAX_SPEC_BYTES: usize = 12000;
+const METHODS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+
+pub fn command() -> Command {
+    Command::new("http")
+        .about("Generate a curl or httpie request, optionally grounded in an OpenAPI spec")
+        .after_help("Examples:\n  ss http \"POST a JSON webhook to example.com with HMAC signature header\"\n  ss http --client httpie --openapi openapi.json \"create a pet named Rex\"")
+        .arg(
+            Arg::new("input")
+                .help("The request to build")
+                .index(1)
+                .required(true),
+        )
+        .arg(
+            Arg::new("client")
+                .long("client")
+                .help("HTTP client to generate for (default: curl)")
+                .num_args(1)
+                .value_parser(["curl", "httpie"])
+                .default_value("curl"),
+        )
+        .arg(
+            Arg::new("openapi")
+                .long("openapi")
+                .help(
+                    "OpenAPI spec (JSON or YAML) sent as context so paths and parameters are real",
+                )
+                .num_args(1)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let client = matches
+        .get_one::<String>("client")
+        .map(|s| s.as_str())
+        .unwrap_or("curl");
+
+    let spec = match matches.get_one::<PathBuf>("openapi") {
+        Some(path) => Some(summarize_spec(&std::fs::read_to_string(path)?)),
+        None => None,
+    };
+
+    let tool = if client == "httpie" {
+        "httpie (`http`/`https` commands)"
+    } else {
+        "curl"
+    };
+    let system = format!("You are a strict HTTP request generator. OUTPUT ONLY a single-line {} command in plain text with no explanations, no markdown, code fences, or backticks. Quote JSON bodies and headers safely for a POSIX shell. When a signature or token must be computed, do it inline with openssl or shell variables rather than placeholders where possible. {}{}", tool, NOT_ABLE_CLAUSE, env_note());
+    let user = match spec {
+        Some(s) => format!(
+            "API description:\n{}\n\nTask: {}",
+            context::fit(matches, "API description", &s),
+            prompt
+        ),
+        None => prompt.clone(),
+    };
+
+    let (command, reasoning) = ask(matches, &system, &user).await;
+    emit_command(&prompt, &command, reasoning)
+}
+
+// JSON specs are reduced to servers plus one line per operation; anything else
+// (YAML) is sent as-is, truncated.
+fn summarize_spec(raw: &str) -> String {
+    let v: JsonValue = match serde_json::from_str(raw) {
+        Ok(v) => v,
+        Err(_) => return truncate(raw, MAX_SPEC_BYTES).to_string(),
+    };
+
+    let mut out = String::new();
+    if let Some(servers) = v.get("servers").and_then(|s| s.as_array()) {
+        let urls: Vec<&str> = servers
+            .iter()
+            .filter_map(|s| s.get("url").and_then(|u| u.as_str()))
+            .collect();
+        out.push_str(&format!("servers: {}\n", urls.join(", ")));
+    } else if let Some(host) = v.get("host").and_then(|h| h.as_str()) {
+        // Swagger 2.0
+        let base = v.get("basePath").and_then(|b| b.as_str()).unwrap_or("");
+        out.push_str(&format!("host: {}{}\n", host, base));
+    }
+
+    if let Some(paths) = v.get("paths").and_then(|p| p.as_object()) {
+        for (path, ops) in paths {
+            for method in METHODS {
+                let op = match ops.get(method) {
+                    Some(op) => op,
+                    None => continue,
+                };
+                let mut line = format!("{} {}", method.to_uppercase(), path);
+                if let Some(summary) = op.get("summary").and_then(|s| s.as_str()) {
+                    line.push_str(&format!(" - {}", summary));
+                }
+                if let Some(params) = op.get("parameters").and_then(|p| p.as_array()) {
+                    let names: Vec<String> = params
+                        .iter()
+                        .filter_map(|p| {
+                            let name = p.get("name")?.as_str()?;
+                            let loc = p.get("in").and_then(|i| i.as_str()).unwrap_or("?");
+                            Some(format!("{}:{}", loc, name))
+                        })
+                        .collect();
+                    if !names.is_empty() {
+                        line.push_str(&format!(" [params {}]", names.join(", ")));
+                    }
+                }
+                if let Some(content) = op
+                    .get("requestBody")
+                    .and_then(|b| b.get("content"))
+                    .and_then(|c| c.as_object())
+                {
+                    let types: Vec<&str> = content.keys().map(|k| k.as_str()).collect();
+                    line.push_str(&format!(" [body {}]", types.join(", ")));
+                }
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+    }
+    if let Some(schemes) = v
+        .get("components")
+        .and_then(|c| c.get("securitySchemes"))
+        .and_then(|s| s.as_object())
+    {
+        for (name, scheme) in schemes {
+            out.push_str(&format!("security {}: {}\n", name, scheme));
+        }
+    }
+    truncate(&out, MAX_SPEC_BYTES).to_string()
+}
@@ -0,0 +1,144 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::Deserialize;
+
+use super::{capture, strip_code_fences};
+use crate::env::{detect_package_manager, env_note};
+use crate::history::save_history;
+use crate::llm::{complete_or_exit, Settings};
+use crate::output::{deliver, exit_not_able, parse_not_able, refuse_if_blocked};
+use crate::prompt::NOT_ABLE_CLAUSE;
+use crate::{exec, privilege};
+
+// How many times the model may correct a package name that the repositories do not know.
+const MAX_CORRECTIONS: usize = 2;
+
+pub fn command() -> Command {
+    Command::new("install")
+        .about("Generate the install command for a tool using the detected package manager")
+        .after_help("Examples:\n  ss install fd\n  ss install --run ripgrep")
+        .arg(
+            Arg::new("tool")
+                .help("Tool to install (e.g. fd, ripgrep)")
+                .index(1)
+                .required(true),
+        )
+        .arg(
+            Arg::new("run")
+                .long("run")
+                .help("Run the install command after confirmation")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+#[derive(Deserialize)]
+struct InstallPlan {
+    packages: Vec<String>,
+    command: String,
+    // Executable name after install when it differs from the tool name (e.g. fdfind)
+    binary: Option<String>,
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let tool = matches
+        .get_one::<String>("tool")
+        .cloned()
+        .unwrap_or_default();
+    let pm = detect_package_manager();
+    let settings = Settings::from_matches(matches);
+
+    let pm_desc = pm.unwrap_or("unknown (prefer the platform's standard package manager)");
+    let system = format!("You generate package installation commands. Package manager: {}. Package names differ between repositories (e.g. fd is `fd-find` on Debian/Ubuntu and installs the binary `fdfind`); use the name the target repositories actually use. OUTPUT ONLY a JSON object with no markdown: {{\"packages\": [\"<package name>\", ...], \"command\": \"<single-line install command, with sudo when required>\", \"binary\": \"<installed executable name if it differs from the tool name, else null>\"}}. {}{}", pm_desc, NOT_ABLE_CLAUSE, env_note());
+    let mut messages = vec![
+        serde_json::json!({"role": "system", "content": system}),
+        serde_json::json!({"role": "user", "content": format!("Install: {}", tool)}),
+    ];
+
+    let mut attempts = 0;
+    let plan = loop {
+        let completion = complete_or_exit(&settings, &messages).await;
+        let answer = strip_code_fences(&completion.content);
+        if let Some(reason) = parse_not_able(&answer) {
+            exit_not_able(&reason);
+        }
+        let plan = match parse_plan(&answer) {
+            Some(p) => p,
+            None => {
+                eprintln!("Could not parse the model's answer:\n{}", answer);
+                std::process::exit(1);
+            }
+        };
+
+        // Correction loop: ask again when the package manager does not know a suggested package.
+        let missing: Vec<String> = match pm {
+            Some(pm) => plan
+                .packages
+                .iter()
+                .filter(|p| package_known(pm, p) == Some(false))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        if missing.is_empty() || attempts >= MAX_CORRECTIONS {
+            if !missing.is_empty() {
+                eprintln!(
+                    "Warning: {} not found in the {} repositories.",
+                    missing.join(", "),
+                    pm.unwrap_or_default()
+                );
+            }
+            break plan;
+        }
+        attempts += 1;
+        eprintln!(
+            "{} not found in the {} repositories; asking for a correction...",
+            missing.join(", "),
+            pm.unwrap_or_default()
+        );
+        messages.push(serde_json::json!({"role": "assistant", "content": answer}));
+        messages.push(serde_json::json!({"role": "user", "content": format!("The package(s) {} do not exist in the {} repositories on this system. Reply with a corrected JSON object.", missing.join(", "), pm.unwrap_or_default())}));
+    };
+
+    let command = plan.command.trim().to_string();
+    refuse_if_blocked(&command);
+    println!("{}", command);
+    privilege::warn(&command);
+    if let Some(bin) = plan
+        .binary
+        .as_deref()
+        .filter(|b| !b.is_empty() && *b != tool)
+    {
+        eprintln!("Note: the installed executable is `{}`.", bin);
+    }
+    deliver(&command)?;
+    save_history(&format!("install {}", tool), &command)?;
+
+    if matches.get_flag("run") {
+        exec::run(&Settings::from_matches(matches), &command, false).await?;
+    }
+    Ok(())
+}
+
+fn parse_plan(answer: &str) -> Option<InstallPlan> {
+    let start = answer.find('{')?;
+    let end = answer.rfind('}')?;
+    serde_json::from_str(answer.get(start..=end)?).ok()
+}
+
+// Ask the package manager whether it knows a package; None when it cannot be checked.
+fn package_known(pm: &str, package: &str) -> Option<bool> {
+    let known = match pm {
+        "apt-get" => capture("apt-cache", &["show", "--no-all-versions", package]).is_some(),
+        "dnf" | "yum" => capture(pm, &["info", "-q", package]).is_some(),
+        "pacman" => capture("pacman", &["-Si", package]).is_some(),
+        "zypper" => capture("zypper", &["--quiet", "info", package])
+            .map(|o| !o.contains("not found"))
+            .unwrap_or(false),
+        "apk" => capture("apk", &["search", "-e", package])
+            .map(|o| !o.is_empty())
+            .unwrap_or(false),
+        "brew" => capture("brew", &["info", package]).is_some(),
+        _ => return None,
+    };
+    Some(known)
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use serde_json::Value as JsonValue;
+
+use super::{ask, input, read_piped_stdin, run_with_stdin};
+use crate::context;
+use crate::env::truncate;
+use crate::output::{emit_text, is_not_able_response};
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const DEFAULT_SAMPLE_BYTES: usize = 4000;
+// Arrays in the sample keep only their first few items; the shape matters, not the volume.
+const SAMPLE_ARRAY_ITEMS: usize = 3;
+const SAMPLE_STRING_CHARS: usize = 200;
+
+pub fn command() -> Command {
+    Command::new("jq")
+        .about("Generate a jq filter, using JSON piped on stdin as a sample")
+        .after_help("Examples:\n  curl -s https://api.example.com/images | ss jq \"the name and latest tag of each image\"\n  docker inspect web | ss jq --verify \"list the mounted host paths\"")
+        .arg(Arg::new("input").help("What the filter should extract or transform").index(1).required(true))
+        .arg(
+            Arg::new("verify")
+                .long("verify")
+                .help("Run the generated filter against the piped JSON and report whether it produces output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sample-bytes")
+                .long("sample-bytes")
+                .help("Maximum size of the JSON sample sent to the model (default: 4000)")
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let verify = matches.get_flag("verify");
+    let sample_bytes = matches
+        .get_one::<usize>("sample-bytes")
+        .copied()
+        .unwrap_or(DEFAULT_SAMPLE_BYTES);
+
+    let input = read_piped_stdin()?;
+    if input.is_none() {
+        eprintln!("No JSON piped on stdin; generating the filter without a sample.");
+    }
+
+    let system = format!("You are a strict jq filter generator. OUTPUT ONLY a single jq filter expression in plain text: no `jq` command name, no surrounding quotes, no flags, no explanations, no markdown, code fences, or backticks. The filter must work on input shaped like the provided sample. {}", NOT_ABLE_CLAUSE);
+    let user = match &input {
+        Some(json) => format!(
+            "Sample input JSON (arrays shortened to their first {} items):\n{}\n\nTask: {}",
+            SAMPLE_ARRAY_ITEMS,
+            context::fit(matches, "JSON sample", &sample_json(json, sample_bytes)),
+            prompt
+        ),
+        None => prompt.clone(),
+    };
+
+    let (filter, reasoning) = ask(matches, &system, &user).await;
+
+    // Verification output goes to stderr so stdout stays just the filter.
+    if verify && !is_not_able_response(&filter) {
+        match &input {
+            Some(json) => verify_filter(&filter, json),
+            None => eprintln!("Nothing piped on stdin; skipping verification."),
+        }
+    }
+
+    emit_text(&prompt, &filter, reasoning)
+}
+
+// Build a compact sample: shrink arrays and long strings when the input parses,
+// otherwise (e.g. JSON lines) fall back to a plain byte cut.
+fn sample_json(raw: &str, max_bytes: usize) -> String {
+    let text = match serde_json::from_str::<JsonValue>(raw) {
+        Ok(v) => serde_json::to_string_pretty(&shrink(v)).unwrap_or_else(|_| raw.to_string()),
+        Err(_) => raw.to_string(),
+    };
+    if text.len() <= max_bytes {
+        return text;
+    }
+    format!("{}\n... (truncated)", truncate(&text, max_bytes))
+}
+
+fn shrink(v: JsonValue) -> JsonValue {
+    match v {
+        JsonValue::Array(items) => JsonValue::Array(
+            items
+                .into_iter()
+                .take(SAMPLE_ARRAY_ITEMS)
+                .map(shrink)
+                .collect(),
+        ),
+        JsonValue::Object(map) => {
+            JsonValue::Object(map.into_iter().map(|(k, v)| (k, shrink(v))).collect())
+        }
+        JsonValue::String(s) if s.chars().count() > SAMPLE_STRING_CHARS => {
+            JsonValue::String(s.chars().take(SAMPLE_STRING_CHARS).collect::<String>() + "...")
+        }
+        other => other,
+    }
+}
+
+fn verify_filter(filter: &str, json: &str) {
+    let output = match run_with_stdin("jq", &[filter], json) {
+        Ok(o) => o,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("jq not found on PATH; skipping verification.");
+            return;
+        }
+        Err(e) => {
+            eprintln!("jq verification failed: {}", e);
+            return;
+        }
+    };
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        eprintln!(
+            "jq verification failed: {}",
+            err.lines().next().unwrap_or("unknown error")
+        );
+        return;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty() && l.trim() != "null")
+        .collect();
+    if lines.is_empty() {
+        eprintln!("jq verification: filter ran but produced no output on the sample.");
+    } else {
+        eprintln!("jq verification: {} line(s) of output, e.g.:", lines.len());
+        for l in lines.iter().take(3) {
+            eprintln!("  {}", truncate(l, 120));
+        }
+    }
+}
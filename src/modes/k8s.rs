@@ -0,0 +1,77 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use super::{ask, capture, input};
+use crate::context;
+use crate::env::{env_note, truncate};
+use crate::output::emit_command;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+// api-resources can list hundreds of kinds on clusters with many CRDs.
+const MAX_RESOURCES_BYTES: usize = 4000;
+
+pub fn command() -> Command {
+    Command::new("k8s")
+        .about("Generate kubectl/helm commands using the current cluster context and namespace")
+        .after_help("Examples:\n  ss k8s \"restart the payments deployment and watch rollout\"\n  ss k8s --api-resources \"list all certificates that expire this month\"")
+        .arg(Arg::new("input").help("What you want to do on the cluster").index(1).required(true))
+        .arg(
+            Arg::new("api-resources")
+                .long("api-resources")
+                .help("Also send `kubectl api-resources` so commands use the resource kinds (including CRDs) the cluster really serves")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+
+    let mut ctx = String::new();
+    match capture("kubectl", &["config", "current-context"]) {
+        Some(c) => ctx.push_str(&format!("kubectl context: {}\n", c)),
+        None => eprintln!(
+            "kubectl not found or no current context; generating without cluster details."
+        ),
+    }
+    // An empty namespace in the kubeconfig means kubectl falls back to "default"
+    if let Some(ns) = capture(
+        "kubectl",
+        &["config", "view", "--minify", "-o", "jsonpath={..namespace}"],
+    ) {
+        let ns = if ns.is_empty() {
+            "default".to_string()
+        } else {
+            ns
+        };
+        ctx.push_str(&format!("configured namespace: {}\n", ns));
+    }
+    if let Some(v) = capture("helm", &["version", "--short"]) {
+        ctx.push_str(&format!("helm: {}\n", v));
+    }
+    if matches.get_flag("api-resources") {
+        match capture("kubectl", &["api-resources", "--no-headers", "-o", "name"]) {
+            Some(r) => {
+                let names = r.lines().collect::<Vec<_>>().join(", ");
+                ctx.push_str(&format!(
+                    "api resources: {}\n",
+                    truncate(&names, MAX_RESOURCES_BYTES)
+                ));
+            }
+            None => eprintln!("kubectl api-resources failed; is the cluster reachable?"),
+        }
+    }
+
+    let system = format!("You are a strict Kubernetes command generator. OUTPUT ONLY kubectl/helm shell commands in plain text with no explanations, no markdown, code fences, or backticks. The entire response MUST be a single line; chain steps with && when several are needed. Rely on the configured namespace rather than adding -n unless the task names a different one, and only use resource kinds the cluster serves when they are listed. {}{}", NOT_ABLE_CLAUSE, env_note());
+    let user = if ctx.is_empty() {
+        prompt.clone()
+    } else {
+        format!(
+            "Cluster context:\n{}\nTask: {}",
+            context::fit(matches, "cluster context", &ctx),
+            prompt
+        )
+    };
+
+    let (command, reasoning) = ask(matches, &system, &user).await;
+    emit_command(&prompt, &command, reasoning)
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use std::time::Duration;
+
+use super::{ask, input, output_with_timeout};
+use crate::context;
+use crate::env::{env_note, truncate};
+use crate::output::emit_command;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const DEFAULT_MAX_DOC_BYTES: usize = 12000;
+// Always keep the top of the page (NAME/SYNOPSIS) before picking relevant paragraphs.
+const HEAD_BYTES: usize = 2000;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub fn command() -> Command {
+    Command::new("man")
+        .about("Answer a question about a command using its installed man page or --help output")
+        .after_help("Examples:\n  ss man rsync \"how do I exclude dotfiles but keep .gitignore\"\n  ss man tar \"extract a single file\"")
+        .arg(
+            Arg::new("tool")
+                .help("Command to look up (e.g. rsync)")
+                .index(1)
+                .required(true),
+        )
+        .arg(
+            Arg::new("input")
+                .help("What you want to do with it")
+                .index(2)
+                .required(true),
+        )
+        .arg(
+            Arg::new("max-doc-bytes")
+                .long("max-doc-bytes")
+                .help("Maximum size of the documentation sent as context (default: 12000)")
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let tool = matches
+        .get_one::<String>("tool")
+        .cloned()
+        .unwrap_or_default();
+    let prompt = input(matches)?;
+    let max_bytes = matches
+        .get_one::<usize>("max-doc-bytes")
+        .copied()
+        .unwrap_or(DEFAULT_MAX_DOC_BYTES);
+
+    let docs = match read_docs(&tool) {
+        Some(d) => d,
+        None => {
+            eprintln!("No man page or --help output found for {}.", tool);
+            std::process::exit(1);
+        }
+    };
+    let docs = context::fit(
+        matches,
+        "documentation excerpt",
+        &trim_docs(&docs, &prompt, max_bytes),
+    );
+    let version = probe(&tool, "--version")
+        .and_then(|v| v.lines().next().map(|l| l.to_string()))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let system = format!("You are a strict shell command generator. OUTPUT ONLY a single-line shell command in plain text with no explanations, no markdown, code fences, or backticks. Use only options that appear in the provided documentation of the installed version; never invent flags. {}{}", NOT_ABLE_CLAUSE, env_note());
+    let user = format!(
+        "Installed {} version: {}\nDocumentation (excerpt):\n{}\n\nTask: {}",
+        tool, version, docs, prompt
+    );
+
+    let (command, reasoning) = ask(matches, &system, &user).await;
+    emit_command(&prompt, &command, reasoning)
+}
+
+// Prefer the man page rendered as plain text; fall back to `--help`.
+fn read_docs(tool: &str) -> Option<String> {
+    let man = std::process::Command::new("man")
+        .arg(tool)
+        .env("MANPAGER", "cat")
+        .env("MANWIDTH", "100")
+        .env("PAGER", "cat")
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success() && !o.stdout.is_empty())
+        .map(|o| strip_overstrike(&String::from_utf8_lossy(&o.stdout)));
+    man.or_else(|| probe(tool, "--help").filter(|h| !h.is_empty()))
+}
+
+// `tool flag`, with stdin closed and a time limit: the tool is whatever the user named,
+// and one that ignores the flag may sit waiting for input.
+fn probe(tool: &str, flag: &str) -> Option<String> {
+    let output = output_with_timeout(std::process::Command::new(tool).arg(flag), PROBE_TIMEOUT)?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Remove the backspace overstrike sequences man uses for bold/underline (what `col -b` does).
+fn strip_overstrike(s: &str) -> String {
+    let mut out: Vec<char> = Vec::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '\u{8}' {
+            out.pop();
+        } else {
+            out.push(c);
+        }
+    }
+    out.into_iter().collect()
+}
+
+// Keep the head of the page, then the paragraphs that mention words from the question.
+fn trim_docs(docs: &str, question: &str, max_bytes: usize) -> String {
+    if docs.len() <= max_bytes {
+        return docs.to_string();
+    }
+    let head = truncate(docs, HEAD_BYTES.min(max_bytes));
+    let mut out = format!("{}\n...\n", head);
+
+    let keywords: Vec<String> = question
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '.')
+        .filter(|w| w.len() > 3)
+        .map(|w| w.to_lowercase())
+        .collect();
+
+    for para in docs[head.len()..].split("\n\n") {
+        let lower = para.to_lowercase();
+        if !keywords.iter().any(|k| lower.contains(k.as_str())) {
+            continue;
+        }
+        if out.len() + para.len() + 2 > max_bytes {
+            break;
+        }
+        out.push_str(para);
+        out.push_str("\n\n");
+    }
+    out
+}
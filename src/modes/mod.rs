@@ -0,0 +1,239 @@
+// Task-specific generation modes exposed as subcommands (`ss jq ...`).
+// Each mode gathers its own context, then reuses the shared LLM and output paths.
+
+pub mod alias;
+pub mod cheat;
+pub mod cloud;
+pub mod docker;
+pub mod ffmpeg;
+pub mod find;
+pub mod git;
+pub mod http;
+pub mod install;
+pub mod jq;
+pub mod k8s;
+pub mod man;
+pub mod sql;
+pub mod systemd;
+pub mod text;
+pub mod tf;
+pub mod watch;
+
+use anyhow::Result;
+use clap::ArgMatches;
+use serde_json::Value as JsonValue;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::process::{Output, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::llm::{complete_or_exit, Settings};
+use crate::output::deterministic;
+use crate::{context, record, snippet, template};
+
+// Read everything piped on stdin; None when stdin is an interactive terminal.
+pub fn read_piped_stdin() -> io::Result<Option<String>> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
+    }
+    let mut s = String::new();
+    stdin.read_to_string(&mut s)?;
+    Ok(Some(s))
+}
+
+// The first `lines` lines piped on stdin, cut at `max_bytes`; None when stdin is an
+// interactive terminal. Reading stops there, so a multi-GB log is never held in memory.
+pub fn read_piped_head(lines: usize, max_bytes: usize) -> io::Result<Option<String>> {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
+    }
+    let mut reader = stdin.lock().take(max_bytes as u64);
+    let mut head = Vec::new();
+    for _ in 0..lines {
+        if reader.read_until(b'\n', &mut head)? == 0 {
+            break;
+        }
+    }
+    // The byte cap may have split the last character
+    let text = String::from_utf8_lossy(&head);
+    Ok(Some(text.trim_end_matches('\u{FFFD}').to_string()))
+}
+
+// The mode's instruction, with an @snippet expanded and any {{placeholders}} filled in.
+pub fn input(matches: &ArgMatches) -> Result<String> {
+    let raw = matches
+        .get_one::<String>("input")
+        .cloned()
+        .unwrap_or_default();
+    template::apply(&snippet::resolve(&raw)?, matches)
+}
+
+// Send a one-shot system + user exchange and return the trimmed answer, plus the
+// model's reasoning when -S was given.
+pub async fn ask(matches: &ArgMatches, system: &str, user: &str) -> (String, Option<JsonValue>) {
+    ask_with(&Settings::from_matches(matches), matches, system, user).await
+}
+
+// `ask` with settings the mode already built, e.g. to warm up the connection.
+pub async fn ask_with(
+    settings: &Settings,
+    matches: &ArgMatches,
+    system: &str,
+    user: &str,
+) -> (String, Option<JsonValue>) {
+    let messages = vec![
+        serde_json::json!({"role": "system", "content": system}),
+        serde_json::json!({"role": "user", "content": user}),
+    ];
+    context::preview(&messages);
+    let completion = complete_or_exit(settings, &messages).await;
+    let reasoning = if matches.get_flag("show-reasoning") {
+        completion.reasoning
+    } else {
+        None
+    };
+    (completion.content.trim().to_string(), reasoning)
+}
+
+// Run a program with `input` on stdin and capture its output.
+pub fn run_with_stdin(program: &str, args: &[&str], input: &str) -> io::Result<Output> {
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The program may exit before reading everything; a broken pipe shows up in its status
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    child.wait_with_output()
+}
+
+// Run a program with no stdin, killing it once `limit` passes (None then). Output is read
+// on threads that are left behind rather than joined, so a grandchild that keeps the
+// pipes open cannot hang the caller; whatever arrived shortly after exit is returned.
+pub fn output_with_timeout(cmd: &mut std::process::Command, limit: Duration) -> Option<Output> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+    let collect = |source: Option<Box<dyn Read + Send>>| {
+        let buf = Arc::new(Mutex::new(Vec::new()));
+        let (done_tx, done_rx) = mpsc::channel();
+        let sink = buf.clone();
+        std::thread::spawn(move || {
+            if let Some(mut source) = source {
+                let mut chunk = [0u8; 4096];
+                while let Ok(n) = source.read(&mut chunk) {
+                    if n == 0 {
+                        break;
+                    }
+                    sink.lock()
+                        .expect("output lock")
+                        .extend_from_slice(&chunk[..n]);
+                }
+            }
+            let _ = done_tx.send(());
+        });
+        (buf, done_rx)
+    };
+    let stdout = collect(
+        child
+            .stdout
+            .take()
+            .map(|s| Box::new(s) as Box<dyn Read + Send>),
+    );
+    let stderr = collect(
+        child
+            .stderr
+            .take()
+            .map(|s| Box::new(s) as Box<dyn Read + Send>),
+    );
+
+    let deadline = Instant::now() + limit;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+    let take = |(buf, done): (Arc<Mutex<Vec<u8>>>, mpsc::Receiver<()>)| {
+        let _ = done.recv_timeout(Duration::from_millis(200));
+        std::mem::take(&mut *buf.lock().expect("output lock"))
+    };
+    Some(Output {
+        status,
+        stdout: take(stdout),
+        stderr: take(stderr),
+    })
+}
+
+// Run a program and return its trimmed stdout, or None if it is missing or fails.
+pub fn capture(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Models sometimes wrap file contents in ``` fences despite instructions; drop them.
+pub fn strip_code_fences(s: &str) -> String {
+    let trimmed = s.trim();
+    if !trimmed.starts_with("```") {
+        return trimmed.to_string();
+    }
+    let mut lines: Vec<&str> = trimmed.lines().skip(1).collect();
+    if lines.last().map(|l| l.trim() == "```").unwrap_or(false) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+// Ask a yes/no question on stderr; anything but y/yes (including EOF) is no.
+pub fn confirm(question: &str) -> bool {
+    ask_yes_no(question, &mut io::stdin().lock())
+}
+
+// Like confirm, answered on the terminal, for when stdin is piped input. No terminal
+// means no.
+pub fn confirm_on_tty(question: &str) -> bool {
+    match std::fs::File::open("/dev/tty") {
+        Ok(tty) => ask_yes_no(question, &mut io::BufReader::new(tty)),
+        Err(_) => {
+            eprintln!("{} No (no terminal to answer on).", question);
+            false
+        }
+    }
+}
+
+fn ask_yes_no(question: &str, answers: &mut impl io::BufRead) -> bool {
+    if deterministic() {
+        eprintln!("{} No (--deterministic).", question);
+        return false;
+    }
+    let question = format!("{} [y/N] ", question);
+    eprint!("{}", question);
+    let _ = io::stderr().flush();
+    let mut line = String::new();
+    if answers.read_line(&mut line).is_err() {
+        return false;
+    }
+    record::answered(&question, &line);
+    matches!(line.trim().to_lowercase().as_str(), "y" | "yes")
+}
@@ -0,0 +1,281 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use super::{ask, capture, input, strip_code_fences};
+use crate::context;
+use crate::env::{language_note, truncate};
+use crate::output::{emit_text, is_not_able_response};
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const MAX_SCHEMA_BYTES: usize = 12000;
+
+pub fn command() -> Command {
+    Command::new("sql")
+        .about("Generate a SQL query using a schema file or an introspected database")
+        .after_help("Examples:\n  ss sql --schema schema.sql \"monthly active users by plan\"\n  ss sql --db postgres://app@localhost/app --explain \"ten largest customers this quarter\"\n  ss sql --db ./data.db \"duplicate emails in users\"")
+        .arg(Arg::new("input").help("What the query should return").index(1).required(true))
+        .arg(
+            Arg::new("schema")
+                .long("schema")
+                .help("Schema DDL file sent as context")
+                .num_args(1)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("db")
+                .long("db")
+                .help("Connection string to introspect (postgres://..., mysql://..., or a sqlite file path)")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("dialect")
+                .long("dialect")
+                .help("SQL dialect (default: inferred from --db, else postgres)")
+                .num_args(1)
+                .value_parser(["postgres", "mysql", "sqlite"]),
+        )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .help("Show the query plan (with --db) or a short explanation of the query on stderr")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let db = matches.get_one::<String>("db");
+    let dialect = matches
+        .get_one::<String>("dialect")
+        .cloned()
+        .or_else(|| db.map(|d| dialect_of(d).to_string()))
+        .unwrap_or_else(|| "postgres".to_string());
+
+    let schema = if let Some(path) = matches.get_one::<PathBuf>("schema") {
+        Some(std::fs::read_to_string(path)?)
+    } else if let Some(conn) = db {
+        let s = introspect(conn, &dialect);
+        if s.is_none() {
+            eprintln!("Could not introspect the database schema; generating without it.");
+        }
+        s
+    } else {
+        None
+    };
+
+    let system = format!("You are a strict SQL generator for {}. OUTPUT ONLY one SQL statement in plain text with no explanations, no markdown, code fences, or backticks around it; it may span several lines and must end with a semicolon. Use only tables and columns from the provided schema when one is given, and the syntax and date functions of the target dialect. {}", dialect, NOT_ABLE_CLAUSE);
+    let user = match &schema {
+        Some(s) => {
+            let s = &context::fit(matches, "schema", s);
+            let ctx = if s.len() > MAX_SCHEMA_BYTES {
+                eprintln!(
+                    "Schema is {} bytes; sending the first {}.",
+                    s.len(),
+                    MAX_SCHEMA_BYTES
+                );
+                format!("{}\n-- (schema truncated)", truncate(s, MAX_SCHEMA_BYTES))
+            } else {
+                s.clone()
+            };
+            format!("Schema:\n{}\n\nTask: {}", ctx, prompt)
+        }
+        None => prompt.clone(),
+    };
+
+    let (answer, reasoning) = ask(matches, &system, &user).await;
+    let query = strip_code_fences(&answer);
+
+    if matches.get_flag("explain") && !is_not_able_response(&query) {
+        match db {
+            Some(_) if !single_statement(&query) => {
+                eprintln!("Not explaining: the query holds more than one statement.")
+            }
+            Some(conn) => match explain_plan(conn, &dialect, &query) {
+                Some(plan) => eprintln!("Query plan:\n{}", plan),
+                None => eprintln!("EXPLAIN failed against the database."),
+            },
+            None => {
+                let system = format!("Explain what the given SQL query returns and how, in at most three short plain-text sentences. No markdown.{}", language_note());
+                let (explanation, _) = ask(matches, &system, &query).await;
+                eprintln!("{}", explanation);
+            }
+        }
+    }
+
+    emit_text(&prompt, &query, reasoning)
+}
+
+fn dialect_of(conn: &str) -> &'static str {
+    if conn.starts_with("postgres://") || conn.starts_with("postgresql://") {
+        "postgres"
+    } else if conn.starts_with("mysql://") || conn.starts_with("mariadb://") {
+        "mysql"
+    } else {
+        "sqlite"
+    }
+}
+
+fn sqlite_path(conn: &str) -> &str {
+    conn.strip_prefix("sqlite://")
+        .or_else(|| conn.strip_prefix("sqlite:"))
+        .unwrap_or(conn)
+}
+
+// Client invocations never carry the password on argv, where other users can see it in
+// the process list; it goes in PGPASSWORD or MYSQL_PWD instead.
+struct Client {
+    args: Vec<String>,
+    env: Option<(&'static str, String)>,
+}
+
+// psql and pg_dump take the URL with its password moved to PGPASSWORD.
+fn pg_client(conn: &str) -> Client {
+    let Ok(mut url) = Url::parse(conn) else {
+        return Client {
+            args: vec![conn.to_string()],
+            env: None,
+        };
+    };
+    let password = url.password().map(decode);
+    let _ = url.set_password(None);
+    Client {
+        args: vec![url.to_string()],
+        env: password.map(|p| ("PGPASSWORD", p)),
+    }
+}
+
+// mysql clients take discrete flags rather than a URL.
+fn mysql_client(conn: &str) -> Option<Client> {
+    let url = Url::parse(conn).ok()?;
+    let mut args = vec![format!("--host={}", url.host_str().unwrap_or("localhost"))];
+    if let Some(port) = url.port() {
+        args.push(format!("--port={}", port));
+    }
+    if !url.username().is_empty() {
+        args.push(format!("--user={}", decode(url.username())));
+    }
+    args.push(url.path().trim_start_matches('/').to_string());
+    Some(Client {
+        args,
+        env: url.password().map(|p| ("MYSQL_PWD", decode(p))),
+    })
+}
+
+// The URL keeps user names and passwords percent-encoded (`p%40ss`); clients need them
+// as typed.
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}
+
+// Like `capture`, with the client's password in the environment.
+fn run_client(program: &str, before: &[&str], client: &Client, after: &[&str]) -> Option<String> {
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(before)
+        .args(&client.args)
+        .args(after)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some((name, value)) = &client.env {
+        cmd.env(name, value);
+    }
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn introspect(conn: &str, dialect: &str) -> Option<String> {
+    match dialect {
+        "postgres" => run_client(
+            "pg_dump",
+            &["--schema-only", "--no-owner", "--no-privileges"],
+            &pg_client(conn),
+            &[],
+        ),
+        "mysql" => run_client(
+            "mysqldump",
+            &["--no-data", "--skip-comments"],
+            &mysql_client(conn)?,
+            &[],
+        ),
+        _ => capture("sqlite3", &["-readonly", sqlite_path(conn), ".schema"]),
+    }
+}
+
+// Whether `query` is one statement: no `;` outside quotes and comments except at the end.
+// EXPLAIN covers only the first statement of several, and the client would run the rest.
+fn single_statement(query: &str) -> bool {
+    let mut chars = query.chars().peekable();
+    let mut ended = false;
+    while let Some(c) = chars.next() {
+        if ended && !c.is_whitespace() {
+            return false;
+        }
+        match c {
+            '\'' | '"' | '`' => {
+                for d in chars.by_ref() {
+                    if d == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for d in chars.by_ref() {
+                    if d == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for d in chars.by_ref() {
+                    if prev == '*' && d == '/' {
+                        break;
+                    }
+                    prev = d;
+                }
+            }
+            ';' => ended = true,
+            _ => {}
+        }
+    }
+    true
+}
+
+// The plan is read inside a read-only transaction that is rolled back (a read-only
+// connection for sqlite), so nothing can change even if EXPLAIN would run the query.
+fn explain_plan(conn: &str, dialect: &str, query: &str) -> Option<String> {
+    let query = query.trim().trim_end_matches(';');
+    let stmt = format!("EXPLAIN {}", query);
+    match dialect {
+        "postgres" => run_client(
+            "psql",
+            &["-X", "-q", "-v", "ON_ERROR_STOP=1"],
+            &pg_client(conn),
+            &["-c", "BEGIN READ ONLY", "-c", &stmt, "-c", "ROLLBACK"],
+        ),
+        "mysql" => run_client(
+            "mysql",
+            &[],
+            &mysql_client(conn)?,
+            &[
+                "-e",
+                &format!("START TRANSACTION READ ONLY; {}; ROLLBACK;", stmt),
+            ],
+        ),
+        _ => capture(
+            "sqlite3",
+            &[
+                "-readonly",
+                sqlite_path(conn),
+                &format!("EXPLAIN QUERY PLAN {}", query),
+            ],
+        ),
+    }
+}
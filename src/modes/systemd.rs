@@ -0,0 +1,197 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use directories::BaseDirs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use super::{ask, capture, confirm, input, strip_code_fences};
+use crate::env::env_note;
+use crate::history::save_history;
+use crate::output::{
+    copy_to_clipboard, exit_not_able, parse_not_able, print_reasoning, refuse_if_blocked,
+};
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const FILE_MARKER: &str = "### FILE:";
+
+pub fn command() -> Command {
+    Command::new("systemd")
+        .about("Generate a systemd service (and timer) unit, verified with systemd-analyze when available")
+        .after_help("Examples:\n  ss systemd \"run /opt/backup.sh nightly as user backup\"\n  ss systemd --user \"sync ~/notes to my server every 15 minutes\"")
+        .arg(Arg::new("input").help("What the unit should run and when").index(1).required(true))
+        .arg(
+            Arg::new("user")
+                .long("user")
+                .help("Target the per-user manager (~/.config/systemd/user) instead of /etc/systemd/system")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+struct UnitFile {
+    name: String,
+    content: String,
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let user_units = matches.get_flag("user");
+
+    let manager = if user_units {
+        "the per-user systemd manager (systemctl --user)"
+    } else {
+        "the system-wide systemd manager"
+    };
+    let system = format!("You are a strict systemd unit generator for {}. OUTPUT ONLY unit files, each introduced by a line `{} <name>.<service|timer>` followed by the complete file contents. Produce a .service unit and, when the task is scheduled, a matching .timer unit with the same base name. Use absolute paths, set User= when a user is named, and Persistent=true for calendar timers. No explanations, markdown, or code fences. {}{}", manager, FILE_MARKER, NOT_ABLE_CLAUSE, env_note());
+
+    let (answer, reasoning) = ask(matches, &system, &prompt).await;
+    let answer = strip_code_fences(&answer);
+    if let Some(reason) = parse_not_able(&answer) {
+        exit_not_able(&reason);
+    }
+
+    let units = parse_units(&answer);
+    if units.is_empty() {
+        eprintln!("The model did not return any unit files:\n{}", answer);
+        std::process::exit(1);
+    }
+
+    let mut combined = String::new();
+    for u in &units {
+        combined.push_str(&format!("# {}\n{}\n\n", u.name, u.content.trim_end()));
+    }
+    let combined = combined.trim_end().to_string();
+    refuse_if_blocked(&combined);
+    println!("{}", combined);
+    copy_to_clipboard(&combined);
+    save_history(&prompt, &combined)?;
+    if let Some(r) = reasoning {
+        print_reasoning(r);
+    }
+
+    verify(&units);
+
+    if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        let dir = unit_dir(user_units);
+        let question = format!("Write {} to {}?", names(&units), dir.display());
+        if confirm(&question) {
+            install(&units, &dir, user_units);
+        }
+    }
+    Ok(())
+}
+
+fn parse_units(answer: &str) -> Vec<UnitFile> {
+    let mut units: Vec<UnitFile> = Vec::new();
+    for line in answer.lines() {
+        if let Some(name) = line.trim().strip_prefix(FILE_MARKER) {
+            units.push(UnitFile {
+                name: name.trim().to_string(),
+                content: String::new(),
+            });
+        } else if let Some(u) = units.last_mut() {
+            u.content.push_str(line);
+            u.content.push('\n');
+        }
+    }
+    units.retain(|u| !u.name.is_empty() && !u.name.contains('/'));
+    units
+}
+
+fn names(units: &[UnitFile]) -> String {
+    units
+        .iter()
+        .map(|u| u.name.as_str())
+        .collect::<Vec<_>>()
+        .join(" and ")
+}
+
+fn unit_dir(user_units: bool) -> PathBuf {
+    if user_units {
+        if let Some(b) = BaseDirs::new() {
+            return b.config_dir().join("systemd").join("user");
+        }
+    }
+    PathBuf::from("/etc/systemd/system")
+}
+
+// systemd-analyze resolves the timer's service by name, so verify from a scratch dir holding both.
+fn verify(units: &[UnitFile]) {
+    if capture("systemd-analyze", &["--version"]).is_none() {
+        eprintln!("systemd-analyze not available; units were not verified.");
+        return;
+    }
+    // A fresh private directory, removed when `scratch` is dropped
+    let Ok(scratch) = tempfile::Builder::new()
+        .prefix("snapshell-units-")
+        .tempdir()
+    else {
+        return;
+    };
+    let dir = scratch.path();
+    let mut paths = Vec::new();
+    for u in units {
+        let p = dir.join(&u.name);
+        if std::fs::write(&p, &u.content).is_ok() {
+            paths.push(p);
+        }
+    }
+    let mut cmd = std::process::Command::new("systemd-analyze");
+    cmd.arg("verify").args(&paths);
+    match cmd.output() {
+        Ok(o) if o.status.success() && o.stderr.is_empty() => {
+            eprintln!("systemd-analyze verify: OK")
+        }
+        Ok(o) => {
+            eprintln!("systemd-analyze verify reported problems:");
+            eprint!("{}", String::from_utf8_lossy(&o.stderr));
+        }
+        Err(e) => eprintln!("systemd-analyze verify failed to run: {}", e),
+    }
+}
+
+fn install(units: &[UnitFile], dir: &Path, user_units: bool) {
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        eprintln!("Could not create {}: {}", dir.display(), e);
+        return;
+    }
+    for u in units {
+        let path = dir.join(&u.name);
+        if path.exists() && !confirm(&format!("{} exists. Overwrite?", path.display())) {
+            continue;
+        }
+        match write_unit(&path, &u.content) {
+            Ok(()) => eprintln!("Wrote {}", path.display()),
+            Err(e) => {
+                eprintln!("Could not write {}: {}", path.display(), e);
+                eprintln!("Retry with elevated permissions, e.g. save the unit and run: sudo install -m 644 {} {}", u.name, path.display());
+                return;
+            }
+        }
+    }
+    let ctl = if user_units {
+        "systemctl --user"
+    } else {
+        "sudo systemctl"
+    };
+    let start = units
+        .iter()
+        .find(|u| u.name.ends_with(".timer"))
+        .or_else(|| units.first())
+        .map(|u| u.name.as_str())
+        .unwrap_or_default();
+    eprintln!(
+        "Activate with: {} daemon-reload && {} enable --now {}",
+        ctl, ctl, start
+    );
+}
+
+// Unit files should be world-readable but only owner-writable.
+fn write_unit(path: &Path, content: &str) -> std::io::Result<()> {
+    std::fs::write(path, content)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o644))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use super::{ask, confirm_on_tty, input, read_piped_head, run_with_stdin};
+use crate::context;
+use crate::env::env_note;
+use crate::output::{emit_command, is_not_able_response, refuse_if_blocked};
+use crate::policy::policy;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const DEFAULT_SAMPLE_LINES: usize = 20;
+// Hard cap on the sample so one enormous line cannot blow up the request.
+const MAX_SAMPLE_BYTES: usize = 4000;
+
+pub fn command() -> Command {
+    Command::new("text")
+        .about("Generate an awk/sed one-liner, using the first lines of piped input as a sample")
+        .after_help("Examples:\n  cat access.log | ss text \"sum the third column\"\n  ps aux | ss text --preview \"the five processes using the most memory\"")
+        .arg(Arg::new("input").help("What to do with the text").index(1).required(true))
+        .arg(
+            Arg::new("lines")
+                .long("lines")
+                .help("Number of piped input lines sent as context (default: 20)")
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("preview")
+                .long("preview")
+                .help("Run the generated command against the sample only and show its result before the command")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+    let preview = matches.get_flag("preview");
+    let n = matches
+        .get_one::<usize>("lines")
+        .copied()
+        .unwrap_or(DEFAULT_SAMPLE_LINES);
+
+    let sample = read_piped_head(n, MAX_SAMPLE_BYTES)?.filter(|s| !s.is_empty());
+    if sample.is_none() {
+        eprintln!("No input piped on stdin; generating the command without a sample.");
+    }
+
+    let system = format!("You are a strict text-processing command generator. OUTPUT ONLY a single-line shell pipeline built from awk, sed, and standard coreutils, in plain text with no explanations, no markdown, code fences, or backticks. The command must read its input from stdin (never reference a file name) so it can be placed after a pipe. {}{}", NOT_ABLE_CLAUSE, env_note());
+    let user = match &sample {
+        Some(s) => format!(
+            "First {} lines of the input:\n{}\nTask: {}",
+            n,
+            context::fit(matches, "input sample", s),
+            prompt
+        ),
+        None => prompt.clone(),
+    };
+
+    let (command, reasoning) = ask(matches, &system, &user).await;
+
+    // A blocked command is refused before the preview can run it.
+    if !is_not_able_response(&command) {
+        refuse_if_blocked(&command);
+    }
+    // The preview goes to stderr so stdout stays just the command.
+    if preview && !is_not_able_response(&command) {
+        match &sample {
+            _ if policy().check_exec().is_err() => {
+                eprintln!("Preview skipped: running generated commands is disabled by policy.")
+            }
+            // The pipeline is arbitrary shell (awk can run programs and write files), so
+            // it runs only once you have read it.
+            Some(s) if confirm_on_tty(&format!("Preview `{}` on the sample?", command)) => {
+                preview_command(&command, s)
+            }
+            Some(_) => eprintln!("Preview skipped."),
+            None => eprintln!("Nothing piped on stdin; skipping preview."),
+        }
+    }
+
+    emit_command(&prompt, &command, reasoning)
+}
+
+fn preview_command(command: &str, sample: &str) {
+    match run_with_stdin("sh", &["-c", command], sample) {
+        Ok(output) => {
+            eprintln!("Preview (sample input only):");
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            for line in stdout.lines() {
+                eprintln!("  {}", line);
+            }
+            if !output.status.success() {
+                let err = String::from_utf8_lossy(&output.stderr);
+                eprintln!(
+                    "Preview command failed: {}",
+                    err.lines().next().unwrap_or("non-zero exit status")
+                );
+            }
+        }
+        Err(e) => eprintln!("Preview failed to run: {}", e),
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde_json::Value as JsonValue;
+use std::path::Path;
+
+use super::{ask, capture, input};
+use crate::context;
+use crate::env::{env_note, truncate};
+use crate::ignore::Ignore;
+use crate::output::emit_command;
+use crate::prompt::NOT_ABLE_CLAUSE;
+
+const MAX_STATE_BYTES: usize = 4000;
+
+pub fn command() -> Command {
+    Command::new("tf")
+        .about("Generate terraform commands using the current workspace and provider versions")
+        .after_help("Examples:\n  ss tf \"import the existing S3 bucket logs-prod into aws_s3_bucket.logs\"\n  ss tf --state \"move the db module's resources under module.database\"")
+        .arg(
+            Arg::new("input")
+                .help("What you want to do with terraform")
+                .index(1)
+                .required(true),
+        )
+        .arg(
+            Arg::new("state")
+                .long("state")
+                .help("Also send `terraform state list` so addresses of managed resources are real")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let prompt = input(matches)?;
+
+    let mut ctx = String::new();
+    match capture("terraform", &["version", "-json"]) {
+        Some(raw) => ctx.push_str(&describe_version(&raw)),
+        None => eprintln!("terraform not found; generating without workspace details."),
+    }
+    if let Some(ws) = capture("terraform", &["workspace", "show"]) {
+        ctx.push_str(&format!("workspace: {}\n", ws));
+    }
+    if let Ok(entries) = std::fs::read_dir(".") {
+        let mut files: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n.ends_with(".tf") || n.ends_with(".tfvars"))
+            .collect();
+        files.sort();
+        Ignore::for_dir(Path::new(".")).retain_in(Path::new("."), &mut files);
+        if !files.is_empty() {
+            ctx.push_str(&format!("configuration files: {}\n", files.join(", ")));
+        }
+    }
+    if matches.get_flag("state") {
+        match capture("terraform", &["state", "list"]) {
+            Some(list) => ctx.push_str(&format!(
+                "managed resources:\n{}\n",
+                truncate(&list, MAX_STATE_BYTES)
+            )),
+            None => eprintln!("terraform state list failed; is the directory initialized?"),
+        }
+    }
+
+    let system = format!("You are a strict Terraform command generator. OUTPUT ONLY terraform (or related shell) commands in plain text with no explanations, no markdown, code fences, or backticks. The entire response MUST be a single line; chain steps with && when several are needed. Use syntax valid for the installed Terraform and provider versions, and the import ID formats those providers expect. {}{}", NOT_ABLE_CLAUSE, env_note());
+    let user = if ctx.is_empty() {
+        prompt.clone()
+    } else {
+        format!(
+            "Terraform context:\n{}\nTask: {}",
+            context::fit(matches, "terraform context", &ctx),
+            prompt
+        )
+    };
+
+    let (command, reasoning) = ask(matches, &system, &user).await;
+    emit_command(&prompt, &command, reasoning)
+}
+
+// `terraform version -json` lists provider versions once the directory is initialized.
+fn describe_version(raw: &str) -> String {
+    let v: JsonValue = match serde_json::from_str(raw) {
+        Ok(v) => v,
+        Err(_) => return String::new(),
+    };
+    let mut out = String::new();
+    if let Some(tf) = v.get("terraform_version").and_then(|t| t.as_str()) {
+        out.push_str(&format!("terraform version: {}\n", tf));
+    }
+    if let Some(providers) = v.get("provider_selections").and_then(|p| p.as_object()) {
+        let list: Vec<String> = providers
+            .iter()
+            .map(|(name, ver)| format!("{} {}", name, ver.as_str().unwrap_or("?")))
+            .collect();
+        if !list.is_empty() {
+            out.push_str(&format!("providers: {}\n", list.join(", ")));
+        }
+    }
+    out
+}
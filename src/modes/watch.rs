@@ -0,0 +1,277 @@
+use anyhow::Result;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use regex::Regex;
+use serde::Deserialize;
+use std::io::{IsTerminal, Read, Write};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use super::ask_with;
+use crate::atuin;
+use crate::context;
+use crate::env::{env_note, which};
+use crate::exec;
+use crate::exec::shell_quote;
+use crate::history::record_exec;
+use crate::llm::Settings;
+use crate::output::{emit_command, is_not_able_response};
+use crate::policy::policy;
+use crate::prompt::NOT_ABLE_CLAUSE;
+use crate::record::Echo;
+
+// Only the end of a long build log is sent; that is where the error usually is.
+const MAX_OUTPUT_BYTES: usize = 8000;
+// How much of the output is kept while the command runs, with room for the escape codes
+// removed afterwards.
+const MAX_CAPTURED_BYTES: usize = 4 * MAX_OUTPUT_BYTES;
+const DEFAULT_ATTEMPTS: usize = 3;
+
+pub fn command() -> Command {
+    Command::new("watch")
+        .about(
+            "Run a command and, if it fails, diagnose the output and suggest a corrected command",
+        )
+        .after_help(
+            "Examples:\n  ss watch -- cargo build --release\n  ss watch 'npm ci && npm test'",
+        )
+        .arg(
+            Arg::new("command")
+                .help(
+                    "Command to run, after `--`; a single argument is run as a shell command line",
+                )
+                .num_args(1..)
+                .trailing_var_arg(true)
+                .allow_hyphen_values(true)
+                .required(true),
+        )
+        .arg(
+            Arg::new("attempts")
+                .long("attempts")
+                .help("How many corrected commands to offer before giving up (default: 3)")
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+}
+
+#[derive(Deserialize)]
+struct Diagnosis {
+    diagnosis: String,
+    command: String,
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let args: Vec<String> = matches
+        .get_many::<String>("command")
+        .expect("required")
+        .cloned()
+        .collect();
+    let attempts = matches
+        .get_one::<usize>("attempts")
+        .copied()
+        .unwrap_or(DEFAULT_ATTEMPTS);
+    let mut command = if args.len() == 1 {
+        args[0].clone()
+    } else {
+        args.iter()
+            .map(|a| quote_arg(a))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let settings = Settings::from_matches(matches);
+    let mut attempt = 0;
+    // After the first attempt: the corrected command as approved, possibly wrapped in sudo
+    let mut approved: Option<String> = None;
+    loop {
+        let (code, output) = match &approved {
+            Some(run_as) => run_logged(&command, run_as)?,
+            None => run_teed(&command)?,
+        };
+        if code == 0 {
+            if attempt > 0 {
+                eprintln!("`{}` succeeded.", command);
+            }
+            return Ok(());
+        }
+        eprintln!(
+            "Command failed with exit code {}; asking for a diagnosis...",
+            code
+        );
+
+        let system = format!("You diagnose failed shell commands from their output. Reply with ONLY a JSON object, no markdown or code fences: {{\"diagnosis\": \"<the cause in at most 3 short sentences>\", \"command\": \"<a single-line corrected command to run next>\"}}. When the fix is outside the command (a file to edit, a package to install), make the command do it if that is safe, otherwise repeat the original command and say what to change in the diagnosis. {}{}", NOT_ABLE_CLAUSE, env_note());
+        let tail = context::tail(&output, MAX_OUTPUT_BYTES);
+        let user = format!(
+            "Command: {}\nExit code: {}\nOutput (stdout and stderr interleaved{}):\n{}",
+            command,
+            code,
+            if tail.len() < output.len() {
+                ", last part"
+            } else {
+                ""
+            },
+            context::fit_tail(matches, "command output", tail)
+        );
+
+        let (answer, reasoning) = ask_with(&settings, matches, &system, &user).await;
+        let fix = if is_not_able_response(&answer) {
+            answer
+        } else {
+            match parse_diagnosis(&answer) {
+                Some(d) => {
+                    eprintln!("Diagnosis: {}", d.diagnosis.trim());
+                    d.command.trim().to_string()
+                }
+                None => {
+                    eprintln!("The model did not return a diagnosis.");
+                    std::process::exit(1);
+                }
+            }
+        };
+        emit_command(&format!("watch: {}", command), &fix, reasoning)?;
+
+        attempt += 1;
+        let can_run = std::io::stdin().is_terminal() && policy().check_exec().is_ok();
+        if attempt >= attempts || !can_run {
+            std::process::exit(code);
+        }
+        // The same confirmations as --exec, including the separate one for sudo
+        let Some(run_as) = exec::approve(&settings, &fix, false).await? else {
+            std::process::exit(code);
+        };
+        approved = Some(run_as);
+        command = fix;
+    }
+}
+
+// A corrected command is a generated one, so it goes to the execution log and atuin like
+// any other that snapshell runs.
+fn run_logged(generated: &str, command: &str) -> Result<(i32, String)> {
+    let start = Instant::now();
+    let atuin_entry = atuin::start(command);
+    let (code, output) = run_teed(command)?;
+    record_exec(generated, command, Some(code), start.elapsed(), &output);
+    if let Some(entry) = atuin_entry {
+        atuin::end(entry, Some(code));
+    }
+    Ok((code, output))
+}
+
+// Run through `sh -c`, passing output through to the terminal while keeping a copy. At a
+// terminal on Linux it runs under util-linux `script`, so the command still sees a TTY and
+// keeps its colors, progress bars and line buffering; the copy is cleaned of escape codes.
+fn run_teed(command: &str) -> Result<(i32, String)> {
+    let pty = cfg!(target_os = "linux")
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal()
+        && which("script").is_some();
+    let mut cmd = if pty {
+        let mut cmd = std::process::Command::new("script");
+        cmd.args([
+            "-qefc",
+            &format!("sh -c {}", shell_quote(command)),
+            "/dev/null",
+        ]);
+        cmd
+    } else {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    let mut child = cmd
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let out = tee(
+        child.stdout.take(),
+        Echo::new(std::io::stdout()),
+        log.clone(),
+        MAX_CAPTURED_BYTES,
+    );
+    let err = tee(
+        child.stderr.take(),
+        Echo::new(std::io::stderr()),
+        log.clone(),
+        MAX_CAPTURED_BYTES,
+    );
+    let status = child.wait()?;
+    let _ = out.join();
+    let _ = err.join();
+    // The kept tail may start inside a character
+    let mut log = String::from_utf8_lossy(&log.lock().expect("log lock"))
+        .trim_start_matches('\u{FFFD}')
+        .to_string();
+    if pty {
+        log = strip_terminal(&log);
+    }
+    // Killed by a signal: report it like a shell would
+    Ok((status.code().unwrap_or(128), log))
+}
+
+// Output captured from a terminal as it would read on screen: escape sequences removed,
+// and of a line redrawn with `\r` (progress bars) only the last version kept.
+fn strip_terminal(s: &str) -> String {
+    static ESCAPES: OnceLock<Regex> = OnceLock::new();
+    let escapes = ESCAPES.get_or_init(|| {
+        Regex::new(
+            r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(\x07|\x1b\\)|\x1b[()][0-9A-Za-z]|\x1b[=>]",
+        )
+        .expect("escape regex")
+    });
+    escapes
+        .replace_all(s, "")
+        .replace("\r\n", "\n")
+        .lines()
+        .map(|l| l.rsplit('\r').next().unwrap_or(l))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Copy `source` to `sink` as it arrives, keeping the last `keep` bytes of it in `log`.
+pub fn tee<R, W>(
+    source: Option<R>,
+    mut sink: W,
+    log: Arc<Mutex<Vec<u8>>>,
+    keep: usize,
+) -> std::thread::JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let Some(mut source) = source else { return };
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = source.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let _ = sink.write_all(&buf[..n]);
+            let _ = sink.flush();
+            let mut log = log.lock().expect("log lock");
+            log.extend_from_slice(&buf[..n]);
+            let excess = log.len().saturating_sub(keep);
+            log.drain(..excess);
+        }
+    })
+}
+
+// Leave plain words readable in the prompt and history; quote everything else.
+fn quote_arg(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        shell_quote(arg)
+    }
+}
+
+fn parse_diagnosis(answer: &str) -> Option<Diagnosis> {
+    let start = answer.find('{')?;
+    let end = answer.rfind('}')?;
+    serde_json::from_str(answer.get(start..=end)?).ok()
+}
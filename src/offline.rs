@@ -0,0 +1,104 @@
+// --offline: answers without touching the network, from the response cache, then the
+// closest earlier prompt in history, then a matching line of a cached cheat sheet.
+use serde_json::Value as JsonValue;
+
+use crate::history::load_history;
+use crate::llm::{complete, Completion, Settings, Usage};
+use crate::modes::cheat;
+
+// History prompts at least this similar (word overlap) count as the same request.
+const MIN_SIMILARITY: f64 = 0.5;
+
+fn words(s: &str) -> Vec<String> {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '.')
+        .filter(|w| w.len() > 1)
+        .map(|w| w.to_string())
+        .collect()
+}
+
+// Jaccard similarity of the word sets of two prompts.
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: std::collections::BTreeSet<String> = words(a).into_iter().collect();
+    let b: std::collections::BTreeSet<String> = words(b).into_iter().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+fn from_history(prompt: &str) -> Option<(String, String)> {
+    // max_by keeps the last of equal maxima, so newer entries win ties
+    load_history()
+        .ok()?
+        .into_iter()
+        .map(|e| (similarity(prompt, &e.prompt), e))
+        .filter(|(s, _)| *s >= MIN_SIMILARITY)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, e)| (e.prompt, e.command))
+}
+
+// The cheat sheet line whose explanation best overlaps the prompt, for any tool named
+// in the prompt that has a cached sheet. A line sharing no word with the prompt, or with
+// no command on it, is no answer.
+fn from_cheat_sheets(prompt: &str) -> Option<(String, String)> {
+    let prompt_words = words(prompt);
+    let mut best: Option<(usize, String, String)> = None;
+    for tool in &prompt_words {
+        let Some(sheet) = cheat::cached(tool) else {
+            continue;
+        };
+        for line in sheet.lines() {
+            let (command, explanation) = line.split_once('#').unwrap_or((line, ""));
+            if command.trim().is_empty() {
+                continue;
+            }
+            let score = words(explanation)
+                .iter()
+                .filter(|w| prompt_words.contains(w))
+                .count();
+            if score > best.as_ref().map_or(0, |(b, _, _)| *b) {
+                best = Some((score, tool.clone(), command.trim().to_string()));
+            }
+        }
+    }
+    best.map(|(_, tool, command)| (tool, command))
+}
+
+// Answer from local data only; exits when nothing matches.
+pub async fn complete_offline(
+    settings: &Settings,
+    messages: &[JsonValue],
+    prompt: &str,
+) -> Completion {
+    if let Ok(c) = complete(settings, messages).await {
+        eprintln!("(offline: cached answer)");
+        return c;
+    }
+    heuristics(prompt).unwrap_or_else(|| {
+        eprintln!(
+            "Offline: no cached answer, similar history entry, or cheat sheet for this prompt."
+        );
+        std::process::exit(1);
+    })
+}
+
+// The closest history entry, else a matching cheat sheet line.
+pub fn heuristics(prompt: &str) -> Option<Completion> {
+    let (note, content) = if let Some((earlier, command)) = from_history(prompt) {
+        (format!("closest history entry: {}", earlier), command)
+    } else {
+        let (tool, command) = from_cheat_sheets(prompt)?;
+        (format!("from the cached {} cheat sheet", tool), command)
+    };
+    eprintln!("(offline: {})", note);
+    Some(Completion {
+        content,
+        reasoning: None,
+        usage: Usage::default(),
+        model: None,
+        provider: None,
+        cached: false,
+    })
+}
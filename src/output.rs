@@ -0,0 +1,489 @@
+use anyhow::{bail, Result};
+use base64::Engine;
+use serde_json::Value as JsonValue;
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::clipring;
+use crate::confidence::{detect_hedges, warn_hedges};
+use crate::config::command_rules;
+use crate::env::runtime;
+use crate::exec::shell_quote;
+use crate::history::save_history;
+use crate::lint;
+use crate::llm::Completion;
+use crate::policy::policy;
+use crate::privilege;
+use crate::term;
+use crate::widget::RESULT_FILE_ENV;
+
+// Exit status when the model declines with NOT ABLE TO ANSWER, distinct from errors (1).
+pub const EXIT_NOT_ABLE: i32 = 3;
+// Exit status when a generated command is refused by policy or the allow/deny lists.
+pub const EXIT_BLOCKED: i32 = 4;
+
+static PLAIN: OnceLock<bool> = OnceLock::new();
+static DETERMINISTIC: OnceLock<bool> = OnceLock::new();
+static QUIET: OnceLock<bool> = OnceLock::new();
+static PRINT0: OnceLock<bool> = OnceLock::new();
+static ESCAPED: OnceLock<bool> = OnceLock::new();
+static STATS: OnceLock<bool> = OnceLock::new();
+
+// --plain, or a terminal that declares itself dumb: no color, no in-place progress, no
+// banners, and every status line on stderr as a full sentence, for screen readers.
+// --deterministic implies it, since its output is read by other programs.
+pub fn init(matches: &clap::ArgMatches) {
+    let dumb = std::env::var("TERM").is_ok_and(|t| t == "dumb");
+    let deterministic = matches.get_flag("deterministic");
+    let _ = PLAIN.set(matches.get_flag("plain") || dumb || deterministic);
+    let _ = DETERMINISTIC.set(deterministic);
+    let _ = QUIET.set(matches.get_flag("quiet") || matches.get_flag("eval"));
+    let _ = PRINT0.set(matches.get_flag("print0"));
+    let _ = ESCAPED.set(matches.get_flag("escaped"));
+    let _ = STATS.set(matches.get_flag("stats"));
+}
+
+pub fn plain() -> bool {
+    PLAIN.get().copied().unwrap_or(false)
+}
+
+// -q: stdout gets the command and nothing else, for `eval "$(ss -q ...)"`. Warnings
+// and notes about the command are dropped and reasoning goes to stderr; errors and
+// NOT ABLE answers are still reported on stderr.
+pub fn quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
+// Programs that wait for a person at the terminal; as the command in `$(...)` they would
+// hang the calling script.
+const INTERACTIVE: [&str; 16] = [
+    "vi",
+    "vim",
+    "nvim",
+    "nano",
+    "emacs",
+    "pico",
+    "less",
+    "more",
+    "most",
+    "top",
+    "htop",
+    "man",
+    "read",
+    "passwd",
+    "select",
+    "ssh-keygen",
+];
+
+// --eval: why `command` is not safe to embed with `$(ss --eval ...)`, or None when it is.
+// The command must be one line with no control characters and must not stop to ask for
+// input.
+pub fn eval_problem(command: &str) -> Option<String> {
+    let command = command.trim();
+    if command.lines().count() > 1 {
+        return Some("the command spans more than one line".to_string());
+    }
+    if let Some(c) = command.chars().find(|c| c.is_control() && *c != '\t') {
+        return Some(format!(
+            "the command contains a control character ({:?})",
+            c
+        ));
+    }
+    for words in eval_segments(command) {
+        let words = match elevated(skip_prefixes(&words)) {
+            Ok(words) => skip_prefixes(words),
+            Err(why) => return Some(why),
+        };
+        let Some(program) = words.first() else {
+            continue;
+        };
+        let program = program.rsplit('/').next().unwrap_or(program);
+        if INTERACTIVE.contains(&program) {
+            return Some(format!("{} waits for input at the terminal", program));
+        }
+        let asks = words[1..].iter().any(|w| {
+            *w == "--interactive" || (w.starts_with('-') && !w.starts_with("--") && w.contains('i'))
+        });
+        if matches!(program, "rm" | "cp" | "mv") && asks {
+            return Some(format!("{} -i asks before each file", program));
+        }
+    }
+    None
+}
+
+// The simple commands in `command`: split at operators and at subshell and command
+// substitution boundaries, so `echo $(sudo cat f)` is checked as `echo` and `sudo cat f`.
+fn eval_segments(command: &str) -> Vec<Vec<&str>> {
+    command
+        .split(['\n', ';', '|', '&', '(', ')', '`'])
+        .map(|s| {
+            s.split_whitespace()
+                .filter(|w| *w != "$")
+                .collect::<Vec<_>>()
+        })
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+// Past the keywords, wrappers and variable assignments that come before the program.
+fn skip_prefixes<'a, 'b>(mut words: &'b [&'a str]) -> &'b [&'a str] {
+    const PREFIXES: &str = "! { then do else if elif while until time nohup exec command";
+    while let [w, rest @ ..] = words {
+        let assignment = w.split_once('=').is_some_and(|(name, _)| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if !assignment && !PREFIXES.split(' ').any(|p| p == *w) {
+            break;
+        }
+        words = rest;
+    }
+    words
+}
+
+// The command run by a leading sudo or doas, past their options, or why it may stop for
+// a password. Options are parsed the way the programs do, so a `-n` that belongs to the
+// command (`sudo head -n 5 f`) does not count, and `sudo -nu root cmd` does. pkexec has
+// no way to fail instead of asking.
+fn elevated<'a, 'b>(words: &'b [&'a str]) -> Result<&'b [&'a str], String> {
+    // Short options taking a value, and long options (without =) taking the next word.
+    let (short_values, long_values) = match words.first() {
+        Some(&"sudo") => (
+            "CDghprtTuU",
+            "close-from chdir group host prompt role type command-timeout user other-user",
+        ),
+        Some(&"doas") => ("uC", ""),
+        Some(&"pkexec") => return Err("pkexec may ask for authentication".to_string()),
+        _ => return Ok(words),
+    };
+    let mut non_interactive = false;
+    let mut i = 1;
+    while let Some(w) = words.get(i) {
+        if *w == "--" {
+            i += 1;
+            break;
+        }
+        if let Some(long) = w.strip_prefix("--") {
+            if long == "non-interactive" {
+                non_interactive = true;
+            } else if long_values.split(' ').any(|v| v == long) {
+                i += 1;
+            }
+        } else if let Some(flags) = w.strip_prefix('-').filter(|f| !f.is_empty()) {
+            for (j, c) in flags.char_indices() {
+                if c == 'n' {
+                    non_interactive = true;
+                }
+                if short_values.contains(c) {
+                    // The value is the rest of this word, or the next word
+                    if j + 1 == flags.len() {
+                        i += 1;
+                    }
+                    break;
+                }
+            }
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    if !non_interactive {
+        return Err(format!("{} may prompt for a password (use -n)", words[0]));
+    }
+    Ok(&words[i.min(words.len())..])
+}
+
+// --deterministic: for use inside scripts. Nothing is copied to the clipboard and every
+// confirmation is answered with its default (no) instead of waiting for a person.
+pub fn deterministic() -> bool {
+    DETERMINISTIC.get().copied().unwrap_or(false)
+}
+
+// --stats: one line on stderr after each answer saying where it came from, so a
+// configured fallback taking over does not go unnoticed.
+pub fn stats_footer(completion: &Completion, latency: Duration) {
+    if !STATS.get().copied().unwrap_or(false) {
+        return;
+    }
+    let source = match (&completion.model, &completion.provider) {
+        _ if completion.cached => "response cache".to_string(),
+        (Some(model), Some(provider)) => format!("{} via {}", model, provider),
+        (Some(model), None) => model.clone(),
+        (None, _) => "local data".to_string(),
+    };
+    let mut parts = vec![source, format!("{:.2}s", latency.as_secs_f64())];
+    let usage = &completion.usage;
+    if let (Some(p), Some(c)) = (usage.prompt_tokens, usage.completion_tokens) {
+        parts.push(format!("{} prompt + {} completion tokens", p, c));
+    }
+    if let Some(cost) = usage.cost {
+        parts.push(format!("{:.6} credits", cost));
+    }
+    parts.push(
+        if completion.cached {
+            "cache hit"
+        } else {
+            "cache miss"
+        }
+        .to_string(),
+    );
+    eprintln!("Stats: {}", parts.join(", "));
+}
+
+// Print a generated command, copy it to the clipboard, and record it in history.
+// NOT ABLE TO ANSWER responses go to stderr and end the process with EXIT_NOT_ABLE.
+pub fn emit_command(prompt: &str, out: &str, reasoning: Option<JsonValue>) -> Result<()> {
+    emit(prompt, out, reasoning, true)
+}
+
+// Like emit_command, for output that is not a shell command (a SQL query, a jq filter, a
+// commit message, a Dockerfile): printed exactly as generated.
+pub fn emit_text(prompt: &str, out: &str, reasoning: Option<JsonValue>) -> Result<()> {
+    emit(prompt, out, reasoning, false)
+}
+
+fn emit(prompt: &str, out: &str, reasoning: Option<JsonValue>, shell: bool) -> Result<()> {
+    if let Some(reason) = parse_not_able(out) {
+        if let Some(js_val) = reasoning {
+            print_reasoning(js_val);
+        }
+        exit_not_able(&reason);
+    }
+
+    refuse_if_blocked(out);
+    // Only a shell command can hedge, need root, or be misquoted: "might" is fine in a
+    // commit message, and a SQL query naming /etc needs no sudo.
+    if shell {
+        warn_hedges(&detect_hedges(out));
+        lint::warn(out);
+        privilege::warn(out);
+    }
+
+    print_command(out, shell);
+    deliver(out)?;
+    save_history(prompt, out)?;
+
+    if let Some(js_val) = reasoning {
+        print_reasoning(js_val);
+    }
+    Ok(())
+}
+
+// The command on stdout: wrapped to fit a terminal (see term.rs), shell-quoted with
+// --escaped, and ended with a NUL instead of a newline with --print0 (for `xargs -0`).
+// Only stdout changes; the clipboard and history get the command as is. Output that is
+// not shell is never re-spaced.
+fn print_command(command: &str, shell: bool) {
+    let shown = if ESCAPED.get().copied().unwrap_or(false) {
+        shell_quote(command)
+    } else {
+        command.to_string()
+    };
+    if PRINT0.get().copied().unwrap_or(false) {
+        print!("{}\0", shown);
+        let _ = std::io::stdout().flush();
+    } else if ESCAPED.get().copied().unwrap_or(false) || quiet() || !shell {
+        println!("{}", shown);
+    } else {
+        println!("{}", term::display(&shown));
+    }
+}
+
+// --json: one object on stdout instead of the bare command, and nothing on the
+// clipboard. NOT ABLE answers and refusals are reported in the object too, with the
+// usual exit statuses. Only shell command generation has --json, so the shell checks
+// apply.
+pub fn emit_json(prompt: &str, model: &str, out: &str, reasoning: Option<JsonValue>) -> Result<()> {
+    let mut obj = serde_json::json!({"prompt": prompt, "model": model, "command": null});
+    let status = if let Some(reason) = parse_not_able(out) {
+        obj["not_able"] = reason.into();
+        Some(EXIT_NOT_ABLE)
+    } else if let Some(reason) = refusal(out) {
+        obj["refused"] = format!("command {}", reason).into();
+        Some(EXIT_BLOCKED)
+    } else {
+        warn_hedges(&detect_hedges(out));
+        lint::warn(out);
+        privilege::warn(out);
+        obj["command"] = out.into();
+        save_history(prompt, out)?;
+        None
+    };
+    if let Some(r) = reasoning {
+        obj["reasoning"] = r;
+    }
+    println!("{}", obj);
+    if let Some(code) = status {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+// Why a generated command must not be used: the admin policy's blocked patterns are
+// checked first, then the user's deny and allow lists from config.toml.
+pub fn refusal(command: &str) -> Option<String> {
+    if let Some(pattern) = policy().blocked_by(command) {
+        return Some(format!("matches blocked pattern `{}` in policy", pattern));
+    }
+    let rules = command_rules();
+    if let Some(r) = rules.deny.iter().find(|r| r.is_match(command)) {
+        return Some(format!("matches deny pattern `{}`", r.as_str()));
+    }
+    if !rules.allow.is_empty() && !rules.allow.iter().any(|r| r.is_match(command)) {
+        return Some("matches none of the allow patterns".to_string());
+    }
+    None
+}
+
+// Refused commands are shown on stderr under a refusal banner and never printed to
+// stdout, copied, saved, or run.
+pub fn refuse_if_blocked(command: &str) {
+    if let Some(reason) = refusal(command) {
+        if plain() {
+            eprintln!("The command was refused because it {}.", reason);
+            eprintln!("Refused command: {}", command);
+        } else {
+            eprintln!("==== REFUSED: command {} ====", reason);
+            eprintln!("{}", command);
+        }
+        std::process::exit(EXIT_BLOCKED);
+    }
+}
+
+// Uncopyable message: report on stderr, never copy or save it.
+pub fn exit_not_able(reason: &str) -> ! {
+    eprintln!("(NOT ABLE TO ANSWER): {}", reason);
+    std::process::exit(EXIT_NOT_ABLE);
+}
+
+// Hand a finished command to the shell widget when it launched us (see widget.rs),
+// otherwise to the clipboard.
+pub fn deliver(command: &str) -> Result<()> {
+    if !deterministic() {
+        clipring::push(command);
+    }
+    match std::env::var_os(RESULT_FILE_ENV) {
+        Some(path) if !path.is_empty() => std::fs::write(path, command)?,
+        _ => copy_to_clipboard(command),
+    }
+    Ok(())
+}
+
+// pbcopy on macOS and clip.exe under WSL. Over SSH those would reach the remote
+// machine's clipboard, so the command is sent to the user's terminal with OSC 52 instead.
+pub fn copy_to_clipboard(text: &str) {
+    if deterministic() {
+        return;
+    }
+    let runtime = runtime();
+    if runtime.ssh {
+        osc52(text);
+    } else if cfg!(target_os = "macos") {
+        pipe_to("pbcopy", text);
+    } else if runtime.wsl {
+        pipe_to("clip.exe", text);
+    }
+}
+
+fn pipe_to(program: &str, text: &str) {
+    if let Ok(mut child) = std::process::Command::new(program)
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+    {
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        let _ = child.wait();
+    }
+}
+
+// Ask the terminal to set its clipboard. Written to the controlling terminal, never stdout;
+// inside tmux the sequence is wrapped so tmux passes it through.
+fn osc52(text: &str) {
+    let Ok(mut tty) = std::fs::OpenOptions::new().write(true).open("/dev/tty") else {
+        return;
+    };
+    let data = base64::engine::general_purpose::STANDARD.encode(text);
+    let seq = format!("\x1b]52;c;{}\x07", data);
+    let seq = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"))
+    } else {
+        seq
+    };
+    let _ = tty.write_all(seq.as_bytes());
+}
+
+pub fn print_reasoning(js_val: JsonValue) {
+    // Normalize the reasoning output to the canonical form: {"reasoning": "..."}
+    // If the model returned a string, wrap it. If it returned an object that includes
+    // a `reasoning` key, prefer that. Otherwise stringify the object and wrap it.
+    let final_obj = if js_val.is_string() {
+        let s = js_val.as_str().unwrap_or_default();
+        serde_json::json!({"reasoning": s})
+    } else if js_val.is_object() {
+        // If it already contains a `reasoning` key, use as-is
+        if js_val.get("reasoning").is_some() {
+            js_val
+        } else {
+            // Fallback: stringify the object and place under `reasoning`
+            let s = serde_json::to_string(&js_val).unwrap_or_else(|_| js_val.to_string());
+            serde_json::json!({"reasoning": s})
+        }
+    } else {
+        // Other types (numbers, arrays, etc.) - stringify and wrap
+        let s = serde_json::to_string(&js_val).unwrap_or_else(|_| js_val.to_string());
+        serde_json::json!({"reasoning": s})
+    };
+
+    // Print compact single-line JSON to match README examples
+    let line = serde_json::to_string(&final_obj).unwrap_or_else(|_| final_obj.to_string());
+    if quiet() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+pub fn is_not_able_response(s: &str) -> bool {
+    parse_not_able(s).is_some()
+}
+
+// Extract the reason from `(NOT ABLE TO ANSWER): <reason>`; None for any other response.
+pub fn parse_not_able(s: &str) -> Option<String> {
+    const PREFIX: &str = "(not able to answer):";
+    let s = s.trim();
+    // Case-insensitive check for the prefix
+    let head = s.get(..PREFIX.len())?;
+    if !head.eq_ignore_ascii_case(PREFIX) {
+        return None;
+    }
+    let reason = s[PREFIX.len()..].trim();
+    if reason.is_empty() {
+        return None;
+    }
+    Some(reason.to_string())
+}
+
+// Write generated content to `path`, refusing to clobber an existing file unless `force`.
+pub fn write_output_file(path: &Path, content: &str, force: bool, executable: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!(
+            "{} already exists; pass --force to overwrite",
+            path.display()
+        );
+    }
+    let mut text = content.to_string();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    std::fs::write(path, text)?;
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+// Bracketed paste for the line-based prompts (chat, --loop). With it on, the terminal
+// wraps pasted text in ESC[200~ ... ESC[201~, so a pasted log or config arrives as one
+// message instead of one message per line, and is only sent when Enter is pressed
+// after it.
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+use crate::output::plain;
+
+const START: &str = "\x1b[200~";
+const END: &str = "\x1b[201~";
+
+// Whether the terminal currently has bracketed paste on.
+static ON: AtomicBool = AtomicBool::new(false);
+
+// Bracketed paste stays on while this is alive. Callers hold it only while reading a
+// line, so a `process::exit` elsewhere never leaves the shell with paste mode on.
+pub struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        disable();
+    }
+}
+
+// Turn bracketed paste on when both ends are a terminal that can take escape codes.
+pub fn enable() -> Option<Guard> {
+    if plain() || !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return None;
+    }
+    watch_interrupt();
+    print!("\x1b[?2004h");
+    let _ = io::stdout().flush();
+    ON.store(true, Ordering::SeqCst);
+    Some(Guard)
+}
+
+// Turn bracketed paste off if it is on.
+pub fn disable() {
+    if ON.swap(false, Ordering::SeqCst) {
+        print!("\x1b[?2004l");
+        let _ = io::stdout().flush();
+    }
+}
+
+// Ctrl-C at a prompt kills the process before any guard drops. Once paste mode has been
+// used, SIGINT is handled here instead: paste is turned off and the process exits the
+// way the default handler would have (130).
+fn watch_interrupt() {
+    static WATCHING: Once = Once::new();
+    if tokio::runtime::Handle::try_current().is_err() {
+        return;
+    }
+    WATCHING.call_once(|| {
+        tokio::spawn(async {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                disable();
+                std::process::exit(130);
+            }
+        });
+    });
+}
+
+// `first` plus, when it starts a paste, every line up to the one that ends it, read with
+// `next_line`. Returns the text without the markers and whether it was pasted.
+pub fn collect(first: String, mut next_line: impl FnMut() -> Option<String>) -> (String, bool) {
+    if !first.contains(START) {
+        return (first.replace(END, ""), false);
+    }
+    let mut lines = vec![first];
+    while !lines.last().is_some_and(|l| l.contains(END)) {
+        match next_line() {
+            Some(line) => lines.push(line),
+            None => break,
+        }
+    }
+    let text = lines.join("\n").replace(START, "").replace(END, "");
+    (text, true)
+}
@@ -0,0 +1,212 @@
+// Check that files a generated command reads actually exist in the current directory.
+// Only relative paths given to commands that read them are checked; output targets and
+// anything with globs or expansions are left alone.
+use regex::Regex;
+use std::path::Path;
+
+// Commands whose non-flag arguments are all inputs.
+const READERS: [&str; 24] = [
+    "cat",
+    "less",
+    "more",
+    "head",
+    "tail",
+    "wc",
+    "sort",
+    "uniq",
+    "cut",
+    "file",
+    "stat",
+    "du",
+    "ls",
+    "cd",
+    "diff",
+    "cmp",
+    "bat",
+    "xxd",
+    "hexdump",
+    "md5sum",
+    "sha256sum",
+    "zcat",
+    "source",
+    ".",
+];
+// Commands whose first non-flag argument is a pattern, filter, or script, not a path.
+const PATTERN_FIRST: [&str; 9] = [
+    "grep", "egrep", "fgrep", "rg", "ag", "awk", "sed", "jq", "chmod",
+];
+// Commands whose last non-flag argument is a destination that may not exist yet.
+const COPIERS: [&str; 4] = ["cp", "mv", "rsync", "ln"];
+// Interpreters whose first non-flag argument is a script file.
+const INTERPRETERS: [&str; 8] = [
+    "python", "python3", "node", "ruby", "perl", "bash", "sh", "zsh",
+];
+
+pub struct PathIssue {
+    pub path: String,
+    pub suggestion: Option<String>,
+}
+
+// Split one command segment into words, honoring simple single and double quotes.
+fn words(segment: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut quote: Option<char> = None;
+    for c in segment.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => cur.push(c),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, c) if c.is_whitespace() => {
+                if !cur.is_empty() {
+                    out.push(std::mem::take(&mut cur));
+                }
+            }
+            (None, c) => cur.push(c),
+        }
+    }
+    if !cur.is_empty() {
+        out.push(cur);
+    }
+    out
+}
+
+fn looks_like_path(word: &str) -> bool {
+    if word.starts_with(['/', '~', '-'])
+        || word.contains(['$', '*', '?', '[', '{', '=', '<', '>', '`'])
+        || word.parse::<f64>().is_ok()
+    {
+        return false;
+    }
+    word.contains('/') || word.trim_start_matches('.').contains('.') || word.starts_with('.')
+}
+
+// Relative paths the command reads.
+fn read_paths(command: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    for segment in command.split(['\n', ';', '|', '&']) {
+        let words = words(segment);
+        let Some(program) = words.first() else {
+            continue;
+        };
+        let program = program.rsplit('/').next().unwrap_or(program);
+        let args: Vec<&String> = words[1..]
+            .iter()
+            .take_while(|w| !w.starts_with('>'))
+            .filter(|w| !w.starts_with('-'))
+            .collect();
+        let inputs: &[&String] = if READERS.contains(&program) {
+            &args
+        } else if PATTERN_FIRST.contains(&program) {
+            args.get(1..).unwrap_or_default()
+        } else if COPIERS.contains(&program) {
+            &args[..args.len().saturating_sub(1)]
+        } else if INTERPRETERS.contains(&program) {
+            args.get(..1).unwrap_or_default()
+        } else {
+            &[]
+        };
+        paths.extend(
+            inputs
+                .iter()
+                .filter(|w| looks_like_path(w))
+                .map(|w| w.to_string()),
+        );
+    }
+    paths
+}
+
+// Edit distance between two strings, by characters, counting an adjacent
+// transposition as one edit (optimal string alignment).
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+// The closest existing sibling of a missing path, if one is a plausible typo of it.
+fn near_miss(path: &str) -> Option<String> {
+    let p = Path::new(path);
+    let name = p.file_name()?.to_str()?;
+    let parent = p.parent().filter(|d| !d.as_os_str().is_empty());
+    let dir = parent.unwrap_or(Path::new("."));
+    let limit = (name.chars().count() / 4).max(1);
+    let best = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+        .map(|candidate| {
+            let d = if candidate.eq_ignore_ascii_case(name) {
+                0
+            } else {
+                edit_distance(name, &candidate)
+            };
+            (d, candidate)
+        })
+        .filter(|(d, _)| *d <= limit)
+        .min()?
+        .1;
+    Some(match parent {
+        Some(d) => d.join(best).to_string_lossy().into_owned(),
+        None => best,
+    })
+}
+
+pub fn check(command: &str) -> Vec<PathIssue> {
+    let mut issues: Vec<PathIssue> = Vec::new();
+    for path in read_paths(command) {
+        if Path::new(&path).exists() || issues.iter().any(|i| i.path == path) {
+            continue;
+        }
+        issues.push(PathIssue {
+            suggestion: near_miss(&path),
+            path,
+        });
+    }
+    issues
+}
+
+// Report missing paths on stderr; with `fix`, swap in near-miss matches and return the
+// corrected command.
+pub fn review(command: &str, fix: bool) -> String {
+    let mut out = command.to_string();
+    for issue in check(command) {
+        match &issue.suggestion {
+            Some(s) if fix => {
+                eprintln!("Corrected path: `{}` -> `{}`", issue.path, s);
+                // Only whole words, so `note.txt` does not rewrite `notes.txt`.
+                let word = Regex::new(&format!(
+                    r#"(^|[\s'"=]){}([\s'";|&)]|$)"#,
+                    regex::escape(&issue.path)
+                ))
+                .expect("escaped path is a valid regex");
+                out = word
+                    .replace_all(&out, |c: &regex::Captures| {
+                        format!("{}{}{}", &c[1], s, &c[2])
+                    })
+                    .into_owned();
+            }
+            Some(s) => eprintln!(
+                "Warning: `{}` does not exist here; did you mean `{}`?",
+                issue.path, s
+            ),
+            None => eprintln!("Warning: `{}` does not exist here.", issue.path),
+        }
+    }
+    out
+}
@@ -0,0 +1,80 @@
+use directories::ProjectDirs;
+use std::path::PathBuf;
+
+pub fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("com", "snapshell", "snapshell")
+}
+
+// Regenerable data (cheat sheets, response caches) lives under the OS cache dir.
+pub fn cache_dir() -> Option<PathBuf> {
+    project_dirs().map(|d| d.cache_dir().to_path_buf())
+}
+
+// User-authored settings (snippets, ...) live under the OS config dir.
+pub fn config_dir() -> Option<PathBuf> {
+    project_dirs().map(|d| d.config_dir().to_path_buf())
+}
+
+// Records snapshell writes as it runs (history, the execution and audit logs, chat
+// sessions). XDG calls this state: $XDG_STATE_HOME, ~/.local/state on Linux. Other
+// platforms have no such dir and use the local data dir.
+pub fn state_dir() -> Option<PathBuf> {
+    project_dirs().map(|d| {
+        d.state_dir()
+            .unwrap_or_else(|| d.data_local_dir())
+            .to_path_buf()
+    })
+}
+
+// Files that earlier versions kept in the data dir on every platform.
+const STATE_FILES: [&str; 5] = [
+    "history.jsonl",
+    "history.jsonl.bak",
+    "history.jsonl.corrupt",
+    "executions.jsonl",
+    "audit.jsonl",
+];
+
+// Move state written by earlier versions from the data dir to the state dir, once: an
+// entry is only moved when the state dir does not have it yet, so later runs find
+// nothing to do.
+pub fn migrate() {
+    let Some(dirs) = project_dirs() else {
+        return;
+    };
+    let Some(new) = dirs.state_dir() else {
+        return;
+    };
+    let old = dirs.data_local_dir();
+    for name in STATE_FILES.iter().copied().chain(["chats"]) {
+        let from = old.join(name);
+        let to = new.join(name);
+        if !from.exists() || to.exists() {
+            continue;
+        }
+        let moved = std::fs::create_dir_all(new)
+            .and_then(|_| std::fs::rename(&from, &to))
+            .or_else(|e| {
+                // A state dir on another filesystem cannot take a rename; copy the file.
+                if from.is_dir() {
+                    return Err(e);
+                }
+                std::fs::copy(&from, &to).and_then(|_| std::fs::remove_file(&from))
+            });
+        match moved {
+            Ok(()) => eprintln!("Moved {} to {}.", from.display(), to.display()),
+            Err(e) => eprintln!(
+                "Could not move {} to {}: {}; move it by hand to keep it.",
+                from.display(),
+                to.display(),
+                e
+            ),
+        }
+    }
+}
+
+// Man pages go to ~/.local/share/man/man1, which man(1) searches on Linux and macOS when
+// ~/.local/bin is on PATH.
+pub fn man_dir() -> Option<PathBuf> {
+    directories::BaseDirs::new().map(|d| d.home_dir().join(".local/share/man/man1"))
+}
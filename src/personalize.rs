@@ -0,0 +1,170 @@
+// `personalize = true`: a short digest of the user's own history ("prefers rg over grep,
+// uses pnpm, works with kubectl a lot") is added to the system prompt, so commands lean
+// toward the tools they actually use. The digest is written by the model from recent
+// history and rewritten once it is a week old or 50 new entries have come in.
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::config::config;
+use crate::env::truncate;
+use crate::history::load_history;
+use crate::llm::{complete, Settings};
+use crate::output::quiet;
+use crate::paths::state_dir;
+
+// History entries the digest is written from.
+const RECENT: usize = 200;
+// Age and number of new history entries after which the digest is rewritten.
+const MAX_AGE_DAYS: i64 = 7;
+const MAX_NEW_ENTRIES: usize = 50;
+// After a failed rewrite, runs go without one for this long instead of each paying for
+// another request.
+const RETRY_AFTER_HOURS: i64 = 6;
+const MAX_DIGEST_BYTES: usize = 400;
+
+const DIGEST_SYSTEM: &str = "You summarize a user's shell habits from the commands they had generated. Reply with ONE sentence of at most 40 words naming the tools, package managers, languages, and platforms they prefer or use most, e.g. \"Prefers rg over grep and fd over find, uses pnpm, works with kubectl and terraform a lot.\" Mention only what the commands show. No preamble.";
+
+#[derive(Serialize, Deserialize)]
+pub struct Digest {
+    pub generated: String,
+    // History entries at the time it was written
+    pub entries: usize,
+    pub summary: String,
+}
+
+static NOTE: OnceLock<String> = OnceLock::new();
+
+fn digest_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("digest.json"))
+}
+
+pub fn load() -> Option<Digest> {
+    let text = std::fs::read_to_string(digest_path()?).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+// When the last rewrite failed, kept apart from the digest so an old digest stays usable.
+fn failed_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("digest-failed"))
+}
+
+fn recently_failed() -> bool {
+    failed_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|t| DateTime::parse_from_rfc3339(t.trim()).ok())
+        .is_some_and(|t| Utc::now().signed_duration_since(t).num_hours() < RETRY_AFTER_HOURS)
+}
+
+fn record_failure() {
+    if let Some(path) = failed_path() {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(path, Utc::now().to_rfc3339());
+    }
+}
+
+fn save(digest: &Digest) -> Result<()> {
+    let Some(path) = digest_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(digest)?)?;
+    Ok(())
+}
+
+pub fn clear() -> Result<()> {
+    if let Some(path) = failed_path() {
+        let _ = std::fs::remove_file(path);
+    }
+    match digest_path() {
+        Some(path) if path.exists() => Ok(std::fs::remove_file(path)?),
+        _ => Ok(()),
+    }
+}
+
+fn is_stale(digest: &Digest, entries: usize) -> bool {
+    let age = DateTime::parse_from_rfc3339(&digest.generated)
+        .map(|t| Utc::now().signed_duration_since(t).num_days())
+        .unwrap_or(i64::MAX);
+    age >= MAX_AGE_DAYS || entries >= digest.entries + MAX_NEW_ENTRIES
+}
+
+// Ask the model for a new digest of the most recent history. None with no history or
+// when the request fails.
+pub async fn regenerate(settings: &Settings) -> Option<Digest> {
+    let history = load_history().ok()?;
+    if history.is_empty() {
+        return None;
+    }
+    let commands: Vec<&str> = history
+        .iter()
+        .rev()
+        .take(RECENT)
+        .map(|e| e.command.trim())
+        .filter(|c| !c.is_empty() && !c.starts_with("(NOT ABLE TO ANSWER)"))
+        .collect();
+    let mut cheap = settings.clone();
+    cheap.effort = "low".to_string();
+    cheap.retry_not_able = false;
+    cheap.tools.clear();
+    let messages: Vec<JsonValue> = vec![
+        serde_json::json!({"role": "system", "content": DIGEST_SYSTEM}),
+        serde_json::json!({"role": "user", "content": commands.join("\n")}),
+    ];
+    let c = complete(&cheap, &messages).await.ok()?;
+    let summary = c.content.trim();
+    if summary.is_empty() || summary.starts_with('(') {
+        return None;
+    }
+    let digest = Digest {
+        generated: Utc::now().to_rfc3339(),
+        entries: history.len(),
+        summary: truncate(summary, MAX_DIGEST_BYTES).to_string(),
+    };
+    let _ = save(&digest);
+    if let Some(path) = failed_path() {
+        let _ = std::fs::remove_file(path);
+    }
+    Some(digest)
+}
+
+// Load the digest for this run, rewriting it first when it is stale. Off unless
+// `personalize` is set, and with --deterministic, whose output must not depend on history.
+pub async fn init(settings: &Settings) {
+    if !config().personalize || settings.deterministic {
+        return;
+    }
+    let entries = load_history().map(|h| h.len()).unwrap_or(0);
+    let mut digest = load();
+    if !settings.offline
+        && entries > 0
+        && digest.as_ref().is_none_or(|d| is_stale(d, entries))
+        && !recently_failed()
+    {
+        if !quiet() {
+            eprintln!("Updating the digest of your history...");
+        }
+        match regenerate(settings).await {
+            Some(d) => digest = Some(d),
+            None => record_failure(),
+        }
+    }
+    if let Some(d) = digest {
+        let _ = NOTE.set(format!(
+            " From the user's past requests: {}. Prefer these tools when they fit.",
+            d.summary.trim_end_matches('.')
+        ));
+    }
+}
+
+// Appended to the environment note; empty unless personalization is on.
+pub fn note() -> &'static str {
+    NOTE.get().map(|s| s.as_str()).unwrap_or("")
+}
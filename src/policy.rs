@@ -0,0 +1,178 @@
+// System-wide policy set by administrators in /etc/snapshell/policy.toml. Nothing a
+// user passes on the command line or in their environment can relax it, and a policy
+// file that cannot be read or parsed stops snapshell rather than being ignored.
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    // Model ids, with an optional trailing `*` (e.g. "openai/*")
+    allowed_models: Option<Vec<String>>,
+    // Upstream providers OpenRouter may route to (sent as provider.only)
+    allowed_providers: Option<Vec<String>>,
+    // Full API URLs requests may be sent to
+    allowed_endpoints: Option<Vec<String>>,
+    #[serde(default)]
+    disable_exec: bool,
+    #[serde(default)]
+    force_redaction: bool,
+    // Keep installs on the version the administrator deployed
+    #[serde(default)]
+    disable_self_update: bool,
+    // Regexes; matching generated commands are refused
+    #[serde(default)]
+    blocked_commands: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Policy {
+    pub path: Option<PathBuf>,
+    pub allowed_models: Option<Vec<String>>,
+    pub allowed_providers: Option<Vec<String>>,
+    pub allowed_endpoints: Option<Vec<String>>,
+    pub disable_exec: bool,
+    pub force_redaction: bool,
+    pub disable_self_update: bool,
+    pub blocked_commands: Vec<Regex>,
+}
+
+pub fn policy_path() -> PathBuf {
+    if cfg!(windows) {
+        let base = std::env::var("ProgramData").unwrap_or_else(|_| "C:\\ProgramData".to_string());
+        PathBuf::from(base).join("snapshell").join("policy.toml")
+    } else {
+        PathBuf::from("/etc/snapshell/policy.toml")
+    }
+}
+
+fn load() -> Result<Policy> {
+    let path = policy_path();
+    if !path.exists() {
+        return Ok(Policy::default());
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("cannot read policy file {}", path.display()))?;
+    let file: PolicyFile =
+        toml::from_str(&text).with_context(|| format!("invalid policy file {}", path.display()))?;
+    let blocked_commands = file
+        .blocked_commands
+        .iter()
+        .map(|p| {
+            Regex::new(p).with_context(|| {
+                format!(
+                    "invalid blocked_commands pattern `{}` in {}",
+                    p,
+                    path.display()
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Policy {
+        path: Some(path),
+        allowed_models: file.allowed_models,
+        allowed_providers: file.allowed_providers,
+        allowed_endpoints: file.allowed_endpoints,
+        disable_exec: file.disable_exec,
+        force_redaction: file.force_redaction,
+        disable_self_update: file.disable_self_update,
+        blocked_commands,
+    })
+}
+
+// Parse the policy without exiting, for `ss doctor`.
+pub fn check() -> Result<()> {
+    load().map(|_| ())
+}
+
+// Loaded once per process; a broken policy is fatal.
+pub fn policy() -> &'static Policy {
+    static POLICY: OnceLock<Policy> = OnceLock::new();
+    POLICY.get_or_init(|| {
+        load().unwrap_or_else(|e| {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        })
+    })
+}
+
+fn matches_pattern(value: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => value == pattern,
+    }
+}
+
+impl Policy {
+    pub fn check_endpoint(&self, endpoint: &str) -> Result<()> {
+        if let Some(allowed) = &self.allowed_endpoints {
+            if !allowed.iter().any(|e| e == endpoint) {
+                bail!("endpoint {} is not allowed by {}", endpoint, self.source());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_request(&self, endpoint: &str, model: &str) -> Result<()> {
+        self.check_endpoint(endpoint)?;
+        if let Some(allowed) = &self.allowed_models {
+            if !allowed.iter().any(|p| matches_pattern(model, p)) {
+                bail!(
+                    "model {} is not allowed by {} (allowed: {})",
+                    model,
+                    self.source(),
+                    allowed.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_provider(&self, provider: Option<&str>) -> Result<()> {
+        if let (Some(allowed), Some(p)) = (&self.allowed_providers, provider) {
+            if !allowed.iter().any(|a| a.eq_ignore_ascii_case(p)) {
+                bail!(
+                    "response served by provider {}, which {} does not allow",
+                    p,
+                    self.source()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_exec(&self) -> Result<()> {
+        if self.disable_exec {
+            bail!(
+                "running generated commands is disabled by {}",
+                self.source()
+            );
+        }
+        Ok(())
+    }
+
+    pub fn check_self_update(&self) -> Result<()> {
+        if self.disable_self_update {
+            bail!("self-update is disabled by {}", self.source());
+        }
+        Ok(())
+    }
+
+    // The first blocked pattern the command matches.
+    pub fn blocked_by(&self, command: &str) -> Option<&str> {
+        self.blocked_commands
+            .iter()
+            .find(|r| r.is_match(command))
+            .map(|r| r.as_str())
+    }
+
+    fn source(&self) -> String {
+        self.path
+            .as_ref()
+            .map(|p| format!("policy {}", p.display()))
+            .unwrap_or_else(|| "policy".to_string())
+    }
+}
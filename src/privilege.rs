@@ -0,0 +1,181 @@
+// Heuristics for spotting generated commands that need root: package installs, writes
+// under system directories, system service management, and explicit sudo.
+use crate::modes::capture;
+use crate::output::quiet;
+
+const PACKAGE_MANAGERS: [&str; 8] = [
+    "apt", "apt-get", "dnf", "yum", "zypper", "pacman", "apk", "snap",
+];
+const PACKAGE_ACTIONS: [&str; 11] = [
+    "install",
+    "remove",
+    "purge",
+    "upgrade",
+    "update",
+    "autoremove",
+    "reinstall",
+    "add",
+    "del",
+    "refresh",
+    "dist-upgrade",
+];
+// systemctl subcommands that only read state.
+const SYSTEMCTL_READ: [&str; 9] = [
+    "status",
+    "show",
+    "cat",
+    "list-units",
+    "list-unit-files",
+    "list-timers",
+    "is-active",
+    "is-enabled",
+    "is-failed",
+];
+const SYSTEM_DIRS: [&str; 5] = ["/etc/", "/usr/", "/boot/", "/lib/", "/opt/"];
+// Programs that write every path they are given, and ones that only write the last
+// (or the one after -t); a source under /etc is only read.
+const WRITERS: [&str; 8] = [
+    "mv", "rm", "mkdir", "touch", "chmod", "chown", "tee", "truncate",
+];
+const COPIERS: [&str; 4] = ["cp", "ln", "install", "rsync"];
+const ADMIN_TOOLS: [&str; 12] = [
+    "mount",
+    "umount",
+    "useradd",
+    "usermod",
+    "userdel",
+    "groupadd",
+    "modprobe",
+    "iptables",
+    "nft",
+    "ufw",
+    "update-alternatives",
+    "visudo",
+];
+
+pub fn segments(command: &str) -> Vec<Vec<&str>> {
+    command
+        .split(['\n', ';', '|', '&'])
+        .map(|s| s.split_whitespace().collect::<Vec<_>>())
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+fn is_sudo(word: &str) -> bool {
+    matches!(word, "sudo" | "doas" | "pkexec")
+}
+
+pub fn uses_sudo(command: &str) -> bool {
+    segments(command)
+        .iter()
+        .any(|words| words.iter().any(|w| is_sudo(w)))
+}
+
+// Why the command needs elevated privileges, if it looks like it does.
+pub fn requirement(command: &str) -> Option<&'static str> {
+    if cfg!(windows) {
+        return None;
+    }
+    if uses_sudo(command) {
+        return Some("it runs sudo");
+    }
+    // Redirections into system directories, wherever they appear.
+    let words: Vec<&str> = command.split_whitespace().collect();
+    for (i, w) in words.iter().enumerate() {
+        let Some(pos) = w.find('>') else {
+            continue;
+        };
+        if !w[..pos].chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let rest = w[pos..].trim_start_matches('>');
+        let target = if rest.is_empty() {
+            words.get(i + 1).copied().unwrap_or_default()
+        } else {
+            rest
+        };
+        if SYSTEM_DIRS.iter().any(|d| target.starts_with(d)) {
+            return Some("it writes under a system directory");
+        }
+    }
+    for words in segments(command) {
+        // Skip leading VAR=value assignments.
+        let words: Vec<&str> = words
+            .into_iter()
+            .skip_while(|w| w.contains('=') && !w.starts_with('-'))
+            .collect();
+        let Some(&program) = words.first() else {
+            continue;
+        };
+        let program = program.rsplit('/').next().unwrap_or(program);
+        let args = &words[1..];
+        if PACKAGE_MANAGERS.contains(&program)
+            && args.iter().any(|a| {
+                PACKAGE_ACTIONS.contains(a)
+                    || (program == "pacman"
+                        && a.starts_with(['-'])
+                        && a[1..].starts_with(['S', 'R', 'U']))
+            })
+        {
+            return Some("it installs or removes system packages");
+        }
+        if program == "systemctl"
+            && !args.contains(&"--user")
+            && !args.iter().any(|a| SYSTEMCTL_READ.contains(a))
+        {
+            return Some("it manages system services");
+        }
+        let written: Vec<&str> = if COPIERS.contains(&program) {
+            destination(args).into_iter().collect()
+        } else if WRITERS.contains(&program) || (program == "sed" && args.contains(&"-i")) {
+            args.iter()
+                .copied()
+                .filter(|a| !a.starts_with('-'))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if written
+            .iter()
+            .any(|a| SYSTEM_DIRS.iter().any(|d| a.starts_with(d)))
+        {
+            return Some("it writes under a system directory");
+        }
+        if ADMIN_TOOLS.contains(&program) || (program == "sysctl" && args.contains(&"-w")) {
+            return Some("it changes system configuration");
+        }
+    }
+    None
+}
+
+// Where cp, ln, install, or rsync write: the -t directory, else the last operand.
+fn destination<'a>(args: &[&'a str]) -> Option<&'a str> {
+    for (i, a) in args.iter().enumerate() {
+        if *a == "-t" || *a == "--target-directory" {
+            return args.get(i + 1).copied();
+        }
+        if let Some(dir) = a.strip_prefix("--target-directory=") {
+            return Some(dir);
+        }
+    }
+    args.iter().rev().copied().find(|a| !a.starts_with('-'))
+}
+
+pub fn is_root() -> bool {
+    capture("id", &["-u"]).as_deref() == Some("0")
+}
+
+// Print a note on stderr when the command needs root.
+pub fn warn(command: &str) {
+    let Some(reason) = requirement(command).filter(|_| !quiet()) else {
+        return;
+    };
+    if uses_sudo(command) {
+        eprintln!("Note: this command uses sudo and runs with elevated privileges.");
+    } else if !is_root() {
+        eprintln!(
+            "Note: this command probably needs root ({}); run it with sudo.",
+            reason
+        );
+    }
+}
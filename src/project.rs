@@ -0,0 +1,226 @@
+use clap::ArgMatches;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::env::truncate;
+use crate::ignore::Ignore;
+use crate::modes::capture;
+
+// Manifests that identify a project's toolchain, and the label reported for each.
+const MANIFESTS: [(&str, &str); 12] = [
+    ("Cargo.toml", "rust (cargo)"),
+    ("package.json", "node"),
+    ("go.mod", "go"),
+    ("pyproject.toml", "python"),
+    ("requirements.txt", "python (pip)"),
+    ("setup.py", "python (setuptools)"),
+    ("Gemfile", "ruby"),
+    ("pom.xml", "java (maven)"),
+    ("build.gradle", "java (gradle)"),
+    ("build.gradle.kts", "kotlin (gradle)"),
+    ("composer.json", "php"),
+    ("mix.exs", "elixir"),
+];
+
+// Files whose presence alone says something useful (lockfiles pick the package manager).
+const MARKERS: [&str; 13] = [
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "Cargo.lock",
+    "go.sum",
+    "poetry.lock",
+    "uv.lock",
+    "Pipfile.lock",
+    "Dockerfile",
+    "docker-compose.yml",
+    "compose.yaml",
+    "Makefile",
+];
+
+// Config files that tell how tests, linting, and builds are run here.
+const CONFIG_FILES: [&str; 14] = [
+    "justfile",
+    "Taskfile.yml",
+    "tox.ini",
+    "pytest.ini",
+    "noxfile.py",
+    "tsconfig.json",
+    "jest.config.js",
+    "vitest.config.ts",
+    ".nvmrc",
+    "rust-toolchain.toml",
+    ".pre-commit-config.yaml",
+    ".env",
+    "pnpm-workspace.yaml",
+    ".github/workflows",
+];
+// Script and target names listed in the summary, per source.
+const MAX_NAMES: usize = 12;
+// The whole --auto-context summary stays under this.
+const MAX_SUMMARY_BYTES: usize = 800;
+
+static SUMMARY: OnceLock<String> = OnceLock::new();
+
+pub struct Manifest {
+    pub file: &'static str,
+    pub kind: &'static str,
+    pub content: String,
+}
+
+pub struct Project {
+    pub manifests: Vec<Manifest>,
+    pub markers: Vec<&'static str>,
+}
+
+// Files that .gitignore or .snapshellignore exclude are left out, contents and names.
+pub fn detect(dir: &Path, max_manifest_bytes: usize) -> Project {
+    let ignore = Ignore::for_dir(dir);
+    let manifests = MANIFESTS
+        .iter()
+        .filter(|(file, _)| !ignore.is_ignored(&dir.join(file), false))
+        .filter_map(|(file, kind)| {
+            let content = std::fs::read_to_string(dir.join(file)).ok()?;
+            Some(Manifest {
+                file,
+                kind,
+                content: truncate(&content, max_manifest_bytes).to_string(),
+            })
+        })
+        .collect();
+    let markers = MARKERS
+        .iter()
+        .copied()
+        .filter(|m| dir.join(m).exists() && !ignore.is_ignored(&dir.join(m), false))
+        .collect();
+    Project { manifests, markers }
+}
+
+impl Project {
+    pub fn is_empty(&self) -> bool {
+        self.manifests.is_empty() && self.markers.is_empty()
+    }
+
+    // Kinds and other files on one line each, then the (trimmed) manifest contents.
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        if !self.manifests.is_empty() {
+            let kinds: Vec<&str> = self.manifests.iter().map(|m| m.kind).collect();
+            out.push_str(&format!("project type: {}\n", kinds.join(", ")));
+        }
+        if !self.markers.is_empty() {
+            out.push_str(&format!(
+                "other files present: {}\n",
+                self.markers.join(", ")
+            ));
+        }
+        for m in &self.manifests {
+            out.push_str(&format!("--- {} ---\n{}\n", m.file, m.content.trim_end()));
+        }
+        out
+    }
+}
+
+// --auto-context: summarize the working directory once, for every system prompt built
+// later, so "run the tests" becomes the right command for this project.
+pub fn init(matches: &ArgMatches) {
+    if !matches.get_flag("auto-context") {
+        return;
+    }
+    let Ok(dir) = std::env::current_dir() else {
+        return;
+    };
+    let summary = summary(&dir);
+    let _ = SUMMARY.set(format!(
+        " The user is in this working directory: {}.",
+        truncate(&summary, MAX_SUMMARY_BYTES)
+    ));
+}
+
+// Appended to the environment note; empty without --auto-context.
+pub fn note() -> &'static str {
+    SUMMARY.get().map(|s| s.as_str()).unwrap_or("")
+}
+
+// One line: directory name, project kinds, notable files, scripts and make targets, and
+// version control state.
+pub fn summary(dir: &Path) -> String {
+    let project = detect(dir, 0);
+    let ignore = Ignore::for_dir(dir);
+    let name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.display().to_string());
+    let mut parts = vec![name];
+    if !project.manifests.is_empty() {
+        let kinds: Vec<&str> = project.manifests.iter().map(|m| m.kind).collect();
+        parts.push(format!("project type {}", kinds.join(", ")));
+    }
+    let files: Vec<&str> = project
+        .markers
+        .iter()
+        .copied()
+        .chain(CONFIG_FILES.iter().copied().filter(|f| {
+            let path = dir.join(f);
+            path.exists() && !ignore.is_ignored(&path, path.is_dir())
+        }))
+        .collect();
+    if !files.is_empty() {
+        parts.push(format!("files {}", files.join(", ")));
+    }
+    if let Some(scripts) = package_scripts(dir) {
+        parts.push(format!("npm scripts {}", scripts.join(", ")));
+    }
+    if let Some(targets) = make_targets(dir) {
+        parts.push(format!("make targets {}", targets.join(", ")));
+    }
+    parts.push(vcs_state(dir));
+    parts.join("; ")
+}
+
+fn package_scripts(dir: &Path) -> Option<Vec<String>> {
+    let text = std::fs::read_to_string(dir.join("package.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&text).ok()?;
+    let scripts: Vec<String> = json["scripts"]
+        .as_object()?
+        .keys()
+        .take(MAX_NAMES)
+        .cloned()
+        .collect();
+    (!scripts.is_empty()).then_some(scripts)
+}
+
+// Explicit targets of a Makefile; pattern rules, special targets, and variables are skipped.
+fn make_targets(dir: &Path) -> Option<Vec<String>> {
+    let text = std::fs::read_to_string(dir.join("Makefile")).ok()?;
+    let targets: Vec<String> = text
+        .lines()
+        .filter(|l| !l.starts_with(['\t', ' ', '.', '#']))
+        .filter_map(|l| {
+            let (target, rest) = l.split_once(':')?;
+            let ok = !rest.starts_with('=')
+                && !target.is_empty()
+                && target
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | '.'));
+            ok.then(|| target.to_string())
+        })
+        .take(MAX_NAMES)
+        .collect();
+    (!targets.is_empty()).then_some(targets)
+}
+
+fn vcs_state(dir: &Path) -> String {
+    let dir = dir.to_string_lossy();
+    let Some(branch) = capture("git", &["-C", &dir, "rev-parse", "--abbrev-ref", "HEAD"]) else {
+        return "not a git repository".to_string();
+    };
+    let changed = capture("git", &["-C", &dir, "status", "--porcelain"])
+        .map(|s| s.lines().count())
+        .unwrap_or(0);
+    match changed {
+        0 => format!("git branch {}, clean", branch),
+        n => format!("git branch {}, {} changed files", branch, n),
+    }
+}
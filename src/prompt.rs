@@ -0,0 +1,172 @@
+use clap::ArgMatches;
+use serde_json::Value as JsonValue;
+use std::sync::OnceLock;
+
+use crate::config::config;
+use crate::env::{env_note, language_note};
+use crate::output::plain;
+use crate::registry::registry;
+
+// Shared tail of every strict instruction so all modes fail the same, machine-detectable way.
+pub const NOT_ABLE_CLAUSE: &str = "If you do NOT know the correct answer, respond exactly with the following format and nothing else: (NOT ABLE TO ANSWER): <one-sentence reason> — the reason should be a single short sentence explaining why the answer cannot be provided.";
+
+const DEFAULT_SINGLE: &str = "You are a strict shell command generator. OUTPUT ONLY shell commands or shell syntax in plain text with no explanations, no commentary, and no additional prose. DO NOT output any markdown, code fences, backticks, or formatting of any kind. The entire response MUST be a single-line shell command with no extra text. Never add numbering, bullets, examples, or any text before or after the command. If you do NOT know the correct command, respond exactly with the following format and nothing else: (NOT ABLE TO ANSWER): <one-sentence reason> — the reason should be a single short sentence explaining why the command cannot be provided. Always respond only with the shell command(s) or the one-line failure phrase in the format above.";
+const DEFAULT_MULTI: &str = "You are a strict shell command generator. OUTPUT ONLY shell commands or shell syntax in plain text with no explanations, no commentary, and no additional prose. DO NOT output any markdown, code fences, backticks, or formatting of any kind. Multi-line shell scripts are allowed when necessary. Never add numbering, bullets, examples, or any text before or after the command. If you do NOT know the correct command, respond exactly with the following format and nothing else: (NOT ABLE TO ANSWER): <one-sentence reason> — the reason should be a single short sentence explaining why the command cannot be provided. Always respond only with the shell command(s) or the one-line failure phrase in the format above.";
+
+// Choose the command-generation system instruction using priority:
+// CLI specific > CLI generic > ENV specific > ENV generic > built-in default.
+pub fn command_system(matches: &ArgMatches, allow_multiline: bool) -> String {
+    // Read optional custom system instructions from CLI or env vars.
+    let cli_system = matches.get_one::<String>("system").map(|s| s.as_str());
+    let cli_system_single = matches
+        .get_one::<String>("system-single")
+        .map(|s| s.as_str());
+    let cli_system_multi = matches
+        .get_one::<String>("system-multiline")
+        .map(|s| s.as_str());
+
+    let env_system = std::env::var("SNAPSHELL_SYSTEM").ok();
+    let env_system_single = std::env::var("SNAPSHELL_SYSTEM_SINGLE").ok();
+    let env_system_multi = std::env::var("SNAPSHELL_SYSTEM_MULTILINE").ok();
+
+    let mut sys = if let Some(s) = cli_system {
+        s.to_string()
+    } else if allow_multiline {
+        if let Some(s) = cli_system_multi {
+            s.to_string()
+        } else if let Some(s) = env_system_multi {
+            s
+        } else if let Some(s) = env_system {
+            s
+        } else {
+            DEFAULT_MULTI.to_string()
+        }
+    } else if let Some(s) = cli_system_single {
+        s.to_string()
+    } else if let Some(s) = env_system_single {
+        s
+    } else if let Some(s) = env_system {
+        s
+    } else {
+        DEFAULT_SINGLE.to_string()
+    };
+
+    // Append detected environment note so the model tailors commands to the user's OS/distro
+    sys.push_str(&env_note());
+    sys
+}
+
+// The `[[examples]]` from config.toml as user/assistant turns, placed between the system
+// instruction and the prompt. Smaller models follow house conventions far more reliably
+// from a few examples than from instructions alone.
+pub fn few_shot(allow_multiline: bool) -> Vec<JsonValue> {
+    config()
+        .examples
+        .iter()
+        .filter(|e| e.multiline.is_none_or(|m| m == allow_multiline))
+        .filter(|e| !e.prompt.trim().is_empty() && !e.command.trim().is_empty())
+        .flat_map(|e| {
+            [
+                serde_json::json!({"role": "user", "content": e.prompt}),
+                serde_json::json!({"role": "assistant", "content": e.command}),
+            ]
+        })
+        .collect()
+}
+
+// Personas for chat, selected with --persona or /persona. `personas` in config.toml can
+// add more or reword these.
+const PERSONAS: [(&str, &str); 3] = [
+    ("sysadmin", "Answer as an experienced Linux and Unix system administrator: prefer standard tools, name the files and services involved, and warn before anything destructive."),
+    ("sre", "Answer as a site reliability engineer: think about user impact, observability, and rollback, and suggest the metrics, logs, or commands that confirm a diagnosis before anything is changed."),
+    ("teacher", "Answer as a patient teacher for someone learning the shell: explain what each part of a command does and why, and define jargon the first time it appears."),
+];
+
+// Every persona name, built-in and configured, sorted.
+pub fn persona_names() -> Vec<String> {
+    let mut names: Vec<String> = PERSONAS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(registry().personas.keys().cloned())
+        .chain(config().personas.keys().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+// The instruction for persona `name`; config.toml wins over the team registry, which
+// wins over the built-in one.
+pub fn persona(name: &str) -> anyhow::Result<String> {
+    if let Some(text) = config()
+        .personas
+        .get(name)
+        .or_else(|| registry().personas.get(name))
+    {
+        return Ok(text.trim().to_string());
+    }
+    match PERSONAS.iter().find(|(n, _)| *n == name) {
+        Some((_, text)) => Ok(text.to_string()),
+        None => anyhow::bail!(
+            "no persona named {}; choose one of: {}",
+            name,
+            persona_names().join(", ")
+        ),
+    }
+}
+
+// System instruction for interactive chat mode: terse replies, optionally psql-style tables.
+pub fn chat_system(prettify_table: bool, persona_text: Option<&str>) -> String {
+    let mut inter_sys = String::from("You are an assistant for a CLI tool.");
+    if let Some(text) = persona_text {
+        inter_sys.push(' ');
+        inter_sys.push_str(text);
+    }
+    inter_sys.push_str(" Keep replies very brief (max 1-2 short paragraphs). Prioritize clarity and simplicity. Use concise sentences and avoid unnecessary explanation.");
+    if prettify_table && !plain() {
+        inter_sys.push_str(" When showing tabular data, use compact psql-style ASCII tables (no markdown or code fences) so output fits in a terminal.");
+    }
+    inter_sys.push_str(&extra_note());
+    if plain() {
+        inter_sys.push_str(" The reader may be using a screen reader: never draw tables, boxes, or ASCII art; use short sentences and simple lists instead.");
+    }
+    inter_sys.push_str(&language_note());
+    inter_sys
+}
+
+static EXTRA: OnceLock<String> = OnceLock::new();
+
+// `system_extra` from config.toml followed by each --system-append, kept for every
+// system prompt built later.
+pub fn init_extra(matches: &ArgMatches) {
+    let extra: Vec<String> = config()
+        .system_extra
+        .iter()
+        .chain(registry().system_extra.iter())
+        .chain(
+            matches
+                .get_many::<String>("system-append")
+                .into_iter()
+                .flatten(),
+        )
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            if s.ends_with(['.', '!', '?']) {
+                s
+            } else {
+                s + "."
+            }
+        })
+        .collect();
+    let _ = EXTRA.set(extra.join(" "));
+}
+
+// The user's additions, appended after the built-in rules so they refine rather than
+// replace them.
+pub fn extra_note() -> String {
+    match EXTRA.get().filter(|e| !e.is_empty()) {
+        Some(extra) => format!(" Additional instructions from the user: {}", extra),
+        None => String::new(),
+    }
+}
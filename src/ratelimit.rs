@@ -0,0 +1,192 @@
+// `rate_limit` in config.toml: at most that many API requests per minute across every
+// running `ss`, so a shell widget hotkey held down cannot run through a provider's limits
+// or the budget. Requests over the limit wait in a short queue, first come first served;
+// the shared state is a small JSON file in the state dir, read and written under a lock.
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::config;
+use crate::output::quiet;
+use crate::paths::state_dir;
+
+const WINDOW_MS: i64 = 60_000;
+// Requests that may wait for a slot; more than this are refused.
+const MAX_QUEUE: usize = 5;
+// A waiter that has not checked in this long is gone (killed, Ctrl-C) and loses its place.
+// One dropped while waiting (--max-wait elsewhere, a closed serve request) leaves at once.
+const STALE_MS: i64 = 5_000;
+const POLL: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Deserialize, Default)]
+struct State {
+    // Send times (ms) within the last minute
+    sent: Vec<i64>,
+    waiting: Vec<Waiter>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Waiter {
+    ticket: String,
+    seen: i64,
+}
+
+enum Turn {
+    Go,
+    Wait { position: usize, seconds: i64 },
+}
+
+fn state_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("ratelimit.json"))
+}
+
+// Wait until a request may be sent. Returns at once without a `rate_limit`.
+pub async fn acquire() -> Result<()> {
+    let Some(limit) = config().rate_limit.filter(|l| *l > 0) else {
+        return Ok(());
+    };
+    let Some(path) = state_path() else {
+        return Ok(());
+    };
+    let ticket = format!(
+        "{}-{}",
+        std::process::id(),
+        Utc::now().timestamp_nanos_opt().unwrap_or(0)
+    );
+    let mut queued = Queued {
+        path: &path,
+        ticket: &ticket,
+        done: false,
+    };
+    let mut shown = None;
+    loop {
+        match take_turn(&path, &ticket, limit as usize)? {
+            Turn::Go => {
+                queued.done = true;
+                if shown.is_some() && !quiet() && std::io::stderr().is_terminal() {
+                    eprint!("\r\x1b[K");
+                }
+                return Ok(());
+            }
+            Turn::Wait { position, seconds } => {
+                if shown != Some((position, seconds)) && !quiet() {
+                    let line = format!(
+                        "Rate limit of {}/min reached; queued at position {} (next slot in ~{}s)",
+                        limit, position, seconds
+                    );
+                    if std::io::stderr().is_terminal() {
+                        eprint!("\r\x1b[K{}", line);
+                    } else if shown.is_none() {
+                        eprintln!("{}", line);
+                    }
+                    shown = Some((position, seconds));
+                }
+                tokio::time::sleep(POLL).await;
+            }
+        }
+    }
+}
+
+// Takes the ticket out of the queue when acquire() is dropped before its turn.
+struct Queued<'a> {
+    path: &'a Path,
+    ticket: &'a str,
+    done: bool,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = leave(self.path, self.ticket);
+        }
+    }
+}
+
+fn leave(path: &Path, ticket: &str) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    file.lock()?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    let mut state: State = serde_json::from_str(&text).unwrap_or_default();
+    state.waiting.retain(|w| w.ticket != ticket);
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(serde_json::to_string(&state)?.as_bytes())?;
+    Ok(())
+}
+
+// One look at the shared state under an exclusive lock: take a slot if this ticket is
+// first in line and one is free, otherwise join or stay in the queue.
+fn take_turn(path: &Path, ticket: &str, limit: usize) -> Result<Turn> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)?;
+    file.lock()?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    let mut state: State = serde_json::from_str(&text).unwrap_or_default();
+
+    let now = Utc::now().timestamp_millis();
+    state.sent.retain(|t| now - t < WINDOW_MS);
+    state
+        .waiting
+        .retain(|w| w.ticket == ticket || now - w.seen < STALE_MS);
+
+    let position = match state.waiting.iter().position(|w| w.ticket == ticket) {
+        Some(i) => {
+            state.waiting[i].seen = now;
+            i
+        }
+        None => {
+            if state.waiting.len() >= MAX_QUEUE {
+                bail!(
+                    "rate limit of {}/min reached and {} requests are already waiting; try again shortly",
+                    limit,
+                    MAX_QUEUE
+                );
+            }
+            state.waiting.push(Waiter {
+                ticket: ticket.to_string(),
+                seen: now,
+            });
+            state.waiting.len() - 1
+        }
+    };
+
+    let turn = if position == 0 && state.sent.len() < limit {
+        state.waiting.remove(0);
+        state.sent.push(now);
+        Turn::Go
+    } else {
+        // This ticket goes once `behind` more sends have left the window; past the ones on
+        // record, the last one is the best guess.
+        let behind = (state.sent.len() + position + 1).saturating_sub(limit);
+        let free_at = match behind {
+            0 => now,
+            n => state
+                .sent
+                .get(n - 1)
+                .or(state.sent.last())
+                .map_or(now, |t| t + WINDOW_MS),
+        };
+        Turn::Wait {
+            position: position + 1,
+            seconds: ((free_at - now).max(0) + 999) / 1000,
+        }
+    };
+
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(serde_json::to_string(&state)?.as_bytes())?;
+    Ok(turn)
+}
@@ -0,0 +1,103 @@
+// Models report their reasoning in different shapes: OpenRouter's `reasoning` string or
+// `reasoning_details` list, a provider's `reasoning_content`, structured objects, or
+// <think> tags inside the content itself. Everything is reduced to plain text here, and
+// inline tags are removed so only the answer is treated as the command.
+use serde_json::Value as JsonValue;
+
+use crate::llm::OpenRouterChoiceMessage;
+
+// Tags some open-weight models wrap their chain of thought in.
+const THINK_TAGS: [&str; 2] = ["think", "thinking"];
+
+// Answer used when the output ended inside a think block.
+const CUT_OFF: &str =
+    "(NOT ABLE TO ANSWER): the model ran out of output while still reasoning; try again or use another model.";
+
+// Keys that hold reasoning text in the structured shapes seen so far.
+const TEXT_KEYS: [&str; 5] = ["text", "summary", "content", "reasoning", "thinking"];
+
+// The answer without any inline reasoning, and the reasoning text from wherever it was.
+pub fn normalize(message: &OpenRouterChoiceMessage) -> (String, Option<String>) {
+    let (content, inline) = split_think(message.content.as_deref().unwrap_or_default());
+    // OpenRouter can send the same text as both `reasoning` and `reasoning_details`, so
+    // the first field that has any wins rather than concatenating them.
+    let field = [
+        &message.reasoning,
+        &message.reasoning_content,
+        &message.reasoning_details,
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(text_of);
+    (content, field.or(inline))
+}
+
+fn text_of(value: &JsonValue) -> Option<String> {
+    let text = match value {
+        JsonValue::Null => return None,
+        JsonValue::String(s) => s.clone(),
+        JsonValue::Array(items) => items
+            .iter()
+            .filter_map(text_of)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        JsonValue::Object(map) => {
+            // Encrypted reasoning (reasoning.encrypted) is opaque; there is nothing to show.
+            if map
+                .get("type")
+                .and_then(|t| t.as_str())
+                .is_some_and(|t| t.contains("encrypted"))
+            {
+                return None;
+            }
+            match TEXT_KEYS.iter().find_map(|k| map.get(*k)) {
+                Some(inner) => text_of(inner)?,
+                None => value.to_string(),
+            }
+        }
+        other => other.to_string(),
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+// Remove every <think>...</think> block from `content`, returning what is left and the
+// text of the blocks. DeepSeek-style models also produce two broken shapes: a preamble
+// that ends in </think> with no opening tag (their chat template opens it in the prompt),
+// and a <think> that never closes because output ran out mid-thought. The first is cut
+// at the closing tag; the second leaves no answer, which is reported as NOT ABLE.
+pub fn split_think(content: &str) -> (String, Option<String>) {
+    let mut rest = content.to_string();
+    let mut thoughts = Vec::new();
+    for tag in THINK_TAGS {
+        let open = format!("<{}>", tag);
+        let close = format!("</{}>", tag);
+        while let Some(start) = rest.find(&open) {
+            let Some(len) = rest[start..].find(&close) else {
+                break;
+            };
+            let inner = rest[start + open.len()..start + len].trim().to_string();
+            if !inner.is_empty() {
+                thoughts.push(inner);
+            }
+            rest.replace_range(start..start + len + close.len(), "");
+        }
+        if let Some(end) = rest.rfind(&close) {
+            thoughts.insert(0, rest[..end].trim().to_string());
+            rest = rest[end + close.len()..].to_string();
+        }
+        if let Some(start) = rest.find(&open) {
+            thoughts.push(rest[start + open.len()..].trim().to_string());
+            rest.truncate(start);
+            if rest.trim().is_empty() {
+                rest = CUT_OFF.to_string();
+            }
+        }
+    }
+    thoughts.retain(|t| !t.is_empty());
+    if rest == content {
+        return (content.to_string(), None);
+    }
+    let thoughts = (!thoughts.is_empty()).then(|| thoughts.join("\n"));
+    (rest.trim().to_string(), thoughts)
+}
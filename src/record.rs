@@ -0,0 +1,117 @@
+// `--record FILE`: an asciicast v2 recording of an exec-mode session (the prompt, the
+// generated command, the confirmation, and the command's output) that plays back with
+// `asciinema play` or on asciinema.org. Events are written as they happen, so a session
+// that ends in a failed command or Ctrl-C still leaves a playable file.
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::exec::shell_quote;
+use crate::term;
+
+struct Recording {
+    file: File,
+    start: Instant,
+}
+
+static RECORDING: OnceLock<Mutex<Recording>> = OnceLock::new();
+
+// Open the file, write the header, and show the prompt as it was typed.
+pub fn start(path: &Path, force: bool, prompt: &str) -> Result<()> {
+    if path.exists() && !force {
+        bail!(
+            "{} already exists; pass --force to overwrite",
+            path.display()
+        );
+    }
+    let mut file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let height = std::env::var("LINES")
+        .ok()
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(24);
+    let header = serde_json::json!({
+        "version": 2,
+        "width": term::caps().width.unwrap_or(80),
+        "height": height,
+        "timestamp": Utc::now().timestamp(),
+        "title": prompt,
+        "env": {
+            "SHELL": std::env::var("SHELL").unwrap_or_default(),
+            "TERM": std::env::var("TERM").unwrap_or_default(),
+        },
+    });
+    writeln!(file, "{}", header)?;
+    let _ = RECORDING.set(Mutex::new(Recording {
+        file,
+        start: Instant::now(),
+    }));
+    output(&format!("$ ss -x {}\n", shell_quote(prompt)));
+    Ok(())
+}
+
+pub fn active() -> bool {
+    RECORDING.get().is_some()
+}
+
+// Text as the terminal showed it. Output from pipes has bare newlines, which a player
+// would not return to the first column on.
+pub fn output(text: &str) {
+    let Some(recording) = RECORDING.get() else {
+        return;
+    };
+    if text.is_empty() {
+        return;
+    }
+    let mut r = recording.lock().expect("recording lock");
+    let event = serde_json::json!([
+        (r.start.elapsed().as_secs_f64() * 1e6).round() / 1e6,
+        "o",
+        text.replace("\r\n", "\n").replace('\n', "\r\n"),
+    ]);
+    let _ = writeln!(r.file, "{}", event);
+}
+
+// A question asked on stderr and the answer typed to it, which the terminal echoed.
+pub fn answered(question: &str, answer: &str) {
+    output(question);
+    output(&format!("{}\n", answer.trim_end_matches(['\r', '\n'])));
+}
+
+// A writer that passes everything through and records it, for the command's output.
+// A character split between two reads is held back until the rest of it arrives.
+pub struct Echo<W> {
+    inner: W,
+    pending: Vec<u8>,
+}
+
+impl<W> Echo<W> {
+    pub fn new(inner: W) -> Self {
+        Echo {
+            inner,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for Echo<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.pending.extend_from_slice(&buf[..n]);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let text: Vec<u8> = self.pending.drain(..complete).collect();
+        output(&String::from_utf8_lossy(&text));
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,189 @@
+// Opt-in masking of identifying details in outbound prompts. Email addresses, IPv4
+// addresses, internal hostnames, the local username, and configured terms are swapped
+// for documentation-style placeholders before a request leaves the machine; the table
+// stays local and maps placeholders in the answer back to the real values.
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+use crate::config::config;
+use crate::modes::capture;
+
+// Hostnames under these suffixes are treated as internal.
+const INTERNAL_SUFFIXES: &str = r"local|internal|corp|lan|intranet|home\.arpa";
+// Addresses that identify nobody and are left as-is.
+const KEEP_IPS: [&str; 3] = ["127.0.0.1", "0.0.0.0", "255.255.255.255"];
+// Account names too generic to mask.
+const KEEP_USERS: [&str; 3] = ["root", "admin", "user"];
+
+#[derive(Default)]
+pub struct Redactor {
+    // (placeholder, original)
+    table: Vec<(String, String)>,
+}
+
+fn patterns() -> &'static Vec<(&'static str, Regex)> {
+    static PATTERNS: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let mut host = vec![format!(
+            r"(?i)\b(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+(?:{})\b",
+            INTERNAL_SUFFIXES
+        )];
+        for domain in &config().redact.domains {
+            host.push(format!(
+                r"(?i)\b(?:[a-z0-9-]+\.)*{}\b",
+                regex::escape(domain.trim_start_matches('.'))
+            ));
+        }
+        // The machine's own name and the username are often ordinary words ("web",
+        // "build", "dev"), so they are masked only where they act as one: in paths,
+        // addresses, and host or user arguments. Only the name itself (group 1) changes.
+        if let Some(name) = capture("hostname", &[]).filter(|h| !h.is_empty()) {
+            let name = regex::escape(&name);
+            host.push(format!(
+                r"(?i)(?:@|//|\b(?:ssh|scp|mosh|ping|telnet|host|dig|nslookup)\s+(?:-\S+\s+)*)({})\b",
+                name
+            ));
+            host.push(format!(r"(?i)\b({})(?:\.[a-z]|:[/~])", name));
+        }
+
+        let mut user: Vec<String> = config()
+            .redact
+            .terms
+            .iter()
+            .map(|t| format!(r"\b{}\b", regex::escape(t)))
+            .collect();
+        if let Some(name) = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok()
+            .filter(|u| !u.is_empty() && !KEEP_USERS.contains(&u.as_str()))
+        {
+            let name = regex::escape(&name);
+            user.push(format!(
+                r"(?:/home/|/Users/|~|\s-u\s*|\s-l\s*|--user[=\s])({})\b",
+                name
+            ));
+            user.push(format!(r"\b({})@", name));
+        }
+
+        let mut out = vec![
+            (
+                "email",
+                Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b").unwrap(),
+            ),
+            (
+                "ip",
+                Regex::new(
+                    r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b",
+                )
+                .unwrap(),
+            ),
+        ];
+        // Invalid config entries are escaped above, so these always compile.
+        out.extend(host.iter().map(|p| ("host", Regex::new(p).unwrap())));
+        out.extend(user.iter().map(|p| ("user", Regex::new(p).unwrap())));
+        out
+    })
+}
+
+impl Redactor {
+    fn placeholder(&mut self, kind: &str, original: &str) -> String {
+        if let Some((p, _)) = self.table.iter().find(|(_, o)| o == original) {
+            return p.clone();
+        }
+        let n = self
+            .table
+            .iter()
+            .filter(|(p, _)| placeholder_kind(p) == kind)
+            .count()
+            + 1;
+        let p = match kind {
+            "email" => format!("person-{}@example.com", n),
+            "ip" => format!("192.0.2.{}", n),
+            "host" => format!("host-{}.example", n),
+            _ => format!("user-{}", n),
+        };
+        self.table.push((p.clone(), original.to_string()));
+        p
+    }
+
+    pub fn redact(&mut self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (kind, re) in patterns() {
+            out = re
+                .replace_all(&out, |c: &Captures| {
+                    let whole = c.get(0).unwrap();
+                    let m = c.get(1).unwrap_or(whole);
+                    let (before, after) = (
+                        &whole.as_str()[..m.start() - whole.start()],
+                        &whole.as_str()[m.end() - whole.start()..],
+                    );
+                    // Earlier placeholders and harmless addresses pass through.
+                    let value = m.as_str();
+                    let masked = if KEEP_IPS.contains(&value)
+                        || self.table.iter().any(|(p, _)| p == value)
+                    {
+                        value.to_string()
+                    } else {
+                        self.placeholder(kind, value)
+                    };
+                    format!("{}{}{}", before, masked, after)
+                })
+                .into_owned();
+        }
+        out
+    }
+
+    // Put the original values back in one pass, so a restored value is never read again
+    // as a placeholder. Placeholders match whole tokens only (`192.0.2.1` is not part of
+    // `192.0.2.10`), longest first.
+    pub fn restore(&self, text: &str) -> String {
+        let mut entries: Vec<&(String, String)> = self.table.iter().collect();
+        entries.sort_by_key(|(p, _)| std::cmp::Reverse(p.len()));
+        let continues = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-');
+        let mut out = String::new();
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            let starts_token = text[..i]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !continues(c) && c != '.');
+            let found = entries.iter().find(|(p, _)| {
+                let Some(after) = rest.strip_prefix(p.as_str()) else {
+                    return false;
+                };
+                let mut next = after.chars();
+                match next.next() {
+                    // A dot ends the token unless more of a name follows it.
+                    Some('.') => !next.next().is_some_and(continues),
+                    Some(c) => !continues(c),
+                    None => true,
+                }
+            });
+            match found.filter(|_| starts_token) {
+                Some((p, o)) => {
+                    out.push_str(o);
+                    i += p.len();
+                }
+                None => {
+                    let c = rest.chars().next().unwrap();
+                    out.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+        out
+    }
+}
+
+fn placeholder_kind(p: &str) -> &'static str {
+    if p.starts_with("person-") {
+        "email"
+    } else if p.starts_with("192.0.2.") {
+        "ip"
+    } else if p.starts_with("host-") {
+        "host"
+    } else {
+        "user"
+    }
+}
@@ -0,0 +1,60 @@
+// `--loop`: refine a generated command in a short REPL before accepting it.
+use serde_json::Value as JsonValue;
+use std::io::{self, Write};
+
+use crate::diff;
+use crate::llm::{complete_or_exit, Completion, Settings};
+use crate::output::is_not_able_response;
+use crate::paste;
+
+// Returns the accepted completion, or None if the user discarded it. `messages` holds
+// the conversation so far and keeps growing with each refinement.
+pub async fn run(
+    settings: &Settings,
+    messages: &mut Vec<JsonValue>,
+    first: Completion,
+) -> Option<Completion> {
+    let mut current = first;
+    eprintln!("{}", current.content.trim());
+    eprintln!("Type a refinement, empty line to accept, or /quit to discard.");
+    loop {
+        eprint!("refine> ");
+        let _ = io::stderr().flush();
+        let paste = paste::enable();
+        let Some(line) = read_raw_line() else {
+            return Some(current);
+        };
+        let (line, _) = paste::collect(line, read_raw_line);
+        drop(paste);
+        let line = line.trim();
+        if line.is_empty() {
+            return Some(current);
+        }
+        if line == "/quit" || line == "/exit" {
+            return None;
+        }
+
+        messages.push(serde_json::json!({"role": "assistant", "content": current.content}));
+        messages.push(serde_json::json!({"role": "user", "content": line}));
+        let next = complete_or_exit(settings, messages).await;
+        let new = next.content.trim();
+
+        if is_not_able_response(new) {
+            // Keep the previous command; drop the failed turn from the conversation.
+            eprintln!("{}", new);
+            messages.truncate(messages.len() - 2);
+            continue;
+        }
+        eprint!("{}", diff::render(current.content.trim(), new));
+        current = next;
+    }
+}
+
+// None on EOF or a read error.
+fn read_raw_line() -> Option<String> {
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+    }
+}
@@ -0,0 +1,126 @@
+// A team's shared snippets, personas, and system instructions, pulled read-only from the
+// `registry` URL in config.toml by `ss snippet sync`. Local snippets and personas with
+// the same name win, so nobody is stuck with a shared prompt that does not suit them.
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::OnceLock;
+
+use crate::config::config;
+use crate::paths::state_dir;
+
+// What a git registry keeps at the top of the repository.
+const REGISTRY_FILE: &str = "snapshell.json";
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Registry {
+    pub snippets: BTreeMap<String, String>,
+    pub personas: BTreeMap<String, String>,
+    // Appended to every system prompt, after config.toml's system_extra
+    pub system_extra: Option<String>,
+    // Set on sync: where it came from and when
+    pub source: Option<String>,
+    pub synced: Option<String>,
+}
+
+fn registry_path() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("registry.json"))
+}
+
+// The last synced registry; empty before the first sync or if the copy is unreadable.
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        registry_path()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    })
+}
+
+fn is_git(url: &str) -> bool {
+    url.ends_with(".git")
+        || url.starts_with("git@")
+        || url.starts_with("git://")
+        || url.starts_with("ssh://")
+}
+
+// Fetch the registry named in config.toml and keep a copy for later runs.
+pub async fn sync() -> Result<Registry> {
+    let Some(url) = config().registry.as_deref() else {
+        bail!("no registry configured; set registry = \"<https or git URL>\" in config.toml");
+    };
+    let mut registry = if is_git(url) {
+        fetch_git(url)?
+    } else if url.starts_with("https://") {
+        fetch_https(url).await?
+    } else {
+        bail!(
+            "registry must be an https:// URL or a git repository, not {}",
+            url
+        );
+    };
+    registry.snippets.retain(|name, _| {
+        let ok = crate::snippet::validate_name(name).is_ok();
+        if !ok {
+            eprintln!("Skipping registry snippet with an invalid name: {}", name);
+        }
+        ok
+    });
+    registry.source = Some(url.to_string());
+    registry.synced = Some(Utc::now().to_rfc3339());
+
+    let Some(path) = registry_path() else {
+        bail!("could not determine the state directory");
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&registry)? + "\n")?;
+    Ok(registry)
+}
+
+async fn fetch_https(url: &str) -> Result<Registry> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("snapshell/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("reading the registry from {}", url))
+}
+
+// A shallow clone into a private scratch directory (removed when it goes out of scope);
+// only snapshell.json is read from it.
+fn fetch_git(url: &str) -> Result<Registry> {
+    let scratch = tempfile::Builder::new()
+        .prefix("snapshell-registry-")
+        .tempdir()
+        .context("creating a scratch directory")?;
+    let dir = scratch.path().join("clone");
+    let output = std::process::Command::new("git")
+        .args(["clone", "--quiet", "--depth", "1", "--", url])
+        .arg(&dir)
+        .stdin(Stdio::null())
+        .output()
+        .context("running git")?;
+    let text = if output.status.success() {
+        std::fs::read_to_string(dir.join(REGISTRY_FILE))
+            .with_context(|| format!("{} has no {} at the top level", url, REGISTRY_FILE))
+    } else {
+        Err(anyhow::anyhow!(
+            "git clone {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    };
+    serde_json::from_str(&text?).with_context(|| format!("reading {} from {}", REGISTRY_FILE, url))
+}
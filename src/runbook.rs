@@ -0,0 +1,182 @@
+// `ss runbook`: turn a chat session or a stretch of history into a Markdown runbook with
+// a goal, numbered steps with their commands, and how to verify the result, so a
+// debugging session ends up as documentation someone else can follow.
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::env::env_note;
+use crate::history::{load_exec_log, load_history};
+use crate::llm::parse_duration;
+use crate::modes::{ask, strip_code_fences};
+use crate::output::{exit_not_able, parse_not_able, print_reasoning, write_output_file};
+use crate::session;
+
+// Only the end of a failed run's stderr goes into the request.
+const MAX_STDERR_BYTES: usize = 400;
+
+pub fn command() -> Command {
+    Command::new("runbook")
+        .about("Write a Markdown runbook (goal, steps, commands, verification) from a chat session or recent history")
+        .after_help("Examples:\n  ss runbook last -o restore-backup.md\n  ss runbook --last 8\n  ss runbook --since 2h -o docs/nginx-502.md")
+        .arg(
+            Arg::new("session")
+                .help("Chat session id from `ss chat list`, or `last`")
+                .index(1)
+                .conflicts_with_all(["last", "since"]),
+        )
+        .arg(
+            Arg::new("last")
+                .long("last")
+                .value_name("N")
+                .help("Use the last N history entries")
+                .num_args(1)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .value_name("DURATION")
+                .help("Use the history entries from the last DURATION, e.g. 30m or 2h")
+                .num_args(1)
+                .value_parser(parse_duration),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .help("Write the runbook to this file (default: runbook.md); - prints it")
+                .num_args(1)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .help("Overwrite the output file if it already exists")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let material = match matches.get_one::<String>("session") {
+        Some(id) => from_session(id)?,
+        None if matches.contains_id("last") || matches.contains_id("since") => {
+            from_history(matches)?
+        }
+        None => bail!(
+            "name a chat session (e.g. `ss runbook last`) or pass --last N or --since DURATION"
+        ),
+    };
+    let path = matches
+        .get_one::<PathBuf>("output")
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("runbook.md"));
+    let to_stdout = path.as_os_str() == "-";
+    if !to_stdout && path.exists() && !matches.get_flag("force") {
+        bail!(
+            "{} already exists; pass --force to overwrite",
+            path.display()
+        );
+    }
+
+    let system = format!("You turn a terminal troubleshooting session into a runbook that a teammate can follow later. OUTPUT ONLY Markdown, with no surrounding code fence, in this structure: a `# ` title naming the task; `## Goal` with one or two sentences on what the runbook achieves and when to use it; `## Steps` as a numbered list where each step says in one line why it is done and gives its command in a ```bash block; `## Verification` with the commands or checks that show it worked and what to expect; and `## Notes` only for pitfalls the session ran into. Use the commands from the session, generalized with <PLACEHOLDERS> where they name one-off values. Leave out dead ends and commands that failed and were replaced, but mention in Notes what went wrong with them. Do not add steps the session did not take, except verification checks. If the session holds no usable commands, respond exactly with: (NOT ABLE TO ANSWER): <one-sentence reason>.{}", env_note());
+    let (answer, reasoning) = ask(matches, &system, &material).await;
+    let doc = strip_code_fences(&answer);
+    if let Some(reason) = parse_not_able(&doc) {
+        exit_not_able(&reason);
+    }
+
+    if to_stdout {
+        println!("{}", doc);
+    } else {
+        write_output_file(&path, &doc, matches.get_flag("force"), false)?;
+        eprintln!("Wrote {} ({} steps).", path.display(), count_steps(&doc));
+    }
+    if let Some(r) = reasoning {
+        print_reasoning(r);
+    }
+    Ok(())
+}
+
+// The main line of a saved chat; branches were side trips.
+fn from_session(id: &str) -> Result<String> {
+    let s = session::load(id)?;
+    if s.turns.is_empty() {
+        bail!("chat session {} has no turns", s.id);
+    }
+    let mut out = format!("Chat session {} started {}:\n", s.id, s.started);
+    for (i, t) in s.turns.iter().enumerate() {
+        out.push_str(&format!(
+            "\n{}. User: {}\nAnswer:\n{}\n",
+            i + 1,
+            t.prompt.trim(),
+            t.response.trim()
+        ));
+    }
+    Ok(out)
+}
+
+// History entries in the range, each with how its runs went when it was run with --exec.
+fn from_history(matches: &ArgMatches) -> Result<String> {
+    let mut entries = load_history()?;
+    if let Some(window) = matches.get_one::<Duration>("since") {
+        let cutoff = Utc::now() - chrono::Duration::from_std(*window)?;
+        entries.retain(|e| DateTime::parse_from_rfc3339(&e.timestamp).is_ok_and(|t| t >= cutoff));
+    }
+    if let Some(n) = matches.get_one::<usize>("last") {
+        entries.drain(..entries.len().saturating_sub(*n));
+    }
+    if entries.is_empty() {
+        bail!("no history entries in that range; see `ss history`");
+    }
+    let runs = load_exec_log()?;
+    let mut out = String::from("Commands generated from these requests, oldest first:\n");
+    for (i, e) in entries.iter().enumerate() {
+        out.push_str(&format!(
+            "\n{}. [{}] Request: {}\nCommand:\n{}\n",
+            i + 1,
+            e.timestamp,
+            e.prompt,
+            e.command
+        ));
+        for r in runs
+            .iter()
+            .filter(|r| r.history.as_deref() == Some(e.timestamp.as_str()))
+        {
+            match r.exit_code {
+                Some(0) => out.push_str("Ran: succeeded\n"),
+                code => {
+                    let status = code.map_or("killed by a signal".to_string(), |c| {
+                        format!("failed with exit {}", c)
+                    });
+                    out.push_str(&format!("Ran: {}\n", status));
+                    let stderr = r.stderr.trim();
+                    if !stderr.is_empty() {
+                        let mut start = stderr.len().saturating_sub(MAX_STDERR_BYTES);
+                        while !stderr.is_char_boundary(start) {
+                            start += 1;
+                        }
+                        out.push_str(&format!("stderr:\n{}\n", &stderr[start..]));
+                    }
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+// Top-level numbered items under `## Steps`.
+fn count_steps(doc: &str) -> usize {
+    doc.lines()
+        .skip_while(|l| !l.trim_start().starts_with("## Steps"))
+        .skip(1)
+        .take_while(|l| !l.starts_with("## "))
+        .filter(|l| {
+            l.split_once(". ")
+                .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .count()
+}
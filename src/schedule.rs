@@ -0,0 +1,150 @@
+// `--schedule WHEN`: wrap the generated command so it runs later. A one-off time goes to
+// `at` (or a transient systemd timer where at is missing); a repeating one goes into the
+// crontab (or a transient systemd timer where there is no cron). The model only turns
+// WHEN into concrete times; the wrapper itself is built here from what is installed.
+use anyhow::{bail, Context, Result};
+use chrono::{Local, NaiveDateTime};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::env::which;
+use crate::exec::shell_quote;
+use crate::llm::{complete, Settings};
+use crate::modes::strip_code_fences;
+use crate::output::parse_not_able;
+
+#[derive(Deserialize)]
+struct When {
+    recurring: bool,
+    // One-off runs: local time as "YYYY-MM-DD HH:MM"
+    at: Option<String>,
+    // Repeating runs: a five-field cron expression and the systemd OnCalendar equivalent
+    cron: Option<String>,
+    on_calendar: Option<String>,
+}
+
+pub struct Schedule {
+    // The command that installs the schedule
+    pub command: String,
+    // What it does, for stderr
+    pub summary: String,
+}
+
+// Ask for the times WHEN stands for, then wrap `command` for this system's scheduler.
+pub async fn wrap(settings: &Settings, when: &str, command: &str) -> Result<Schedule> {
+    if settings.offline {
+        bail!("--schedule needs the API to read the time; drop --offline");
+    }
+    let mut cheap = settings.clone();
+    cheap.effort = "low".to_string();
+    cheap.retry_not_able = false;
+    let now = Local::now();
+    let messages: Vec<JsonValue> = vec![
+        serde_json::json!({"role": "system", "content": "You convert a description of when to run something into times. OUTPUT ONLY a JSON object with no markdown: {\"recurring\": <true if it repeats>, \"at\": \"<for a single run: local time as YYYY-MM-DD HH:MM, else null>\", \"cron\": \"<if recurring: five-field cron expression, else null>\", \"on_calendar\": \"<if recurring: the same schedule as a systemd OnCalendar expression, else null>\"}. A single run must be in the future; \"tonight at 2am\" means the coming 02:00. If the description is not a time, respond exactly with: (NOT ABLE TO ANSWER): <one-sentence reason>."}),
+        serde_json::json!({"role": "user", "content": format!("Now: {}\nWhen: {}", now.format("%Y-%m-%d %H:%M %A (UTC%:z)"), when)}),
+    ];
+    let answer = strip_code_fences(&complete(&cheap, &messages).await?.content);
+    if let Some(reason) = parse_not_able(&answer) {
+        bail!("could not schedule \"{}\": {}", when, reason);
+    }
+    let parsed: When = answer
+        .find('{')
+        .zip(answer.rfind('}'))
+        .and_then(|(start, end)| answer.get(start..=end))
+        .and_then(|json| serde_json::from_str(json).ok())
+        .with_context(|| format!("could not read the schedule from: {}", answer))?;
+
+    if parsed.recurring {
+        recurring(&parsed, command)
+    } else {
+        once(&parsed, command)
+    }
+}
+
+fn once(when: &When, command: &str) -> Result<Schedule> {
+    let at = when.at.as_deref().unwrap_or_default();
+    let time = NaiveDateTime::parse_from_str(at, "%Y-%m-%d %H:%M")
+        .with_context(|| format!("the model gave an unreadable time: {}", at))?;
+    if time <= Local::now().naive_local() {
+        bail!("{} is in the past", at);
+    }
+    if which("at").is_some() {
+        let mut summary = format!("Runs once at {} with at.", at);
+        if cfg!(target_os = "macos") {
+            summary.push_str(" On macOS, at jobs only run once atrun is enabled: sudo launchctl load -w /System/Library/LaunchDaemons/com.apple.atrun.plist");
+        }
+        // at keeps the current directory and environment for the job.
+        return Ok(Schedule {
+            command: format!(
+                "printf '%s\\n' {} | at -t {}",
+                shell_quote(command),
+                time.format("%Y%m%d%H%M")
+            ),
+            summary,
+        });
+    }
+    if which("systemd-run").is_some() {
+        return Ok(Schedule {
+            command: systemd_run(&format!("{}:00", at), command)?,
+            summary: format!("Runs once at {} from a systemd user timer.", at),
+        });
+    }
+    bail!("neither at nor systemd-run is installed to run the command later")
+}
+
+fn recurring(when: &When, command: &str) -> Result<Schedule> {
+    if let Some(cron) = when.cron.as_deref().filter(|_| which("crontab").is_some()) {
+        if cron.split_whitespace().count() != 5 {
+            bail!("the model gave an unreadable cron expression: {}", cron);
+        }
+        if command.contains('\n') {
+            bail!("a multi-line command cannot go in a crontab; save it as a script and schedule that");
+        }
+        // cron turns an unescaped % into a newline.
+        let line = format!(
+            "{} {}",
+            cron.split_whitespace().collect::<Vec<_>>().join(" "),
+            in_cwd(command)?.replace('%', "\\%")
+        );
+        return Ok(Schedule {
+            command: format!(
+                "(crontab -l 2>/dev/null; printf '%s\\n' {}) | crontab -",
+                shell_quote(&line)
+            ),
+            summary: format!("Adds a crontab entry: {}", cron),
+        });
+    }
+    if let Some(spec) = when
+        .on_calendar
+        .as_deref()
+        .filter(|_| which("systemd-run").is_some())
+    {
+        return Ok(Schedule {
+            command: systemd_run(spec, command)?,
+            summary: format!(
+                "Runs on {} from a systemd user timer, until the next reboot.",
+                spec
+            ),
+        });
+    }
+    bail!("neither crontab nor systemd-run is installed to run the command on a schedule")
+}
+
+fn systemd_run(spec: &str, command: &str) -> Result<String> {
+    Ok(format!(
+        "systemd-run --user --on-calendar={} -- sh -c {}",
+        shell_quote(spec),
+        shell_quote(&in_cwd(command)?)
+    ))
+}
+
+// cron and systemd start jobs in the home directory, so relative paths would point
+// somewhere else than where the command was generated.
+fn in_cwd(command: &str) -> Result<String> {
+    let cwd = std::env::current_dir()?;
+    Ok(format!(
+        "cd {} && {}",
+        shell_quote(&cwd.to_string_lossy()),
+        command
+    ))
+}
@@ -0,0 +1,104 @@
+// Rendering of generated multiline scripts written to disk with --output.
+
+pub const SHEBANG: &str = "#!/usr/bin/env bash";
+pub const DEFAULT_PREAMBLE: &str = "set -euo pipefail";
+
+// Extra instruction for the model when the result is saved as a script file.
+pub const SCRIPT_NOTE: &str = " The output will be saved to a file and run with bash: write a complete bash script, without a shebang line.";
+
+// Preamble priority: --preamble > SNAPSHELL_SCRIPT_PREAMBLE > built-in default. Empty disables it.
+pub fn preamble(cli: Option<&str>) -> String {
+    cli.map(|s| s.to_string())
+        .or_else(|| std::env::var("SNAPSHELL_SCRIPT_PREAMBLE").ok())
+        .unwrap_or_else(|| DEFAULT_PREAMBLE.to_string())
+}
+
+// Prepend the shebang and preamble, dropping a shebang the model added anyway
+// and not repeating a preamble it already wrote.
+pub fn render(body: &str, preamble: &str) -> String {
+    let mut lines: Vec<&str> = body.trim().lines().collect();
+    if lines.first().map(|l| l.starts_with("#!")).unwrap_or(false) {
+        lines.remove(0);
+    }
+    while lines.first().map(|l| l.trim().is_empty()).unwrap_or(false) {
+        lines.remove(0);
+    }
+
+    let mut out = format!("{}\n", SHEBANG);
+    let preamble = preamble.trim();
+    if !preamble.is_empty() && !lines.iter().any(|l| l.trim() == preamble) {
+        out.push_str(preamble);
+        out.push_str("\n\n");
+    }
+    out.push_str(&lines.join("\n"));
+    out.push('\n');
+    out
+}
+
+// Appended to the multiline instruction by --annotate; overrides the "no commentary" rule for comments only.
+pub const ANNOTATE_NOTE: &str = " Exception to the no-commentary rule: precede each logical block of the script with a brief `#` comment line saying what the block does. Comments must be valid shell comments; still no prose outside the script.";
+
+// Drop full-line `#` comments (keeping a shebang) and the blank runs they leave behind.
+// Trailing comments are kept: removing them safely needs real shell quoting rules.
+// Heredoc bodies are data (a config file, a SQL script), so they are kept as they are.
+pub fn strip_comments(script: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    // Delimiters of heredocs still open, in the order their bodies come, and whether
+    // each was opened with `<<-` (leading tabs before the delimiter).
+    let mut pending: Vec<(String, bool)> = Vec::new();
+    for (i, line) in script.lines().enumerate() {
+        if let Some((delimiter, tabs)) = pending.first() {
+            let end = if *tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line
+            };
+            if end == delimiter {
+                pending.remove(0);
+            }
+            out.push(line);
+            continue;
+        }
+        pending.extend(heredocs(line));
+        let t = line.trim_start();
+        if t.starts_with('#') && !(i == 0 && t.starts_with("#!")) {
+            continue;
+        }
+        if t.is_empty() && out.last().map(|l| l.trim().is_empty()).unwrap_or(true) {
+            continue;
+        }
+        out.push(line);
+    }
+    while out.last().map(|l| l.trim().is_empty()).unwrap_or(false) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+// The heredocs a line opens (`<<EOF`, `<<-'EOF'`, `<< "EOF"`), skipping `<<<` here-strings.
+fn heredocs(line: &str) -> Vec<(String, bool)> {
+    let mut found = Vec::new();
+    let mut rest = line;
+    while let Some(pos) = rest.find("<<") {
+        let mut after = &rest[pos + 2..];
+        if let Some(more) = after.strip_prefix('<') {
+            rest = more;
+            continue;
+        }
+        let tabs = after.starts_with('-');
+        after = after.trim_start_matches('-').trim_start();
+        let word: String = after
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !matches!(c, ';' | '|' | '&' | '<' | '>' | ')'))
+            .collect();
+        let delimiter: String = word
+            .chars()
+            .filter(|c| !matches!(c, '\'' | '"' | '\\'))
+            .collect();
+        if !delimiter.is_empty() {
+            found.push((delimiter, tabs));
+        }
+        rest = &after[word.len()..];
+    }
+    found
+}
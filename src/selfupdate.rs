@@ -0,0 +1,188 @@
+// `ss self-update`: replace the running binary with the latest GitHub release. Release
+// assets are named snapshell-<os>-<arch>[.exe] and listed with their sha256 in a
+// SHA256SUMS asset; a download whose checksum is missing or wrong is never installed.
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::IsTerminal;
+use std::path::Path;
+
+use crate::modes::confirm;
+use crate::policy::policy;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/eufat/snapshell/releases/latest";
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+// `--check` exit status when a newer release exists, so CI can branch on it.
+pub const EXIT_UPDATE_AVAILABLE: i32 = 5;
+
+pub fn command() -> Command {
+    Command::new("self-update")
+        .about("Update snapshell to the latest GitHub release, verifying its checksum")
+        .after_help("Examples:\n  ss self-update\n  ss self-update --check")
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("Only report whether an update exists (exit status 5 if it does)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("yes")
+                .short('y')
+                .long("yes")
+                .help("Replace the binary without asking")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    policy().check_self_update()?;
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("snapshell/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let release: Release = client
+        .get(RELEASES_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("reading the latest release from GitHub")?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = release.tag_name.trim_start_matches('v');
+    if !newer(latest, current) {
+        println!("snapshell {} is up to date.", current);
+        return Ok(());
+    }
+    println!("Update available: {} -> {}", current, latest);
+    if matches.get_flag("check") {
+        std::process::exit(EXIT_UPDATE_AVAILABLE);
+    }
+
+    let name = asset_name();
+    let asset = release
+        .assets
+        .iter()
+        .find(|a| a.name == name)
+        .ok_or_else(|| anyhow!("release {} has no {} asset", release.tag_name, name))?;
+    let sums = release
+        .assets
+        .iter()
+        .find(|a| a.name == CHECKSUMS_ASSET)
+        .ok_or_else(|| {
+            anyhow!(
+                "release {} has no {}; refusing to install an unverified binary",
+                release.tag_name,
+                CHECKSUMS_ASSET
+            )
+        })?;
+
+    let sums = client
+        .get(&sums.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let expected = expected_sum(&sums, &name)
+        .ok_or_else(|| anyhow!("{} does not list {}", CHECKSUMS_ASSET, name))?;
+    eprintln!("Downloading {}...", name);
+    let binary = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let actual = format!("{:x}", Sha256::digest(&binary));
+    if !actual.eq_ignore_ascii_case(&expected) {
+        bail!(
+            "checksum mismatch for {}: expected {}, got {}",
+            name,
+            expected,
+            actual
+        );
+    }
+    eprintln!("Checksum verified ({}).", actual);
+
+    let exe = std::env::current_exe()?;
+    if !matches.get_flag("yes") {
+        if !std::io::stdin().is_terminal() {
+            bail!("not replacing {} without --yes", exe.display());
+        }
+        if !confirm(&format!("Replace {} with {}?", exe.display(), latest)) {
+            eprintln!("Not updated.");
+            return Ok(());
+        }
+    }
+    replace(&exe, &binary)
+        .with_context(|| format!("replacing {} (does it need sudo?)", exe.display()))?;
+    println!("Updated to snapshell {}.", latest);
+    Ok(())
+}
+
+// Compare dotted numeric versions; pre-release suffixes are ignored.
+fn newer(latest: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(latest) > parse(current)
+}
+
+fn asset_name() -> String {
+    let ext = if cfg!(windows) { ".exe" } else { "" };
+    format!(
+        "snapshell-{}-{}{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        ext
+    )
+}
+
+// `sha256sum` format: "<hex>  <name>" (a `*` before the name marks binary mode).
+fn expected_sum(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|l| {
+        let (hash, file) = l.split_once(char::is_whitespace)?;
+        (file.trim().trim_start_matches('*') == name).then(|| hash.to_string())
+    })
+}
+
+// Write next to the old binary, then rename over it, so an interrupted update never
+// leaves a half-written executable. Windows cannot replace a running .exe, so the old
+// one is moved aside first.
+fn replace(exe: &Path, binary: &[u8]) -> Result<()> {
+    let dir = exe.parent().ok_or_else(|| anyhow!("no parent directory"))?;
+    let staged = dir.join(".snapshell-update");
+    std::fs::write(&staged, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+    if cfg!(windows) {
+        let old = exe.with_extension("old.exe");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old)?;
+    }
+    std::fs::rename(&staged, exe)?;
+    Ok(())
+}
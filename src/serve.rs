@@ -0,0 +1,428 @@
+// `ss serve`: a long-running process editors and internal tools can embed, so config,
+// policy, and the API connection are loaded once instead of per request. `--stdio` speaks
+// JSON-RPC 2.0, one message per line; `--http` exposes the same methods as REST endpoints
+// behind a bearer token.
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use hyper::body::HttpBody;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde_json::Value as JsonValue;
+use std::convert::Infallible;
+use std::io::{BufRead, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::exec::explain;
+use crate::history::{load_history, save_history};
+use crate::llm::{complete, warm_up, Settings};
+use crate::output::{parse_not_able, refusal};
+use crate::prompt::{command_system, few_shot};
+
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+const TOKEN_ENV: &str = "SNAPSHELL_SERVE_TOKEN";
+// Request bodies are a prompt or a command; anything bigger is a mistake or abuse.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+// JSON-RPC error codes; the -320xx range is left to the application.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const GENERATION_FAILED: i64 = -32000;
+const COMMAND_REFUSED: i64 = -32001;
+const UNAUTHORIZED: i64 = -32002;
+
+pub fn command() -> Command {
+    Command::new("serve")
+        .about("Serve generate/explain/history requests to editor plugins (JSON-RPC) or internal tools (HTTP)")
+        .after_help("Examples:\n  ss serve --stdio\n  SNAPSHELL_SERVE_TOKEN=s3cret ss serve --http 127.0.0.1:7878")
+        .arg(
+            Arg::new("stdio")
+                .long("stdio")
+                .help("Speak JSON-RPC 2.0 on stdin/stdout, one message per line")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("http")
+                .long("http")
+                .help("Serve REST endpoints on this address, e.g. 127.0.0.1:7878")
+                .num_args(1)
+                .value_parser(clap::value_parser!(SocketAddr)),
+        )
+        .arg(
+            Arg::new("token")
+                .long("token")
+                .help("Bearer token HTTP clients must send (default: SNAPSHELL_SERVE_TOKEN, else a random one printed at startup)")
+                .num_args(1)
+                .requires("http"),
+        )
+        .group(
+            ArgGroup::new("transport")
+                .args(["stdio", "http"])
+                .required(true),
+        )
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+fn rpc_error(code: i64, message: impl Into<String>) -> RpcError {
+    RpcError {
+        code,
+        message: message.into(),
+    }
+}
+
+// State shared by every request: settings and both system instructions are built once.
+struct Server {
+    settings: Settings,
+    single: String,
+    multi: String,
+}
+
+impl Server {
+    // `root` carries the top-level -s/--system-* overrides, as for `ss batch`.
+    fn new(matches: &ArgMatches, root: &ArgMatches) -> Server {
+        Server {
+            settings: Settings::from_matches(matches),
+            single: command_system(root, false),
+            multi: command_system(root, true),
+        }
+    }
+
+    async fn call(&self, method: &str, params: &JsonValue) -> Result<JsonValue, RpcError> {
+        match method {
+            "generate" => self.generate(params).await,
+            "explain" => self.explain(params).await,
+            "history" => history(params),
+            _ => Err(rpc_error(
+                METHOD_NOT_FOUND,
+                format!("unknown method {}", method),
+            )),
+        }
+    }
+
+    // params: {"prompt": "...", "multiline": false, "model": "..."}
+    async fn generate(&self, params: &JsonValue) -> Result<JsonValue, RpcError> {
+        let prompt = required_str(params, "prompt")?;
+        let mut settings = self.settings.clone();
+        if let Some(model) = params["model"].as_str() {
+            settings.model = model.to_string();
+        }
+        let multiline = params["multiline"].as_bool().unwrap_or(false);
+        let system = if multiline { &self.multi } else { &self.single };
+        let mut messages = vec![serde_json::json!({"role": "system", "content": system})];
+        messages.extend(few_shot(multiline));
+        messages.push(serde_json::json!({"role": "user", "content": prompt}));
+        let c = complete(&settings, &messages)
+            .await
+            .map_err(|e| rpc_error(GENERATION_FAILED, e.to_string()))?;
+        let out = c.content.trim().to_string();
+        if let Some(reason) = parse_not_able(&out) {
+            return Ok(serde_json::json!({"command": null, "not_able": true, "reason": reason}));
+        }
+        // Refused commands never reach the client, as on the command line.
+        if let Some(reason) = refusal(&out) {
+            return Err(rpc_error(COMMAND_REFUSED, format!("command {}", reason)));
+        }
+        if let Err(e) = save_history(prompt, &out) {
+            eprintln!("Failed to save history: {}", e);
+        }
+        Ok(serde_json::json!({
+            "command": out,
+            "not_able": false,
+            "model": settings.model,
+            "reasoning": c.reasoning,
+        }))
+    }
+
+    // params: {"command": "..."}
+    async fn explain(&self, params: &JsonValue) -> Result<JsonValue, RpcError> {
+        let command = required_str(params, "command")?;
+        match explain(&self.settings, command).await {
+            Some(text) => Ok(serde_json::json!({"explanation": text})),
+            None => Err(rpc_error(GENERATION_FAILED, "could not get an explanation")),
+        }
+    }
+}
+
+fn required_str<'a>(params: &'a JsonValue, name: &str) -> Result<&'a str, RpcError> {
+    params[name]
+        .as_str()
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| rpc_error(INVALID_PARAMS, format!("missing string param `{}`", name)))
+}
+
+// params: {"limit": 20, "query": "..."}; newest first.
+fn history(params: &JsonValue) -> Result<JsonValue, RpcError> {
+    let limit = params["limit"]
+        .as_u64()
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let query = params["query"].as_str().map(|q| q.to_lowercase());
+    let entries = load_history().map_err(|e| rpc_error(GENERATION_FAILED, e.to_string()))?;
+    let matching: Vec<_> = entries
+        .iter()
+        .rev()
+        .filter(|e| match &query {
+            Some(q) => e.prompt.to_lowercase().contains(q) || e.command.to_lowercase().contains(q),
+            None => true,
+        })
+        .take(limit)
+        .collect();
+    Ok(serde_json::to_value(matching).unwrap_or_default())
+}
+
+pub async fn run(matches: &ArgMatches, root: &ArgMatches) -> Result<()> {
+    let server = Arc::new(Server::new(matches, root));
+    warm_up(&server.settings);
+    match matches.get_one::<SocketAddr>("http") {
+        Some(addr) => serve_http(server, *addr, token(matches)).await,
+        None => serve_stdio(server).await,
+    }
+}
+
+// Requests are answered concurrently, so a slow generation does not hold up a history
+// lookup; clients match responses by id.
+async fn serve_stdio(server: Arc<Server>) -> Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut tasks = tokio::task::JoinSet::new();
+    while let Some(line) = rx.recv().await {
+        // Reap finished requests so a long session does not keep one handle per line
+        while tasks.try_join_next().is_some() {}
+        if line.trim().is_empty() {
+            continue;
+        }
+        let server = server.clone();
+        tasks.spawn(async move {
+            if let Some(response) = handle_line(&server, &line).await {
+                let mut stdout = std::io::stdout().lock();
+                let _ = writeln!(stdout, "{}", response);
+                let _ = stdout.flush();
+            }
+        });
+    }
+    // Stdin closed: finish what is in flight, then exit.
+    while tasks.join_next().await.is_some() {}
+    Ok(())
+}
+
+// None for notifications (no id), which get no response.
+async fn handle_line(server: &Server, line: &str) -> Option<JsonValue> {
+    let request: JsonValue = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return Some(error_response(JsonValue::Null, PARSE_ERROR, &e.to_string())),
+    };
+    let id = request.get("id").cloned();
+    let method = match request["method"].as_str() {
+        Some(m) => m,
+        None => {
+            return Some(error_response(
+                id.unwrap_or(JsonValue::Null),
+                INVALID_REQUEST,
+                "missing method",
+            ))
+        }
+    };
+    let result = server.call(method, &request["params"]).await;
+    let id = id?;
+    Some(match result {
+        Ok(v) => serde_json::json!({"jsonrpc": "2.0", "id": id, "result": v}),
+        Err(e) => error_response(id, e.code, &e.message),
+    })
+}
+
+fn error_response(id: JsonValue, code: i64, message: &str) -> JsonValue {
+    serde_json::json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+// --token, then SNAPSHELL_SERVE_TOKEN, else a fresh random one the operator copies from stderr.
+fn token(matches: &ArgMatches) -> String {
+    if let Some(t) = matches
+        .get_one::<String>("token")
+        .cloned()
+        .or_else(|| std::env::var(TOKEN_ENV).ok())
+        .filter(|t| !t.is_empty())
+    {
+        return t;
+    }
+    // 32 bytes from the OS random source; the token guards running commands, so it must
+    // not be guessable from the start time or pid.
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).expect("OS random source");
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    eprintln!("API token (set {} to choose one): {}", TOKEN_ENV, token);
+    token
+}
+
+async fn serve_http(server: Arc<Server>, addr: SocketAddr, token: String) -> Result<()> {
+    if !addr.ip().is_loopback() {
+        eprintln!(
+            "Warning: listening on {}, reachable from other machines; requests still need the token.",
+            addr
+        );
+    }
+    let token = Arc::new(token);
+    let make = make_service_fn(move |_| {
+        let server = server.clone();
+        let token = token.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let server = server.clone();
+                let token = token.clone();
+                async move { Ok::<_, Infallible>(route(&server, &token, req).await) }
+            }))
+        }
+    });
+    let listener = hyper::Server::try_bind(&addr).with_context(|| format!("binding {}", addr))?;
+    eprintln!("Serving on http://{}", addr);
+    listener.serve(make).await?;
+    Ok(())
+}
+
+// GET /healthz, POST /v1/generate, POST /v1/explain, GET /v1/history?limit=&query=
+async fn route(server: &Server, token: &str, req: Request<Body>) -> Response<Body> {
+    if req.method() == Method::GET && req.uri().path() == "/healthz" {
+        return json_response(StatusCode::OK, serde_json::json!({"ok": true}));
+    }
+    if !authorized(&req, token) {
+        return error_json(rpc_error(UNAUTHORIZED, "missing or wrong bearer token"));
+    }
+
+    let method = match (req.method(), req.uri().path()) {
+        (&Method::POST, "/v1/generate") => "generate",
+        (&Method::POST, "/v1/explain") => "explain",
+        (&Method::GET, "/v1/history") => "history",
+        _ => {
+            return error_json(rpc_error(
+                METHOD_NOT_FOUND,
+                format!("no route {} {}", req.method(), req.uri().path()),
+            ))
+        }
+    };
+    let params = if req.method() == Method::GET {
+        query_params(req.uri().query().unwrap_or_default())
+    } else {
+        match read_json(req).await {
+            Ok(v) => v,
+            Err(e) => return error_json(e),
+        }
+    };
+    match server.call(method, &params).await {
+        Ok(v) => json_response(StatusCode::OK, v),
+        Err(e) => error_json(e),
+    }
+}
+
+fn authorized(req: &Request<Body>, token: &str) -> bool {
+    let sent = req
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // Compare every byte so the time taken does not reveal how much of the token matched.
+    sent.len() == token.len()
+        && sent
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+// Read up to MAX_BODY_BYTES and no further: a declared length over it is refused before
+// reading, and a chunked body is cut off as soon as it passes it.
+async fn read_json(req: Request<Body>) -> Result<JsonValue, RpcError> {
+    let too_large = || rpc_error(INVALID_REQUEST, "request body too large");
+    let declared = req
+        .headers()
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|n| n > MAX_BODY_BYTES as u64) {
+        return Err(too_large());
+    }
+    let mut stream = req.into_body();
+    let mut body = Vec::new();
+    while let Some(chunk) = stream.data().await {
+        let chunk = chunk.map_err(|e| rpc_error(INVALID_REQUEST, e.to_string()))?;
+        if body.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    serde_json::from_slice(&body).map_err(|e| rpc_error(PARSE_ERROR, e.to_string()))
+}
+
+// limit and query for /v1/history; values are percent-decoded, numbers kept as numbers.
+fn query_params(query: &str) -> JsonValue {
+    let mut params = serde_json::Map::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        let v = percent_decode(v);
+        let value = match v.parse::<u64>() {
+            Ok(n) => JsonValue::from(n),
+            Err(_) => JsonValue::from(v),
+        };
+        params.insert(percent_decode(k), value);
+    }
+    JsonValue::Object(params)
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn error_json(e: RpcError) -> Response<Body> {
+    let status = match e.code {
+        PARSE_ERROR | INVALID_REQUEST | INVALID_PARAMS => StatusCode::BAD_REQUEST,
+        METHOD_NOT_FOUND => StatusCode::NOT_FOUND,
+        COMMAND_REFUSED => StatusCode::FORBIDDEN,
+        UNAUTHORIZED => StatusCode::UNAUTHORIZED,
+        _ => StatusCode::BAD_GATEWAY,
+    };
+    json_response(
+        status,
+        serde_json::json!({"error": {"code": e.code, "message": e.message}}),
+    )
+}
+
+fn json_response(status: StatusCode, body: JsonValue) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("valid response")
+}
@@ -0,0 +1,348 @@
+// Saved chat sessions (`ss -a`) and `ss chat list|export`, which renders one as Markdown or
+// HTML for handoffs and postmortems.
+use anyhow::{anyhow, Result};
+use chrono::{Local, Utc};
+use clap::{Arg, ArgMatches, Command};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+
+use crate::paths::state_dir;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Turn {
+    pub prompt: String,
+    pub response: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<JsonValue>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub started: String,
+    pub model: String,
+    pub turns: Vec<Turn>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<Branch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Stats>,
+}
+
+// Spend and latency over every turn of a chat, branches included.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Stats {
+    pub turns: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    // Part of the cost was worked out from list prices rather than reported
+    #[serde(default)]
+    pub cost_estimated: bool,
+    pub total_latency_ms: u64,
+}
+
+impl Stats {
+    pub fn summary(&self) -> String {
+        let average = self.total_latency_ms as f64 / self.turns.max(1) as f64 / 1000.0;
+        format!(
+            "{} turns, {} tokens ({} in, {} out), {}{:.4} credits, average latency {:.1}s",
+            self.turns,
+            self.prompt_tokens + self.completion_tokens,
+            self.prompt_tokens,
+            self.completion_tokens,
+            if self.cost_estimated { "~" } else { "" },
+            self.cost,
+            average
+        )
+    }
+}
+
+// A line of conversation forked off with /branch. It shares the parent's first `fork`
+// turns and only its own turns are stored.
+#[derive(Serialize, Deserialize)]
+pub struct Branch {
+    pub name: String,
+    pub parent: String,
+    pub fork: usize,
+    pub turns: Vec<Turn>,
+}
+
+pub fn command() -> Command {
+    Command::new("chat")
+        .about("List saved chat sessions or export one as Markdown or HTML")
+        .after_help("Examples:\n  ss chat list\n  ss chat export last -o incident.md\n  ss chat export 20250302-141503 --format html")
+        .subcommand_required(true)
+        .subcommand(Command::new("list").about("List saved chat sessions, newest last"))
+        .subcommand(
+            Command::new("export")
+                .about("Render a chat session with its prompts, answers, and reasoning")
+                .arg(
+                    Arg::new("session")
+                        .help("Session id from `ss chat list`, or `last`")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Write to this file instead of stdout; .html/.htm selects HTML")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format (default: from the file extension, else md)")
+                        .num_args(1)
+                        .value_parser(["md", "html"]),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", _)) => list(),
+        Some(("export", sub)) => {
+            let id = sub.get_one::<String>("session").expect("required");
+            let session = load(id)?;
+            let output = sub.get_one::<PathBuf>("output");
+            let html = match sub.get_one::<String>("format") {
+                Some(f) => f == "html",
+                None => output.map(|p| is_html(p)).unwrap_or(false),
+            };
+            let doc = render(&session, html);
+            match output {
+                Some(path) => {
+                    std::fs::write(path, doc)?;
+                    eprintln!("Exported session {} to {}.", session.id, path.display());
+                }
+                None => print!("{}", doc),
+            }
+            Ok(())
+        }
+        _ => unreachable!("subcommand required"),
+    }
+}
+
+fn sessions_dir() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("chats"))
+}
+
+impl Session {
+    pub fn new(model: &str) -> Session {
+        Session {
+            // The process id keeps two chats started in the same second apart
+            id: format!(
+                "{}-{}",
+                Local::now().format("%Y%m%d-%H%M%S"),
+                std::process::id()
+            ),
+            started: Utc::now().to_rfc3339(),
+            model: model.to_string(),
+            turns: Vec::new(),
+            branches: Vec::new(),
+            stats: None,
+        }
+    }
+
+    // Rewritten after every turn so an interrupted chat is still on disk.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = sessions_dir() {
+            std::fs::create_dir_all(&dir)?;
+            let path = dir.join(format!("{}.json", self.id));
+            std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        }
+        Ok(())
+    }
+
+    // `/export <file>` from inside the chat.
+    pub fn export(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, render(self, is_html(path)))?;
+        Ok(())
+    }
+}
+
+fn is_html(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("html") | Some("htm")
+    )
+}
+
+// Session ids sort by start time, so the last file name is the newest session.
+fn session_files() -> Result<Vec<PathBuf>> {
+    let dir = match sessions_dir() {
+        Some(d) if d.exists() => d,
+        _ => return Ok(Vec::new()),
+    };
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+pub fn load(id: &str) -> Result<Session> {
+    let files = session_files()?;
+    let path = if id == "last" {
+        files.last().cloned()
+    } else {
+        files
+            .into_iter()
+            .find(|p| p.file_stem().and_then(|s| s.to_str()) == Some(id))
+    };
+    let path = path.ok_or_else(|| anyhow!("no chat session {}; see `ss chat list`", id))?;
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+fn list() -> Result<()> {
+    let files = session_files()?;
+    if files.is_empty() {
+        println!("no chat sessions");
+        return Ok(());
+    }
+    for path in files {
+        let session: Session = match std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+        {
+            Some(s) => s,
+            None => continue,
+        };
+        let first = session
+            .turns
+            .first()
+            .map(|t| t.prompt.lines().next().unwrap_or_default().to_string())
+            .unwrap_or_default();
+        let cost = session
+            .stats
+            .map(|s| format!("{:.4}", s.cost))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{}  {:>3} turns  {:>8} credits  {}  {}",
+            session.id,
+            session.turns.len()
+                + session
+                    .branches
+                    .iter()
+                    .map(|b| b.turns.len())
+                    .sum::<usize>(),
+            cost,
+            session.model,
+            first
+        );
+    }
+    Ok(())
+}
+
+// Reasoning arrives as a string or as provider-specific JSON; show strings as prose.
+fn reasoning_text(r: &JsonValue) -> String {
+    match r.as_str() {
+        Some(s) => s.to_string(),
+        None => serde_json::to_string_pretty(r).unwrap_or_else(|_| r.to_string()),
+    }
+}
+
+fn render(session: &Session, html: bool) -> String {
+    if html {
+        render_html(session)
+    } else {
+        render_markdown(session)
+    }
+}
+
+fn render_markdown(s: &Session) -> String {
+    let mut out = format!(
+        "# snapshell chat {}\n\n- Started: {}\n- Model: {}\n",
+        s.id, s.started, s.model
+    );
+    if let Some(stats) = &s.stats {
+        out.push_str(&format!("- Usage: {}\n", stats.summary()));
+    }
+    markdown_turns(&mut out, &s.turns, 0);
+    for b in &s.branches {
+        out.push_str(&format!(
+            "\n---\n\n# Branch {} (from {} after turn {})\n",
+            b.name, b.parent, b.fork
+        ));
+        markdown_turns(&mut out, &b.turns, b.fork);
+    }
+    out
+}
+
+fn markdown_turns(out: &mut String, turns: &[Turn], offset: usize) {
+    for (i, t) in turns.iter().enumerate() {
+        out.push_str(&format!("\n## {}. Prompt\n\n", offset + i + 1));
+        for line in t.prompt.lines() {
+            out.push_str(&format!("> {}\n", line));
+        }
+        // Answers often hold commands or psql-style tables, which Markdown would reflow.
+        let answer = t.response.trim();
+        let f = fence(answer);
+        out.push_str(&format!("\n### Answer\n\n{f}\n{answer}\n{f}\n"));
+        if let Some(r) = &t.reasoning {
+            let reasoning = reasoning_text(r);
+            let reasoning = reasoning.trim();
+            let f = fence(reasoning);
+            out.push_str(&format!(
+                "\n<details><summary>Reasoning</summary>\n\n{f}\n{reasoning}\n{f}\n\n</details>\n"
+            ));
+        }
+    }
+}
+
+// A backtick fence longer than any backtick run in `text`, so fenced blocks inside an
+// answer do not close the one around it.
+fn fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(s: &Session) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>snapshell chat {id}</title>\n<style>\nbody {{ font-family: sans-serif; max-width: 50em; margin: 2em auto; }}\nblockquote {{ border-left: 3px solid #ccc; margin: 0; padding-left: 1em; }}\npre {{ background: #f4f4f4; padding: 0.75em; overflow-x: auto; }}\n</style>\n</head>\n<body>\n<h1>snapshell chat {id}</h1>\n<p>Started: {started}<br>Model: {model}</p>\n",
+        id = escape(&s.id),
+        started = escape(&s.started),
+        model = escape(&s.model)
+    );
+    html_turns(&mut out, &s.turns, 0);
+    for b in &s.branches {
+        out.push_str(&format!(
+            "<hr>\n<h1>Branch {} (from {} after turn {})</h1>\n",
+            escape(&b.name),
+            escape(&b.parent),
+            b.fork
+        ));
+        html_turns(&mut out, &b.turns, b.fork);
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_turns(out: &mut String, turns: &[Turn], offset: usize) {
+    for (i, t) in turns.iter().enumerate() {
+        out.push_str(&format!(
+            "<h2>{}. Prompt</h2>\n<blockquote>{}</blockquote>\n<h3>Answer</h3>\n<pre>{}</pre>\n",
+            offset + i + 1,
+            escape(&t.prompt).replace('\n', "<br>\n"),
+            escape(t.response.trim())
+        ));
+        if let Some(r) = &t.reasoning {
+            out.push_str(&format!(
+                "<details><summary>Reasoning</summary>\n<pre>{}</pre>\n</details>\n",
+                escape(reasoning_text(r).trim())
+            ));
+        }
+    }
+}
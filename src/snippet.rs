@@ -0,0 +1,216 @@
+// Saved named prompts, invoked as `ss @name`.
+use anyhow::{bail, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::paths::config_dir;
+use crate::registry::{self, registry};
+use crate::template::placeholders;
+
+pub fn command() -> Command {
+    Command::new("snippet")
+        .about("Manage saved prompts, invoked with `ss @name`")
+        .after_help("Examples:\n  ss snippet save deploy \"build the image tagged with the git sha and push to {{registry}}\"\n  ss @deploy --var registry=ghcr.io/acme\n  ss snippet list\n  ss snippet sync")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("save")
+                .about("Save a prompt under a name ({{placeholders}} allowed)")
+                .arg(Arg::new("name").index(1).required(true))
+                .arg(Arg::new("prompt").index(2).required(true))
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Replace an existing snippet with the same name")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(Command::new("list").about("List saved snippets"))
+        .subcommand(
+            Command::new("show")
+                .about("Print a snippet's prompt")
+                .arg(Arg::new("name").index(1).required(true)),
+        )
+        .subcommand(
+            Command::new("edit")
+                .about("Edit a snippet in $EDITOR")
+                .arg(Arg::new("name").index(1).required(true)),
+        )
+        .subcommand(
+            Command::new("rm")
+                .about("Delete a snippet")
+                .arg(Arg::new("name").index(1).required(true)),
+        )
+        .subcommand(Command::new("sync").about(
+            "Pull the team registry set in config.toml (snippets, personas, system instructions)",
+        ))
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    if let Some(("sync", _)) = matches.subcommand() {
+        let r = registry::sync().await?;
+        eprintln!(
+            "Synced {} snippet(s) and {} persona(s) from {}",
+            r.snippets.len(),
+            r.personas.len(),
+            r.source.as_deref().unwrap_or_default()
+        );
+        return Ok(());
+    }
+    let mut snippets = load()?;
+    let name = |m: &ArgMatches| m.get_one::<String>("name").cloned().unwrap_or_default();
+
+    match matches.subcommand() {
+        Some(("save", m)) => {
+            let n = name(m);
+            validate_name(&n)?;
+            if snippets.contains_key(&n) && !m.get_flag("force") {
+                bail!("snippet `{}` already exists; pass --force to replace it", n);
+            }
+            let prompt = m.get_one::<String>("prompt").cloned().unwrap_or_default();
+            snippets.insert(n.clone(), prompt);
+            store(&snippets)?;
+            eprintln!("Saved snippet `{}`; run it with ss @{}", n, n);
+        }
+        Some(("list", _)) => {
+            let shared: Vec<_> = registry()
+                .snippets
+                .iter()
+                .filter(|(n, _)| !snippets.contains_key(*n))
+                .collect();
+            if snippets.is_empty() && shared.is_empty() {
+                println!("no snippets");
+            }
+            for (n, prompt) in &snippets {
+                print_entry(n, prompt, "");
+            }
+            for (n, prompt) in shared {
+                print_entry(n, prompt, "  (team)");
+            }
+        }
+        Some(("show", m)) => println!("{}", get(&snippets, &name(m))?),
+        Some(("edit", m)) => {
+            let n = name(m);
+            refuse_shared(&snippets, &n)?;
+            let current = get(&snippets, &n)?.to_string();
+            let edited = edit_in_editor(&current)?;
+            if edited.is_empty() {
+                bail!("empty prompt; snippet `{}` left unchanged", n);
+            }
+            snippets.insert(n.clone(), edited);
+            store(&snippets)?;
+            eprintln!("Updated snippet `{}`", n);
+        }
+        Some(("rm", m)) => {
+            let n = name(m);
+            refuse_shared(&snippets, &n)?;
+            if snippets.remove(&n).is_none() {
+                bail!("no snippet named `{}`", n);
+            }
+            store(&snippets)?;
+            eprintln!("Deleted snippet `{}`", n);
+        }
+        _ => unreachable!("subcommand required"),
+    }
+    Ok(())
+}
+
+// Replace an `@name` prompt with the saved snippet; other prompts pass through.
+pub fn resolve(prompt: &str) -> Result<String> {
+    let name = match prompt.strip_prefix('@') {
+        Some(n) if !n.is_empty() && !n.contains(char::is_whitespace) => n,
+        _ => return Ok(prompt.to_string()),
+    };
+    let snippets = load()?;
+    Ok(get(&snippets, name)?.to_string())
+}
+
+fn print_entry(name: &str, prompt: &str, tag: &str) {
+    let vars = placeholders(prompt);
+    if vars.is_empty() {
+        println!("@{}  {}{}", name, prompt, tag);
+    } else {
+        println!("@{}  {}  (vars: {}){}", name, prompt, vars.join(", "), tag);
+    }
+}
+
+// Snippets that exist only in the team registry cannot be changed here.
+fn refuse_shared(snippets: &BTreeMap<String, String>, name: &str) -> Result<()> {
+    if !snippets.contains_key(name) && registry().snippets.contains_key(name) {
+        bail!(
+            "`{}` comes from the team registry, which is read-only; `ss snippet save {} ...` makes a local one that takes its place",
+            name,
+            name
+        );
+    }
+    Ok(())
+}
+
+// Local snippets first, then the team registry.
+fn get<'a>(snippets: &'a BTreeMap<String, String>, name: &str) -> Result<&'a str> {
+    match snippets.get(name).or_else(|| registry().snippets.get(name)) {
+        Some(p) => Ok(p),
+        None => bail!("no snippet named `{}` (see `ss snippet list`)", name),
+    }
+}
+
+pub fn validate_name(name: &str) -> Result<()> {
+    let ok = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !ok {
+        bail!("snippet names may only contain letters, digits, '-', '_' and '.'");
+    }
+    Ok(())
+}
+
+fn snippets_path() -> Option<PathBuf> {
+    config_dir().map(|d| d.join("snippets.json"))
+}
+
+pub fn load() -> Result<BTreeMap<String, String>> {
+    match snippets_path() {
+        Some(p) if p.exists() => Ok(serde_json::from_str(&std::fs::read_to_string(p)?)?),
+        _ => Ok(BTreeMap::new()),
+    }
+}
+
+fn store(snippets: &BTreeMap<String, String>) -> Result<()> {
+    let path = match snippets_path() {
+        Some(p) => p,
+        None => bail!("could not determine the config directory"),
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(snippets)? + "\n")?;
+    Ok(())
+}
+
+fn edit_in_editor(current: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Created exclusively with a random name, and removed on drop whatever happens below
+    let mut tmp = tempfile::Builder::new()
+        .prefix("snapshell-snippet-")
+        .suffix(".txt")
+        .tempfile()?;
+    tmp.write_all(current.as_bytes())?;
+    tmp.flush()?;
+    // $EDITOR may carry arguments (e.g. "code --wait"), so let the shell split it
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(tmp.path())
+        .status()?;
+    // Read by path: editors often save by replacing the file rather than writing into it
+    let edited = std::fs::read_to_string(tmp.path())?;
+    if !status.success() {
+        bail!("editor exited with {}", status);
+    }
+    Ok(edited.trim().to_string())
+}
@@ -0,0 +1,95 @@
+// `ss status`: is OpenRouter reachable, and what is left on each configured key. Unlike
+// `ss doctor` it sends no completion, so it costs nothing and can run in a prompt or a
+// monitoring check.
+use anyhow::{bail, Result};
+use clap::{ArgMatches, Command};
+use reqwest::header::HeaderMap;
+use serde_json::Value as JsonValue;
+
+use crate::llm::{get_json, key_label, Settings, AUTH_KEY_URL, CREDITS_URL};
+
+pub fn command() -> Command {
+    Command::new("status")
+        .about("Check OpenRouter latency and each key's identity, credits, and rate limit")
+        .after_help("Examples:\n  ss status\n  ss status --profile work")
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let settings = Settings::from_matches(matches);
+    if settings.offline {
+        bail!("offline mode is on; unset SNAPSHELL_OFFLINE to contact OpenRouter");
+    }
+    if settings.api_keys.is_empty() {
+        bail!("no API key configured; export SNAPSHELL_OPENROUTER_API_KEY=sk-or-...");
+    }
+    let mut failed = 0;
+    for key in &settings.api_keys {
+        println!("key {}", key_label(key));
+        match get_json(AUTH_KEY_URL, key).await {
+            Ok((body, headers, latency)) => {
+                println!("  latency     {} ms", latency.as_millis());
+                print_key(&body["data"]);
+                print_headroom(&body["data"], &headers);
+            }
+            Err(e) => {
+                failed += 1;
+                println!("  error       {:#}", e);
+                continue;
+            }
+        }
+        // Account-wide credits need more than a plain inference key on some accounts.
+        if let Ok((body, _, _)) = get_json(CREDITS_URL, key).await {
+            let data = &body["data"];
+            if let (Some(total), Some(used)) =
+                (data["total_credits"].as_f64(), data["total_usage"].as_f64())
+            {
+                println!(
+                    "  account     {:.4} of {:.4} credits left",
+                    total - used,
+                    total
+                );
+            }
+        }
+    }
+    if failed > 0 {
+        bail!(
+            "{} of {} keys could not be checked",
+            failed,
+            settings.api_keys.len()
+        );
+    }
+    Ok(())
+}
+
+fn print_key(data: &JsonValue) {
+    if let Some(label) = data["label"].as_str() {
+        println!("  identity    {}", label);
+    }
+    if data["is_free_tier"].as_bool() == Some(true) {
+        println!("  tier        free (no credits purchased)");
+    }
+    let used = data["usage"].as_f64().unwrap_or(0.0);
+    match (data["limit"].as_f64(), data["limit_remaining"].as_f64()) {
+        (Some(limit), remaining) => println!(
+            "  credits     {:.4} used, {:.4} left of a {:.4} key limit",
+            used,
+            remaining.unwrap_or(limit - used),
+            limit
+        ),
+        (None, _) => println!("  credits     {:.4} used, no key limit", used),
+    }
+}
+
+// OpenRouter reports the key's request allowance in the body; a provider in front of it
+// may also send the usual x-ratelimit-* headers, which show what is left right now.
+fn print_headroom(data: &JsonValue, headers: &HeaderMap) {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let (Some(remaining), Some(limit)) =
+        (header("x-ratelimit-remaining"), header("x-ratelimit-limit"))
+    {
+        println!("  rate limit  {} of {} requests left", remaining, limit);
+    } else if let Some(requests) = data["rate_limit"]["requests"].as_i64() {
+        let interval = data["rate_limit"]["interval"].as_str().unwrap_or("?");
+        println!("  rate limit  {} requests per {}", requests, interval);
+    }
+}
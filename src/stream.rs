@@ -0,0 +1,127 @@
+// Streamed responses, used so `-S` can show the model's reasoning while it arrives: the
+// latest reasoning text is kept on one dimmed status line on stderr, which is cleared
+// before the answer is printed. The events are folded back into the JSON shape of a
+// normal response, so everything after `send` treats both alike.
+use anyhow::{bail, Result};
+use serde_json::Value as JsonValue;
+use std::io::{self, Write};
+
+use crate::term;
+
+// Width of the status line when the terminal's is unknown.
+const DEFAULT_WIDTH: usize = 80;
+
+// The status line, cleared when reading stops for any reason (including --max-wait
+// dropping the request).
+struct Ticker {
+    width: usize,
+    dim: bool,
+    shown: bool,
+}
+
+impl Ticker {
+    fn new() -> Ticker {
+        Ticker {
+            width: term::caps().width.unwrap_or(DEFAULT_WIDTH),
+            dim: term::stderr_color(),
+            shown: false,
+        }
+    }
+
+    // Show the end of `text` on one line, never wider than the terminal.
+    fn show(&mut self, text: &str) {
+        let flat: String = text
+            .chars()
+            .map(|c| if c.is_whitespace() { ' ' } else { c })
+            .collect();
+        let room = self.width.saturating_sub("thinking: ".len() + 1);
+        let chars: Vec<char> = flat.chars().collect();
+        let tail: String = chars[chars.len().saturating_sub(room)..].iter().collect();
+        if self.dim {
+            eprint!("\r\x1b[2K\x1b[2mthinking: {}\x1b[0m", tail);
+        } else {
+            eprint!("\r\x1b[2Kthinking: {}", tail);
+        }
+        let _ = io::stderr().flush();
+        self.shown = true;
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        if self.shown {
+            eprint!("\r\x1b[2K");
+            let _ = io::stderr().flush();
+        }
+    }
+}
+
+// Read a server-sent event stream of chat completion chunks and return the equivalent
+// non-streamed response body.
+pub async fn read(mut resp: reqwest::Response) -> Result<String> {
+    let mut ticker = Ticker::new();
+    let mut pending = Vec::new();
+    let mut content = String::new();
+    let mut reasoning = String::new();
+    let mut finish_reason = JsonValue::Null;
+    let mut provider = JsonValue::Null;
+    let mut model = JsonValue::Null;
+    let mut usage = JsonValue::Null;
+    'read: while let Some(chunk) = resp.chunk().await? {
+        pending.extend_from_slice(&chunk);
+        while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            // Lines starting with ':' are keep-alive comments.
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                break 'read;
+            }
+            let Ok(event) = serde_json::from_str::<JsonValue>(data) else {
+                continue;
+            };
+            if let Some(err) = event.get("error") {
+                bail!("the stream ended with an error: {}", err);
+            }
+            if let Some(p) = event.get("provider").filter(|p| !p.is_null()) {
+                provider = p.clone();
+            }
+            if let Some(m) = event.get("model").filter(|m| !m.is_null()) {
+                model = m.clone();
+            }
+            if let Some(u) = event.get("usage").filter(|u| !u.is_null()) {
+                usage = u.clone();
+            }
+            let choice = &event["choices"][0];
+            if let Some(f) = choice.get("finish_reason").filter(|f| !f.is_null()) {
+                finish_reason = f.clone();
+            }
+            let delta = &choice["delta"];
+            if let Some(text) = delta["content"].as_str() {
+                content.push_str(text);
+            }
+            let thought = delta["reasoning"]
+                .as_str()
+                .or_else(|| delta["reasoning_content"].as_str());
+            if let Some(text) = thought {
+                reasoning.push_str(text);
+                ticker.show(&reasoning);
+            }
+        }
+    }
+    let message = if reasoning.is_empty() {
+        serde_json::json!({"content": content})
+    } else {
+        serde_json::json!({"content": content, "reasoning": reasoning})
+    };
+    Ok(serde_json::json!({
+        "provider": provider,
+        "model": model,
+        "choices": [{"message": message, "finish_reason": finish_reason}],
+        "usage": usage,
+    })
+    .to_string())
+}
@@ -0,0 +1,77 @@
+// `{{name}}` placeholders in prompts, filled from --var name=value or asked for interactively.
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::sync::OnceLock;
+
+use crate::output::deterministic;
+
+// A placeholder, with any spaces inside the braces: `{{name}}`, `{{ name }}`, `{{name }}`.
+// Listing and filling share it, so every placeholder listed is also filled.
+fn pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z0-9_-]+)\s*\}\}").unwrap())
+}
+
+// Unique placeholder names in order of first appearance.
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for c in pattern().captures_iter(template) {
+        let name = &c[1];
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+// Placeholders without a value are left as they are.
+pub fn fill(template: &str, vars: &HashMap<String, String>) -> String {
+    pattern()
+        .replace_all(template, |c: &regex::Captures| match vars.get(&c[1]) {
+            Some(value) => value.clone(),
+            None => c[0].to_string(),
+        })
+        .into_owned()
+}
+
+// Parse repeated `--var key=value` arguments.
+pub fn vars_from(matches: &ArgMatches) -> Result<HashMap<String, String>> {
+    let mut vars = HashMap::new();
+    if let Some(values) = matches.get_many::<String>("var") {
+        for v in values {
+            match v.split_once('=') {
+                Some((k, val)) => {
+                    vars.insert(k.trim().to_string(), val.to_string());
+                }
+                None => bail!("--var expects name=value, got `{}`", v),
+            }
+        }
+    }
+    Ok(vars)
+}
+
+// Fill every placeholder in `prompt`, asking on the terminal for any not given with --var.
+pub fn apply(prompt: &str, matches: &ArgMatches) -> Result<String> {
+    let names = placeholders(prompt);
+    if names.is_empty() {
+        return Ok(prompt.to_string());
+    }
+    let mut vars = vars_from(matches)?;
+    for name in names {
+        if vars.contains_key(&name) {
+            continue;
+        }
+        if !io::stdin().is_terminal() || deterministic() {
+            bail!("no value for {{{{{}}}}}; pass --var {}=...", name, name);
+        }
+        eprint!("{}: ", name);
+        io::stderr().flush()?;
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+        vars.insert(name, line.trim().to_string());
+    }
+    Ok(fill(prompt, &vars))
+}
@@ -0,0 +1,242 @@
+// What the terminal can show: its width, how many colors, and whether it understands
+// OSC 8 hyperlinks. A command printed to a terminal is wrapped to fit (with backslash
+// continuations, so a copied selection still runs) and, with --hyperlinks, the files it
+// names become links. Piped output and --plain get the command untouched.
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::modes::capture;
+use crate::output::plain;
+
+// Continuation lines are indented this much.
+const INDENT: &str = "  ";
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Colors {
+    None,
+    Basic,
+    Ansi256,
+    TrueColor,
+}
+
+impl Colors {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Colors::None => "no color",
+            Colors::Basic => "16 colors",
+            Colors::Ansi256 => "256 colors",
+            Colors::TrueColor => "24-bit color",
+        }
+    }
+}
+
+pub struct Caps {
+    pub width: Option<usize>,
+    pub colors: Colors,
+    pub hyperlinks: bool,
+}
+
+static CAPS: OnceLock<Caps> = OnceLock::new();
+static LINKS: OnceLock<bool> = OnceLock::new();
+static HOST: OnceLock<String> = OnceLock::new();
+
+pub fn init(matches: &clap::ArgMatches) {
+    let _ = LINKS.set(matches.get_flag("hyperlinks"));
+}
+
+pub fn caps() -> &'static Caps {
+    CAPS.get_or_init(|| Caps {
+        width: width(),
+        colors: colors(),
+        hyperlinks: hyperlinks(),
+    })
+}
+
+// Whether stderr may carry color escapes (the diff, the reasoning ticker).
+pub fn stderr_color() -> bool {
+    std::io::stderr().is_terminal() && !plain() && caps().colors > Colors::None
+}
+
+// $COLUMNS, else the size of the controlling terminal.
+fn width() -> Option<usize> {
+    if let Some(c) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(c);
+    }
+    let tty = std::fs::File::open("/dev/tty").ok()?;
+    let out = std::process::Command::new("stty")
+        .arg("size")
+        .stdin(tty)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    let size = String::from_utf8_lossy(&out.stdout);
+    size.split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+        .filter(|w| *w > 0)
+}
+
+fn colors() -> Colors {
+    let term = std::env::var("TERM").unwrap_or_default();
+    if std::env::var_os("NO_COLOR").is_some() || term == "dumb" {
+        return Colors::None;
+    }
+    let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        Colors::TrueColor
+    } else if term.contains("256color") {
+        Colors::Ansi256
+    } else {
+        Colors::Basic
+    }
+}
+
+// Terminals known to support OSC 8. Others would print the escapes' text, so unknown
+// terminals get no links.
+fn hyperlinks() -> bool {
+    let var = |v: &str| std::env::var(v).unwrap_or_default();
+    let program = var("TERM_PROGRAM");
+    let term = var("TERM");
+    matches!(
+        program.as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper" | "rio"
+    ) || ["kitty", "alacritty", "foot", "wezterm", "ghostty"]
+        .iter()
+        .any(|t| term.contains(t))
+        || var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5000)
+        || std::env::var_os("WT_SESSION").is_some()
+        || std::env::var_os("KITTY_WINDOW_ID").is_some()
+}
+
+// `command` as it should appear on a terminal on stdout; anywhere else it is unchanged.
+// Only single-line commands are wrapped or linked: re-spacing a multi-line one would
+// join its lines.
+pub fn display(command: &str) -> String {
+    if plain() || !std::io::stdout().is_terminal() || command.contains('\n') {
+        return command.to_string();
+    }
+    let caps = caps();
+    let links = LINKS.get().copied().unwrap_or(false) && caps.hyperlinks;
+    let words = words(command);
+    let wrap = caps
+        .width
+        .filter(|w| command.chars().count() > *w)
+        .filter(|_| words.is_some());
+    let Some(words) = words else {
+        return command.to_string();
+    };
+    if wrap.is_none() && !links {
+        return command.to_string();
+    }
+
+    let mut out = String::new();
+    let mut used = 0;
+    for (i, word) in words.iter().enumerate() {
+        let len = word.chars().count();
+        if let Some(width) = wrap {
+            // Leave room for the " \" that ends a wrapped line.
+            if i > 0 && used + 1 + len + 2 > width && used > INDENT.len() {
+                out.push_str(" \\\n");
+                out.push_str(INDENT);
+                used = INDENT.len();
+            } else if i > 0 {
+                out.push(' ');
+                used += 1;
+            }
+        } else if i > 0 {
+            out.push(' ');
+        }
+        match links.then(|| link(word)).flatten() {
+            Some(linked) => out.push_str(&linked),
+            None => out.push_str(word),
+        }
+        used += len;
+    }
+    out
+}
+
+// Split on unquoted whitespace, keeping quoted strings whole. None when the command has
+// a comment or unbalanced quotes, where re-spacing could change what it does.
+fn words(command: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for c in command.chars() {
+        if escaped {
+            word.push(c);
+            escaped = false;
+            continue;
+        }
+        match (quote, c) {
+            (None, '\\') | (Some('"'), '\\') => {
+                escaped = true;
+                word.push(c);
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.push(c);
+            }
+            (Some(q), _) if c == q => {
+                quote = None;
+                word.push(c);
+            }
+            (None, '#') if word.is_empty() => return None,
+            (None, _) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+    if quote.is_some() || escaped {
+        return None;
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Some(words)
+}
+
+// An OSC 8 link around `word` when it names an existing file or directory.
+fn link(word: &str) -> Option<String> {
+    let path = word.trim_matches(|c| c == '\'' || c == '"');
+    if path.is_empty() || path.starts_with('-') {
+        return None;
+    }
+    let path = Path::new(path);
+    if !path.exists() {
+        return None;
+    }
+    let abs = std::fs::canonicalize(path).ok()?;
+    let url: String = abs
+        .to_string_lossy()
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    Some(format!(
+        "\x1b]8;;file://{}{}\x1b\\{}\x1b]8;;\x1b\\",
+        hostname(),
+        url,
+        word
+    ))
+}
+
+// Links name the host so a terminal connected over SSH does not open a local file.
+fn hostname() -> &'static str {
+    HOST.get_or_init(|| {
+        std::env::var("HOSTNAME")
+            .ok()
+            .filter(|h| !h.is_empty())
+            .or_else(|| capture("hostname", &[]))
+            .unwrap_or_default()
+    })
+}
@@ -0,0 +1,267 @@
+// Local tools the model may call during generation (--tools), so commands are grounded
+// in what is actually installed and present instead of guessed. Programs run directly,
+// never through a shell. `help` runs only well-known programs, under a time limit, and
+// `ls` and `ffprobe` only see local files under the current directory.
+use serde_json::Value as JsonValue;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::env::{truncate, which};
+use crate::ignore::Ignore;
+use crate::modes::{capture, output_with_timeout};
+
+pub const ALL: [&str; 4] = ["which", "help", "ls", "ffprobe"];
+// Tool results are cut to this many bytes before they are sent back.
+const MAX_RESULT_BYTES: usize = 4000;
+const MAX_LS_ENTRIES: usize = 200;
+const HELP_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Programs `help` may run. Each prints its usage for --help and exits; an arbitrary
+// program might ignore the flag and do its job, or wait for a terminal.
+const HELP_PROGRAMS: &[&str] = &[
+    "7z",
+    "apt",
+    "apt-get",
+    "awk",
+    "aws",
+    "az",
+    "base64",
+    "brew",
+    "bzip2",
+    "cargo",
+    "cat",
+    "chmod",
+    "chown",
+    "convert",
+    "cp",
+    "curl",
+    "cut",
+    "date",
+    "df",
+    "dig",
+    "dnf",
+    "docker",
+    "du",
+    "fd",
+    "fdfind",
+    "ffmpeg",
+    "ffprobe",
+    "file",
+    "find",
+    "gcloud",
+    "gh",
+    "git",
+    "go",
+    "grep",
+    "gzip",
+    "head",
+    "helm",
+    "ip",
+    "jq",
+    "journalctl",
+    "kubectl",
+    "ln",
+    "ls",
+    "lsof",
+    "magick",
+    "make",
+    "mkdir",
+    "mv",
+    "mysql",
+    "node",
+    "npm",
+    "openssl",
+    "pacman",
+    "pg_dump",
+    "pip",
+    "pip3",
+    "pnpm",
+    "ps",
+    "psql",
+    "python3",
+    "rclone",
+    "rg",
+    "rsync",
+    "scp",
+    "sed",
+    "sort",
+    "sqlite3",
+    "ss",
+    "ssh",
+    "stat",
+    "systemctl",
+    "tail",
+    "tar",
+    "terraform",
+    "tr",
+    "uniq",
+    "unzip",
+    "wc",
+    "wget",
+    "xargs",
+    "xz",
+    "yarn",
+    "yq",
+    "zip",
+    "zstd",
+];
+
+// OpenAI-style function definitions for the enabled tools.
+pub fn definitions(enabled: &[String]) -> Vec<JsonValue> {
+    let string_param = |name: &str, description: &str| {
+        serde_json::json!({
+            "type": "object",
+            "properties": {name: {"type": "string", "description": description}},
+            "required": [name],
+        })
+    };
+    enabled
+        .iter()
+        .filter_map(|tool| {
+            let (description, parameters) = match tool.as_str() {
+                "which" => ("Find whether a program is installed and where, like `command -v`.", string_param("program", "Program name")),
+                "help" => ("Show the start of a program's --help output, to check which flags it supports.", string_param("program", "Program name")),
+                "ls" => ("List a directory's entries (directories end with /).", string_param("path", "Directory path; . for the current directory")),
+                "ffprobe" => ("Describe a media file's format and streams as JSON using ffprobe.", string_param("path", "Media file path")),
+                _ => return None,
+            };
+            Some(serde_json::json!({
+                "type": "function",
+                "function": {"name": tool, "description": description, "parameters": parameters},
+            }))
+        })
+        .collect()
+}
+
+// A bare program name, so `help` cannot be pointed at arbitrary paths or arguments.
+fn program_name(s: &str) -> Option<&str> {
+    let s = s.trim();
+    let ok = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'));
+    ok.then_some(s)
+}
+
+// An existing local path inside the current directory, resolved to an absolute path
+// (symlinks included, so a link cannot lead out). URLs, protocol inputs, and anything
+// starting with - are refused before the file system is asked.
+fn local_path(path: &str) -> Option<PathBuf> {
+    let path = path.trim();
+    if path.is_empty() || path.starts_with('-') || path.contains("://") {
+        return None;
+    }
+    if let Some((scheme, _)) = path.split_once(':') {
+        if !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+    }
+    let cwd = std::env::current_dir().ok()?.canonicalize().ok()?;
+    let resolved = cwd.join(path).canonicalize().ok()?;
+    resolved.starts_with(&cwd).then_some(resolved)
+}
+
+fn run(name: &str, args: &JsonValue) -> String {
+    let arg = |key: &str| args.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+    match name {
+        "which" => match program_name(arg("program")).and_then(which) {
+            Some(p) => p.display().to_string(),
+            None => "not installed".to_string(),
+        },
+        "help" => {
+            let Some(program) = program_name(arg("program")) else {
+                return "invalid program name".to_string();
+            };
+            if !HELP_PROGRAMS.contains(&program) {
+                return format!("help is not available for {}", program);
+            }
+            if which(program).is_none() {
+                return "not installed".to_string();
+            }
+            let mut cmd = std::process::Command::new(program);
+            match output_with_timeout(cmd.arg("--help"), HELP_TIMEOUT) {
+                Some(o) => {
+                    // Many programs print help on stderr
+                    let mut text = String::from_utf8_lossy(&o.stdout).into_owned();
+                    text.push_str(&String::from_utf8_lossy(&o.stderr));
+                    text
+                }
+                None => "failed to run or did not finish in time".to_string(),
+            }
+        }
+        "ls" => {
+            let path = if arg("path").is_empty() {
+                "."
+            } else {
+                arg("path")
+            };
+            let Some(dir) = local_path(path) else {
+                return "only directories under the current directory can be listed".to_string();
+            };
+            let dir = dir.as_path();
+            match std::fs::read_dir(dir) {
+                Ok(entries) => {
+                    let mut names: Vec<String> = entries
+                        .filter_map(|e| e.ok())
+                        .map(|e| {
+                            let name = e.file_name().to_string_lossy().into_owned();
+                            if e.path().is_dir() {
+                                name + "/"
+                            } else {
+                                name
+                            }
+                        })
+                        .collect();
+                    names.sort();
+                    let hidden = Ignore::for_dir(dir).retain_in(dir, &mut names);
+                    let total = names.len();
+                    names.truncate(MAX_LS_ENTRIES);
+                    let mut out = names.join("\n");
+                    if total > MAX_LS_ENTRIES {
+                        out.push_str(&format!("\n... ({} more)", total - MAX_LS_ENTRIES));
+                    }
+                    if hidden > 0 {
+                        out.push_str(&format!(
+                            "\n({} entries hidden by .gitignore or .snapshellignore)",
+                            hidden
+                        ));
+                    }
+                    out
+                }
+                Err(e) => format!("cannot list: {}", e),
+            }
+        }
+        "ffprobe" => {
+            // The absolute path cannot be read as an option or a protocol (http:, concat:).
+            let Some(file) = local_path(arg("path")).filter(|p| p.is_file()) else {
+                return "only files under the current directory can be probed".to_string();
+            };
+            capture(
+                "ffprobe",
+                &[
+                    "-v",
+                    "error",
+                    "-show_format",
+                    "-show_streams",
+                    "-of",
+                    "json",
+                    "-i",
+                    &file.to_string_lossy(),
+                ],
+            )
+            .unwrap_or_else(|| "ffprobe failed or is not installed".to_string())
+        }
+        _ => format!("unknown tool {}", name),
+    }
+}
+
+// Run one tool call for the model; `enabled` is re-checked so a model cannot call a
+// tool that was not offered.
+pub fn call(name: &str, arguments: &str, enabled: &[String]) -> String {
+    if !enabled.iter().any(|t| t == name) {
+        return format!("tool {} is not enabled", name);
+    }
+    let args: JsonValue = serde_json::from_str(arguments).unwrap_or(JsonValue::Null);
+    eprintln!("(tool) {} {}", name, arguments);
+    let out = run(name, &args);
+    truncate(&out, MAX_RESULT_BYTES).to_string()
+}
@@ -0,0 +1,86 @@
+// Catch misspelled tool names in a prompt ("gti", "dokcer") before it is sent, so the
+// model does not write a command for a tool that does not exist.
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::io::IsTerminal;
+
+use crate::modes::confirm;
+use crate::pathcheck::edit_distance;
+
+// Suffixes that turn a tool name into an ordinary word ("files", "sorted").
+const SUFFIXES: [&str; 4] = ["s", "es", "ed", "ing"];
+
+// Names of every executable on PATH.
+fn installed() -> BTreeSet<String> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return BTreeSet::new();
+    };
+    std::env::split_paths(&path)
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+        .map(|name| name.strip_suffix(".exe").unwrap_or(&name).to_string())
+        .collect()
+}
+
+// Whether `word` is a plausible typo of `tool`: two letters swapped ("gti"), or, in longer
+// words, one letter missing or extra ("kubctl"). Substitutions are left alone because one
+// changed letter usually makes another real word ("clean" and `clear`).
+fn is_typo(word: &str, tool: &str) -> bool {
+    if SUFFIXES.iter().any(|s| word.strip_suffix(s) == Some(tool)) || edit_distance(word, tool) != 1
+    {
+        return false;
+    }
+    if word.len() != tool.len() {
+        return word.len().min(tool.len()) >= 5 && word.chars().next() == tool.chars().next();
+    }
+    let mut a: Vec<char> = word.chars().collect();
+    let mut b: Vec<char> = tool.chars().collect();
+    a.sort_unstable();
+    b.sort_unstable();
+    a == b
+}
+
+// Pairs of (typo, installed tool) found in the prompt, each typo once.
+pub fn suggestions(prompt: &str) -> Vec<(String, String)> {
+    let tools = installed();
+    let mut out: Vec<(String, String)> = Vec::new();
+    for word in prompt.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_')) {
+        if !(3..=24).contains(&word.len())
+            || word.chars().any(|c| c.is_ascii_uppercase())
+            || word.chars().all(|c| c.is_ascii_digit())
+            || tools.contains(word)
+            || out.iter().any(|(w, _)| w == word)
+        {
+            continue;
+        }
+        if let Some(tool) = tools.iter().find(|t| t.len() >= 3 && is_typo(word, t)) {
+            out.push((word.to_string(), tool.clone()));
+        }
+    }
+    out
+}
+
+// Offer to fix each suspected typo; returns the prompt with the accepted corrections.
+// Without a terminal to ask on, the prompt is sent unchanged with a note.
+pub fn correct(prompt: String) -> String {
+    let found = suggestions(&prompt);
+    if found.is_empty() {
+        return prompt;
+    }
+    let interactive = std::io::stdin().is_terminal();
+    let mut out = prompt;
+    for (typo, tool) in found {
+        if !interactive {
+            eprintln!("Note: `{}` looks like a typo for `{}`.", typo, tool);
+            continue;
+        }
+        if !confirm(&format!("Did you mean `{}` instead of `{}`?", tool, typo)) {
+            continue;
+        }
+        let word = Regex::new(&format!(r"\b{}\b", regex::escape(&typo)))
+            .expect("escaped word is a valid regex");
+        out = word.replace_all(&out, tool.as_str()).into_owned();
+    }
+    out
+}
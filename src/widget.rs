@@ -0,0 +1,152 @@
+// `ss init <shell>`: a line-editor widget that turns the text on the command line into a
+// command in place. The widget runs snapshell with SNAPSHELL_RESULT_FILE set to a temp
+// file; the final command is written there instead of to the clipboard, and the widget
+// loads it into the editable buffer. `ss init --man` installs the man pages instead.
+use anyhow::{anyhow, Context, Result};
+use clap::{Arg, ArgMatches, Command};
+use std::path::{Path, PathBuf};
+
+use crate::paths::man_dir;
+
+pub const RESULT_FILE_ENV: &str = "SNAPSHELL_RESULT_FILE";
+
+// Whether the shell widget launched this run and is waiting for the result file.
+pub fn driving() -> bool {
+    std::env::var_os(RESULT_FILE_ENV).is_some_and(|p| !p.is_empty())
+}
+
+const ZSH: &str = r#"# snapshell widget: type a request, press Alt-s, edit the command, press Enter.
+_snapshell_widget() {
+  [[ -z $BUFFER ]] && return
+  local out
+  out=$(mktemp "${TMPDIR:-/tmp}/snapshell.XXXXXX") || return
+  zle -I
+  SNAPSHELL_RESULT_FILE="$out" SNAPSHELL_WIDGET_SESSION=$$ snapshell "$BUFFER" >/dev/null </dev/tty
+  if [[ -s $out ]]; then
+    BUFFER=$(<"$out")
+    CURSOR=${#BUFFER}
+  fi
+  rm -f "$out"
+  zle reset-prompt
+}
+zle -N _snapshell_widget
+bindkey '^[s' _snapshell_widget
+"#;
+
+const BASH: &str = r#"# snapshell widget: type a request, press Alt-s, edit the command, press Enter.
+_snapshell_widget() {
+  [[ -z $READLINE_LINE ]] && return
+  local out
+  out=$(mktemp "${TMPDIR:-/tmp}/snapshell.XXXXXX") || return
+  SNAPSHELL_RESULT_FILE="$out" SNAPSHELL_WIDGET_SESSION=$$ snapshell "$READLINE_LINE" >/dev/null </dev/tty
+  if [[ -s $out ]]; then
+    READLINE_LINE=$(<"$out")
+    READLINE_POINT=${#READLINE_LINE}
+  fi
+  rm -f "$out"
+}
+bind -x '"\es": _snapshell_widget'
+"#;
+
+const FISH: &str = r#"# snapshell widget: type a request, press Alt-s, edit the command, press Enter.
+function _snapshell_widget
+    set -l request (commandline)
+    test -z "$request"; and return
+    set -l out (mktemp); or return
+    SNAPSHELL_RESULT_FILE=$out SNAPSHELL_WIDGET_SESSION=$fish_pid snapshell "$request" >/dev/null </dev/tty
+    if test -s $out
+        commandline -r -- (string collect < $out)
+        commandline -f end-of-line
+    end
+    rm -f $out
+    commandline -f repaint
+end
+bind \es _snapshell_widget
+"#;
+
+pub fn command() -> Command {
+    Command::new("init")
+        .about("Print a shell widget (Alt-s) that replaces the command line with the generated command")
+        .after_help("Add to your shell startup file, e.g.:\n  eval \"$(snapshell init zsh)\"      # ~/.zshrc\n  eval \"$(snapshell init bash)\"     # ~/.bashrc\n  snapshell init fish | source       # ~/.config/fish/config.fish\n  eval \"$(snapshell init zsh -n 3)\" # Alt-s again cycles through 3 candidates")
+        .arg(
+            Arg::new("shell")
+                .index(1)
+                .required_unless_present("man")
+                .value_parser(["zsh", "bash", "fish"]),
+        )
+        .arg(
+            Arg::new("candidates")
+                .short('n')
+                .long("candidates")
+                .value_name("N")
+                .help("Ask for N alternative commands; pressing the key again on one replaces it with the next")
+                .value_parser(clap::value_parser!(u8).range(2..=9))
+                .conflicts_with("man"),
+        )
+        .arg(
+            Arg::new("man")
+                .long("man")
+                .help("Install man pages (snapshell(1) and one per mode) into DIR (default: ~/.local/share/man/man1)")
+                .value_name("DIR")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    if let Some(dir) = matches.get_one::<PathBuf>("man") {
+        let dir = if dir.as_os_str().is_empty() {
+            man_dir().ok_or_else(|| anyhow!("no home directory found; pass --man=DIR"))?
+        } else {
+            dir.clone()
+        };
+        return install_man(&dir);
+    }
+    let script = match matches.get_one::<String>("shell").map(|s| s.as_str()) {
+        Some("zsh") => ZSH,
+        Some("bash") => BASH,
+        _ => FISH,
+    };
+    // Every script calls `snapshell "$<buffer>"` exactly once.
+    match matches.get_one::<u8>("candidates") {
+        Some(n) => print!(
+            "{}",
+            script.replace("snapshell \"$", &format!("snapshell -n {} \"$", n))
+        ),
+        None => print!("{}", script),
+    }
+    Ok(())
+}
+
+// One page for the top-level command and one per mode, named like git's:
+// snapshell-git(1), snapshell-sql(1), ...
+fn install_man(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    // Building propagates the global flags (-m, -r, ...) into each mode's page
+    let mut cmd = crate::cli::build();
+    cmd.build();
+    let mut pages = vec![("snapshell".to_string(), clap_mangen::Man::new(cmd.clone()))];
+    for sub in cmd.get_subcommands() {
+        let name = format!("snapshell-{}", sub.get_name());
+        let sub = sub
+            .clone()
+            .bin_name(format!("snapshell {}", sub.get_name()));
+        pages.push((name.clone(), clap_mangen::Man::new(sub).title(name)));
+    }
+    for (name, page) in &pages {
+        let mut buf = Vec::new();
+        page.render(&mut buf)?;
+        let path = dir.join(format!("{}.1", name));
+        std::fs::write(&path, buf).with_context(|| format!("writing {}", path.display()))?;
+    }
+    println!("Installed {} man pages in {}.", pages.len(), dir.display());
+    if let Some(root) = dir.parent() {
+        println!(
+            "Try `man snapshell`; if it is not found, add {} to MANPATH.",
+            root.display()
+        );
+    }
+    Ok(())
+}